@@ -0,0 +1,189 @@
+use crate::clipboard::CopyFormat;
+use crate::keymap::Keymap;
+use crate::{Language, Theme, Translations};
+use serde::{Deserialize, Serialize};
+
+/// Acción de la aplicación. La barra de herramientas, los atajos de teclado y
+/// la paleta de comandos ejecutan todos las mismas acciones.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Action {
+    OpenFile,
+    OpenFolder,
+    Save,
+    SaveAs,
+    CloseWorkspace,
+    QuickOpen,
+    CommandPalette,
+    ToggleRawView,
+    ToggleSplitView,
+    ToggleEditMode,
+    ToggleOutline,
+    Find,
+    SearchWorkspace,
+    OpenSettings,
+    GoBack,
+    GoForward,
+    Reload,
+    NextChange,
+    PreviousChange,
+    ExportHtml,
+    ExportPdf,
+    ExportPng,
+    ExportVisiblePng,
+    ExportEpub,
+    CopyAs(CopyFormat),
+    CloseTab,
+    NextTab,
+    PreviousTab,
+    ToggleFullscreen,
+    ShowShortcuts,
+    ToggleVimNavigation,
+    SetTheme(Theme),
+    SetLanguage(Language),
+}
+
+impl Action {
+    /// Todas las acciones, en el orden en que se listan en la paleta.
+    pub const ALL: &'static [Action] = &[
+        Action::OpenFile,
+        Action::OpenFolder,
+        Action::Save,
+        Action::SaveAs,
+        Action::QuickOpen,
+        Action::CommandPalette,
+        Action::ToggleRawView,
+        Action::ToggleSplitView,
+        Action::ToggleEditMode,
+        Action::ToggleOutline,
+        Action::Find,
+        Action::SearchWorkspace,
+        Action::GoBack,
+        Action::GoForward,
+        Action::Reload,
+        Action::NextChange,
+        Action::PreviousChange,
+        Action::ExportHtml,
+        Action::ExportPdf,
+        Action::ExportPng,
+        Action::ExportVisiblePng,
+        Action::ExportEpub,
+        Action::CopyAs(CopyFormat::Html),
+        Action::CopyAs(CopyFormat::Markdown),
+        Action::CopyAs(CopyFormat::Text),
+        Action::CloseTab,
+        Action::NextTab,
+        Action::PreviousTab,
+        Action::ToggleFullscreen,
+        Action::ToggleVimNavigation,
+        Action::CloseWorkspace,
+        Action::SetTheme(Theme::Light),
+        Action::SetTheme(Theme::Dark),
+        Action::SetTheme(Theme::System),
+        Action::SetLanguage(Language::English),
+        Action::SetLanguage(Language::Spanish),
+        Action::SetLanguage(Language::System),
+        Action::OpenSettings,
+        Action::ShowShortcuts,
+    ];
+
+    pub fn icon(self) -> &'static str {
+        use egui_phosphor::regular;
+        match self {
+            Action::OpenFile => regular::FOLDER_OPEN,
+            Action::OpenFolder => regular::FOLDERS,
+            Action::Save => regular::FLOPPY_DISK,
+            Action::SaveAs => regular::FLOPPY_DISK_BACK,
+            Action::CloseWorkspace => regular::FOLDER_MINUS,
+            Action::QuickOpen => regular::FILE_MAGNIFYING_GLASS,
+            Action::CommandPalette => regular::TERMINAL_WINDOW,
+            Action::ToggleRawView => regular::CODE,
+            Action::ToggleSplitView => regular::COLUMNS,
+            Action::ToggleEditMode => regular::PENCIL_SIMPLE,
+            Action::ToggleOutline => regular::LIST_BULLETS,
+            Action::Find => regular::MAGNIFYING_GLASS,
+            Action::SearchWorkspace => regular::MAGNIFYING_GLASS,
+            Action::OpenSettings => regular::GEAR,
+            Action::GoBack => regular::ARROW_LEFT,
+            Action::GoForward => regular::ARROW_RIGHT,
+            Action::Reload => regular::ARROW_CLOCKWISE,
+            Action::NextChange => regular::ARROW_LINE_DOWN,
+            Action::PreviousChange => regular::ARROW_LINE_UP,
+            Action::ExportHtml => regular::EXPORT,
+            Action::ExportPdf => regular::FILE_PDF,
+            Action::ExportPng => regular::FILE_PNG,
+            Action::ExportVisiblePng => regular::FRAME_CORNERS,
+            Action::ExportEpub => regular::BOOK_OPEN,
+            Action::CopyAs(_) => regular::COPY,
+            Action::CloseTab => regular::X,
+            Action::NextTab => regular::CARET_RIGHT,
+            Action::PreviousTab => regular::CARET_LEFT,
+            Action::ToggleFullscreen => regular::CORNERS_OUT,
+            Action::ShowShortcuts => regular::KEYBOARD,
+            Action::ToggleVimNavigation => regular::ARROWS_DOWN_UP,
+            Action::SetTheme(Theme::Light) => regular::SUN,
+            Action::SetTheme(Theme::Dark) => regular::MOON,
+            Action::SetTheme(Theme::System) => regular::MONITOR,
+            Action::SetLanguage(_) => regular::TRANSLATE,
+        }
+    }
+
+    pub fn label(self, translations: &Translations) -> String {
+        match self {
+            Action::OpenFile => translations.open_file.to_string(),
+            Action::OpenFolder => translations.open_folder.to_string(),
+            Action::Save => translations.action_save.to_string(),
+            Action::SaveAs => translations.action_save_as.to_string(),
+            Action::CloseWorkspace => translations.close_workspace.to_string(),
+            Action::QuickOpen => translations.action_quick_open.to_string(),
+            Action::CommandPalette => translations.action_command_palette.to_string(),
+            Action::ToggleRawView => translations.action_toggle_raw.to_string(),
+            Action::ToggleSplitView => translations.action_toggle_split.to_string(),
+            Action::ToggleEditMode => translations.action_toggle_edit.to_string(),
+            Action::ToggleOutline => translations.toggle_outline.to_string(),
+            Action::Find => translations.action_find.to_string(),
+            Action::SearchWorkspace => translations.workspace_search.to_string(),
+            Action::OpenSettings => translations.settings.to_string(),
+            Action::GoBack => translations.go_back.to_string(),
+            Action::GoForward => translations.go_forward.to_string(),
+            Action::Reload => translations.action_reload.to_string(),
+            Action::NextChange => translations.action_next_change.to_string(),
+            Action::PreviousChange => translations.action_previous_change.to_string(),
+            Action::ExportHtml => translations.action_export_html.to_string(),
+            Action::ExportPdf => translations.action_export_pdf.to_string(),
+            Action::ExportPng => translations.action_export_png.to_string(),
+            Action::ExportVisiblePng => translations.action_export_visible_png.to_string(),
+            Action::ExportEpub => translations.action_export_epub.to_string(),
+            Action::CopyAs(format) => format!("{} {}", translations.action_copy_as, format.name(translations)),
+            Action::CloseTab => translations.action_close_tab.to_string(),
+            Action::NextTab => translations.action_next_tab.to_string(),
+            Action::PreviousTab => translations.action_previous_tab.to_string(),
+            Action::ToggleFullscreen => translations.action_toggle_fullscreen.to_string(),
+            Action::ShowShortcuts => translations.action_show_shortcuts.to_string(),
+            Action::ToggleVimNavigation => translations.action_toggle_vim_navigation.to_string(),
+            Action::SetTheme(theme) => {
+                let name = match theme {
+                    Theme::Light => translations.theme_light,
+                    Theme::Dark => translations.theme_dark,
+                    Theme::System => translations.theme_system,
+                };
+                format!("{}: {}", translations.theme_section, name)
+            }
+            Action::SetLanguage(language) => {
+                let name = match language {
+                    Language::English => translations.language_english,
+                    Language::Spanish => translations.language_spanish,
+                    Language::System => translations.language_system,
+                };
+                format!("{}: {}", translations.language_section, name)
+            }
+        }
+    }
+
+    /// Texto del tooltip: nombre de la acción y su atajo, si tiene.
+    pub fn tooltip(self, translations: &Translations, keymap: &Keymap) -> String {
+        match keymap.shortcut(self) {
+            Some(shortcut) => format!("{} ({})", self.label(translations), shortcut),
+            None => self.label(translations),
+        }
+    }
+}
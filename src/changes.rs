@@ -0,0 +1,196 @@
+use crate::document::Document;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+// Tiempo en que las marcas de cambios se desvanecen después de mostrarse
+const FADE_DURATION: Duration = Duration::from_secs(8);
+
+// Celdas de la tabla de comparación (4 bytes cada una) a partir de las que
+// el tramo distinto se marca entero como un solo cambio
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Una zona del documento que cambió al recargarlo: bloques consecutivos
+/// modificados o agregados y la cantidad de bloques quitados en ese lugar.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Change {
+    /// Bloques del documento nuevo que cambiaron. Vacío si solo se quitaron bloques.
+    pub blocks: Range<usize>,
+    /// Cuántos de esos bloques (los primeros) reemplazan a bloques anteriores;
+    /// el resto son nuevos.
+    pub modified: usize,
+    /// Bloques anteriores quitados sin reemplazo, justo antes de `blocks.end`.
+    pub deleted: usize,
+}
+
+impl Change {
+    pub fn inserted(&self) -> Range<usize> {
+        self.blocks.start + self.modified..self.blocks.end
+    }
+
+    pub fn modified(&self) -> Range<usize> {
+        self.blocks.start..self.blocks.start + self.modified
+    }
+}
+
+/// Diferencias por bloque entre un documento y su versión anterior.
+pub struct DocumentChanges {
+    pub changes: Vec<Change>,
+    /// Cambio elegido con la navegación "siguiente/anterior cambio".
+    pub current: Option<usize>,
+    // Momento en que las marcas se mostraron por primera vez
+    shown_at: Option<Instant>,
+}
+
+impl DocumentChanges {
+    /// Compara los bloques de dos versiones de un documento por su texto
+    /// fuente. Devuelve `None` si no cambió ningún bloque.
+    pub fn compute(old_source: &str, old: &Document, new_source: &str, new: &Document) -> Option<Self> {
+        let old_blocks: Vec<&str> = old.blocks.iter().map(|block| old_source[block.range.clone()].trim_end()).collect();
+        let new_blocks: Vec<&str> = new.blocks.iter().map(|block| new_source[block.range.clone()].trim_end()).collect();
+        let changes = diff_blocks(&old_blocks, &new_blocks);
+        if changes.is_empty() {
+            return None;
+        }
+        Some(Self {
+            changes,
+            current: None,
+            shown_at: None,
+        })
+    }
+
+    /// Opacidad de las marcas, de 1 a 0. El tiempo empieza a correr la
+    /// primera vez que se dibujan, no al recargar una pestaña en segundo plano.
+    pub fn opacity(&mut self) -> f32 {
+        let shown_at = *self.shown_at.get_or_insert_with(Instant::now);
+        let elapsed = shown_at.elapsed().as_secs_f32() / FADE_DURATION.as_secs_f32();
+        (1.0 - elapsed).clamp(0.0, 1.0)
+    }
+
+    /// Elige el cambio siguiente o anterior y vuelve a mostrar las marcas.
+    /// Devuelve el bloque al que hay que desplazarse.
+    pub fn step(&mut self, forward: bool, block_count: usize) -> Option<usize> {
+        let count = self.changes.len();
+        let index = match (self.current, forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(current), true) => (current + 1) % count,
+            (Some(current), false) => (current + count - 1) % count,
+        };
+        self.current = Some(index);
+        self.shown_at = None;
+        // Un cambio que solo quita bloques se muestra con el bloque siguiente
+        let block = self.changes[index].blocks.start;
+        (block_count > 0).then(|| block.min(block_count - 1))
+    }
+}
+
+/// Diferencia entre dos listas de bloques por subsecuencia común más larga.
+/// Cada tramo sin coincidencias se convierte en un `Change`.
+fn diff_blocks(old: &[&str], new: &[&str]) -> Vec<Change> {
+    // Los extremos comunes se descartan antes: suele quedar un tramo chico
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let (n, m) = (old_middle.len(), new_middle.len());
+    if n.saturating_mul(m) > MAX_DIFF_CELLS {
+        return vec![Change {
+            blocks: prefix..prefix + m,
+            modified: n.min(m),
+            deleted: n.saturating_sub(m),
+        }];
+    }
+
+    // lengths[i][j]: largo de la subsecuencia común de old_middle[i..] y new_middle[j..]
+    let mut lengths = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if old_middle[i] == new_middle[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let (mut removed, mut added_start) = (0, 0);
+    loop {
+        let matched = i < n && j < m && old_middle[i] == new_middle[j];
+        if matched || (i == n && j == m) {
+            // Fin de un tramo sin coincidencias
+            if removed > 0 || added_start < j {
+                let added = j - added_start;
+                changes.push(Change {
+                    blocks: prefix + added_start..prefix + j,
+                    modified: added.min(removed),
+                    deleted: removed.saturating_sub(added),
+                });
+            }
+            if !matched {
+                break;
+            }
+            i += 1;
+            j += 1;
+            removed = 0;
+            added_start = j;
+        } else if j < m && (i == n || lengths[i][j + 1] >= lengths[i + 1][j]) {
+            j += 1;
+        } else {
+            i += 1;
+            removed += 1;
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(blocks: Range<usize>, modified: usize, deleted: usize) -> Change {
+        Change { blocks, modified, deleted }
+    }
+
+    #[test]
+    fn identical_documents_have_no_changes() {
+        assert_eq!(diff_blocks(&["a", "b", "c"], &["a", "b", "c"]), Vec::new());
+    }
+
+    #[test]
+    fn modified_block() {
+        assert_eq!(diff_blocks(&["a", "b", "c"], &["a", "x", "c"]), vec![change(1..2, 1, 0)]);
+    }
+
+    #[test]
+    fn inserted_and_deleted_blocks() {
+        assert_eq!(diff_blocks(&["a", "c"], &["a", "b", "c"]), vec![change(1..2, 0, 0)]);
+        assert_eq!(diff_blocks(&["a", "b", "c"], &["a", "c"]), vec![change(1..1, 0, 1)]);
+        assert_eq!(diff_blocks(&["a", "b"], &[]), vec![change(0..0, 0, 2)]);
+    }
+
+    #[test]
+    fn separate_changes() {
+        let old = ["a", "b", "c", "d", "e"];
+        let new = ["x", "b", "c", "d", "e", "f"];
+        assert_eq!(diff_blocks(&old, &new), vec![change(0..1, 1, 0), change(5..6, 0, 0)]);
+    }
+
+    #[test]
+    fn large_rewrite_is_a_single_change() {
+        let old: Vec<String> = (0..2500).map(|index| format!("old {index}")).collect();
+        let mut new: Vec<String> = (0..2100).map(|index| format!("new {index}")).collect();
+        new.insert(0, old[0].clone());
+        new.push(old[2499].clone());
+        let old: Vec<&str> = old.iter().map(String::as_str).collect();
+        let new: Vec<&str> = new.iter().map(String::as_str).collect();
+        assert_eq!(diff_blocks(&old, &new), vec![change(1..2101, 2100, 398)]);
+    }
+}
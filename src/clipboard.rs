@@ -0,0 +1,138 @@
+use crate::document::Document;
+use crate::export;
+use crate::text;
+use crate::Translations;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Formato en el que se copia una parte del documento.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum CopyFormat {
+    /// HTML con formato, más el texto plano para donde no se puede pegar HTML.
+    Html,
+    /// El código fuente tal cual.
+    Markdown,
+    Text,
+}
+
+impl CopyFormat {
+    pub const ALL: [CopyFormat; 3] = [CopyFormat::Html, CopyFormat::Markdown, CopyFormat::Text];
+
+    pub fn name(self, translations: &Translations) -> &'static str {
+        match self {
+            CopyFormat::Html => "HTML",
+            CopyFormat::Markdown => "Markdown",
+            CopyFormat::Text => translations.copy_format_text,
+        }
+    }
+}
+
+/// Portapapeles del sistema. Se crea al copiar por primera vez y se
+/// conserva abierto: en Linux, lo copiado se ofrece a las otras aplicaciones
+/// mientras exista.
+#[derive(Default)]
+pub struct Clipboard {
+    inner: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    /// Copia un fragmento de Markdown en el formato pedido. Las imágenes
+    /// relativas del HTML se resuelven desde `base_dir`.
+    pub fn copy(&mut self, source: &str, base_dir: &Path, format: CopyFormat) -> Result<(), arboard::Error> {
+        let clipboard = match &mut self.inner {
+            Some(clipboard) => clipboard,
+            inner => inner.insert(arboard::Clipboard::new()?),
+        };
+        let contents = contents(source, base_dir, format);
+        match contents.html {
+            Some(html) => clipboard.set_html(html, Some(contents.text)),
+            None => clipboard.set_text(contents.text),
+        }
+    }
+}
+
+/// Lo que se deja en el portapapeles: el HTML, si el formato lo lleva, y el
+/// texto para las aplicaciones que no lo aceptan.
+#[derive(PartialEq, Debug)]
+pub struct ClipboardContents {
+    pub html: Option<String>,
+    pub text: String,
+}
+
+pub fn contents(source: &str, base_dir: &Path, format: CopyFormat) -> ClipboardContents {
+    match format {
+        CopyFormat::Html => ClipboardContents {
+            html: Some(export::body_html(source, base_dir)),
+            text: plain_text(source),
+        },
+        CopyFormat::Markdown => ClipboardContents {
+            html: None,
+            text: source.to_string(),
+        },
+        CopyFormat::Text => ClipboardContents {
+            html: None,
+            text: plain_text(source),
+        },
+    }
+}
+
+fn plain_text(source: &str) -> String {
+    text::unwrapped_text(&Document::parse(source)).trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tab::{DocumentTab, ViewMode};
+
+    const SOURCE: &str = "Intro.\n\n# Uno\n\nTexto con **negrita**.\n\n## Uno.a\n\n- [enlace](https://example.com)\n\n# Dos\n\nFin.\n";
+
+    fn tab() -> DocumentTab {
+        let mut tab = DocumentTab::new(0, ViewMode::Rendered);
+        tab.markdown_content = SOURCE.to_string();
+        tab.document = Document::parse(SOURCE);
+        tab
+    }
+
+    fn section(heading: usize) -> String {
+        let tab = tab();
+        let range = tab.section_range(tab.document.headings[heading].block);
+        SOURCE[range].to_string()
+    }
+
+    #[test]
+    fn section_runs_until_the_next_heading_of_its_level() {
+        assert_eq!(section(0), "# Uno\n\nTexto con **negrita**.\n\n## Uno.a\n\n- [enlace](https://example.com)\n\n");
+        assert_eq!(section(1), "## Uno.a\n\n- [enlace](https://example.com)\n\n");
+        assert_eq!(section(2), "# Dos\n\nFin.\n");
+    }
+
+    #[test]
+    fn section_as_html_markdown_and_text() {
+        let source = section(1);
+        let html = contents(&source, Path::new("."), CopyFormat::Html);
+        assert_eq!(
+            html.html.as_deref(),
+            Some("<h2 id=\"unoa\"><a class=\"anchor\" href=\"#unoa\" aria-hidden=\"true\">#</a>Uno.a</h2>\n<ul>\n<li><a href=\"https://example.com\">enlace</a></li>\n</ul>\n")
+        );
+        assert_eq!(html.text, "Uno.a\n-----\n\n• enlace (https://example.com)");
+
+        let markdown = contents(&source, Path::new("."), CopyFormat::Markdown);
+        assert_eq!(markdown, ClipboardContents { html: None, text: source.clone() });
+
+        let text = contents(&source, Path::new("."), CopyFormat::Text);
+        assert_eq!(text, ClipboardContents { html: None, text: html.text });
+    }
+
+    #[test]
+    fn selection_inside_a_paragraph() {
+        let start = SOURCE.find("con").unwrap();
+        let selection = &SOURCE[start..start + "con **negrita**".len()];
+
+        let html = contents(selection, Path::new("."), CopyFormat::Html);
+        assert_eq!(html.html.as_deref(), Some("<p>con <strong>negrita</strong></p>\n"));
+        assert_eq!(html.text, "con negrita");
+        assert_eq!(contents(selection, Path::new("."), CopyFormat::Markdown).text, selection);
+        assert_eq!(contents(selection, Path::new("."), CopyFormat::Text).text, "con negrita");
+    }
+}
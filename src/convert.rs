@@ -0,0 +1,155 @@
+use crate::document::Document;
+use crate::encoding;
+use crate::export::{self, ExportTheme};
+use crate::pdf::{self, PageSize, PdfOptions};
+use crate::snapshot::{self, Area, PngOptions};
+use crate::text;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Formatos a los que se puede convertir un documento sin abrir la ventana.
+#[derive(Clone, Copy, PartialEq, Debug, clap::ValueEnum)]
+pub enum Format {
+    Html,
+    Pdf,
+    Png,
+    Txt,
+}
+
+impl Format {
+    /// Formato que corresponde a la extensión de un archivo de salida.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            "html" | "htm" => Some(Format::Html),
+            "pdf" => Some(Format::Pdf),
+            "png" => Some(Format::Png),
+            "txt" | "text" => Some(Format::Txt),
+            _ => None,
+        }
+    }
+}
+
+/// Opciones de la conversión: las mismas que ofrece la ventana al exportar.
+#[derive(clap::Args, Clone, Copy, Debug)]
+pub struct ConvertOptions {
+    /// Color theme of exported HTML files and PNG images
+    #[arg(long, value_enum, default_value_t = ExportTheme::Light)]
+    pub theme: ExportTheme,
+    /// Page size of exported PDF files
+    #[arg(long, value_enum, default_value_t = PageSize::A4)]
+    pub page_size: PageSize,
+    /// Page margins of exported PDF files, in millimeters
+    #[arg(long, value_name = "MM", default_value_t = 20.0)]
+    pub margin: f32,
+    /// Line width of plain text output
+    #[arg(long, value_name = "COLUMNS", default_value_t = 80)]
+    pub width: usize,
+    /// Width of PNG images, in points
+    #[arg(long, value_name = "POINTS", default_value_t = 800.0)]
+    pub image_width: f32,
+    /// Pixels per point of PNG images
+    #[arg(long, value_name = "FACTOR", default_value_t = 2.0)]
+    pub scale: f32,
+}
+
+impl ConvertOptions {
+    fn pdf(&self) -> PdfOptions {
+        PdfOptions {
+            page_size: self.page_size,
+            margin: self.margin,
+        }
+    }
+
+    fn png(&self) -> PngOptions {
+        PngOptions {
+            width: self.image_width,
+            scale: self.scale,
+        }
+    }
+}
+
+/// Documento leído desde un archivo o desde la entrada estándar.
+pub struct Input {
+    pub source: String,
+    /// Nombre sin extensión, para el título del documento exportado.
+    pub title: String,
+    /// Carpeta desde la que se resuelven las imágenes relativas.
+    pub base_dir: PathBuf,
+}
+
+impl Input {
+    /// Lee `path`, o la entrada estándar si es `-`.
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let (bytes, title, base_dir) = if path.as_os_str() == "-" {
+            let mut bytes = Vec::new();
+            io::stdin().read_to_end(&mut bytes)?;
+            (bytes, "stdin".to_string(), PathBuf::new())
+        } else {
+            let title = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+            let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            (fs::read(path)?, title, base_dir)
+        };
+        Ok(Self {
+            source: encoding::decode(&bytes).text,
+            title,
+            base_dir,
+        })
+    }
+}
+
+/// Convierte el documento con el mismo código que usa la ventana para exportarlo.
+pub fn convert(input: &Input, format: Format, options: &ConvertOptions) -> io::Result<Vec<u8>> {
+    match format {
+        Format::Html => Ok(export::standalone_html(&input.source, &input.title, &input.base_dir, options.theme).into_bytes()),
+        Format::Pdf => pdf::export_pdf(&Document::parse(&input.source), &input.title, &options.pdf()),
+        Format::Png => snapshot::export_png(&Document::parse(&input.source), options.theme, Area::Document, &options.png()),
+        Format::Txt => Ok(text::plain_text(&Document::parse(&input.source), options.width).into_bytes()),
+    }
+}
+
+/// `mdview convert`: escribe el resultado en `output`. Sin `format`, se
+/// deduce de la extensión de la salida.
+pub fn convert_file(input: &Path, output: &Path, format: Option<Format>, options: &ConvertOptions) -> io::Result<()> {
+    let Some(format) = format.or_else(|| Format::from_path(output)) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("cannot tell the format of {}; use --to html, pdf, png or txt", output.display()),
+        ));
+    };
+    let bytes = convert(&Input::read(input)?, format, options)?;
+    fs::write(output, bytes)
+}
+
+/// `mdview render`: escribe el resultado en la salida estándar.
+pub fn render(input: &Path, format: Format, options: &ConvertOptions) -> io::Result<()> {
+    let bytes = convert(&Input::read(input)?, format, options)?;
+    match io::Write::write_all(&mut io::stdout().lock(), &bytes) {
+        // Quien leía la salida (por ejemplo `head`) ya no la quiere
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_from_the_output_extension() {
+        assert_eq!(Format::from_path(Path::new("out/doc.html")), Some(Format::Html));
+        assert_eq!(Format::from_path(Path::new("doc.HTM")), Some(Format::Html));
+        assert_eq!(Format::from_path(Path::new("doc.Pdf")), Some(Format::Pdf));
+        assert_eq!(Format::from_path(Path::new("doc.png")), Some(Format::Png));
+        assert_eq!(Format::from_path(Path::new("doc.txt")), Some(Format::Txt));
+        assert_eq!(Format::from_path(Path::new("doc.text")), Some(Format::Txt));
+    }
+
+    #[test]
+    fn unknown_or_missing_extension() {
+        assert_eq!(Format::from_path(Path::new("doc.md")), None);
+        assert_eq!(Format::from_path(Path::new("doc")), None);
+        assert_eq!(Format::from_path(Path::new(".html")), None);
+    }
+}
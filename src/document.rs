@@ -0,0 +1,399 @@
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag};
+use std::ops::Range;
+
+/// Extensiones de Markdown habilitadas en toda la aplicación.
+pub fn markdown_options() -> Options {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_SMART_PUNCTUATION);
+    options
+}
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct SpanStyle {
+    pub strong: bool,
+    pub emphasis: bool,
+    pub strikethrough: bool,
+    pub code: bool,
+    pub link: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Span {
+    pub text: String,
+    pub style: SpanStyle,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ListMarker {
+    Bullet,
+    Ordered(u64),
+    Task(bool),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum BlockKind {
+    Heading { level: HeadingLevel, spans: Vec<Span> },
+    Paragraph { spans: Vec<Span> },
+    /// Un ítem de lista. `marker` es `None` para los párrafos que continúan un ítem.
+    ListItem { marker: Option<ListMarker>, spans: Vec<Span> },
+    CodeBlock { lang: String, code: String },
+    /// La primera fila es la cabecera.
+    Table { alignments: Vec<Alignment>, rows: Vec<Vec<Vec<Span>>> },
+    Rule,
+}
+
+/// Bloque hoja del documento con su rango en el código fuente.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Block {
+    pub kind: BlockKind,
+    pub range: Range<usize>,
+    /// Nivel de anidamiento dentro de listas.
+    pub indent: usize,
+    /// Nivel de anidamiento dentro de citas (`>`).
+    pub quote_depth: usize,
+}
+
+impl Block {
+    /// Textos del bloque tal como se muestran en la vista renderizada: uno por
+    /// bloque, salvo las tablas, que tienen uno por celda.
+    pub fn text_units(&self) -> Vec<String> {
+        match &self.kind {
+            BlockKind::Heading { spans, .. }
+            | BlockKind::Paragraph { spans }
+            | BlockKind::ListItem { spans, .. } => vec![spans_text(spans)],
+            BlockKind::CodeBlock { code, .. } => vec![code.trim_end_matches('\n').to_string()],
+            BlockKind::Table { rows, .. } => rows.iter().flatten().map(|cell| spans_text(cell)).collect(),
+            BlockKind::Rule => Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+    /// Identificador estilo GitHub (`#mi-titulo`), único dentro del documento.
+    pub anchor: String,
+    /// Índice del bloque del encabezado en `Document::blocks`.
+    pub block: usize,
+}
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Document {
+    pub blocks: Vec<Block>,
+    pub headings: Vec<Heading>,
+}
+
+pub fn spans_text(spans: &[Span]) -> String {
+    spans.iter().map(|span| span.text.as_str()).collect()
+}
+
+pub fn heading_level_number(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+/// Convierte el texto de un encabezado en un ancla: minúsculas, espacios como
+/// guiones y sin puntuación.
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.trim().chars() {
+        if c.is_alphanumeric() || c == '-' || c == '_' {
+            slug.extend(c.to_lowercase());
+        } else if c.is_whitespace() {
+            slug.push('-');
+        }
+    }
+    slug
+}
+
+struct ListState {
+    next_number: Option<u64>,
+}
+
+#[derive(Default)]
+struct Builder {
+    blocks: Vec<Block>,
+    spans: Vec<Span>,
+    style: SpanStyle,
+    quote_depth: usize,
+    lists: Vec<ListState>,
+    // Ítem abierto cuyo texto todavía no se emitió
+    pending_item: Option<(Option<ListMarker>, usize)>,
+    heading: Option<(HeadingLevel, usize)>,
+    paragraph_start: Option<usize>,
+    code_block: Option<(String, usize)>,
+    code: String,
+    table: Option<(Vec<Alignment>, usize)>,
+    table_rows: Vec<Vec<Vec<Span>>>,
+    table_row: Vec<Vec<Span>>,
+    footnote_label: Option<String>,
+}
+
+impl Builder {
+    fn indent(&self) -> usize {
+        self.lists.len().saturating_sub(1)
+    }
+
+    fn push_block(&mut self, kind: BlockKind, range: Range<usize>) {
+        self.blocks.push(Block {
+            kind,
+            range,
+            indent: self.indent(),
+            quote_depth: self.quote_depth,
+        });
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if let Some(last) = self.spans.last_mut() {
+            if last.style == self.style {
+                last.text.push_str(text);
+                return;
+            }
+        }
+        self.spans.push(Span {
+            text: text.to_string(),
+            style: self.style.clone(),
+        });
+    }
+
+    // Emite el texto de un ítem de lista "tight" (sin párrafos) antes de que
+    // empiece otro bloque dentro del mismo ítem.
+    fn flush_item(&mut self, end: usize) {
+        if let Some((marker, start)) = self.pending_item.take() {
+            let spans = std::mem::take(&mut self.spans);
+            if !spans.is_empty() || marker.is_some() {
+                self.push_block(BlockKind::ListItem { marker, spans }, start..end);
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag, range: Range<usize>) {
+        let is_block = matches!(
+            tag,
+            Tag::Paragraph
+                | Tag::Heading(..)
+                | Tag::BlockQuote
+                | Tag::CodeBlock(_)
+                | Tag::List(_)
+                | Tag::Table(_)
+                | Tag::FootnoteDefinition(_)
+        );
+        if is_block && !matches!(tag, Tag::Paragraph) {
+            self.flush_item(range.start);
+        }
+
+        match tag {
+            Tag::Paragraph => {
+                if self.pending_item.is_some() && !self.spans.is_empty() {
+                    self.flush_item(range.start);
+                }
+                self.paragraph_start = Some(range.start);
+                if let Some(label) = self.footnote_label.take() {
+                    self.push_text(&format!("[{}] ", label));
+                }
+            }
+            Tag::Heading(level, _, _) => {
+                self.spans.clear();
+                self.heading = Some((level, range.start));
+            }
+            Tag::BlockQuote => self.quote_depth += 1,
+            Tag::CodeBlock(kind) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(lang) => lang.to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code.clear();
+                self.code_block = Some((lang, range.start));
+            }
+            Tag::List(first) => self.lists.push(ListState { next_number: first }),
+            Tag::Item => {
+                let marker = match self.lists.last_mut() {
+                    Some(ListState { next_number: Some(n) }) => {
+                        let marker = ListMarker::Ordered(*n);
+                        *n += 1;
+                        marker
+                    }
+                    _ => ListMarker::Bullet,
+                };
+                self.spans.clear();
+                self.pending_item = Some((Some(marker), range.start));
+            }
+            Tag::FootnoteDefinition(label) => self.footnote_label = Some(label.to_string()),
+            Tag::Table(alignments) => {
+                self.table_rows.clear();
+                self.table = Some((alignments, range.start));
+            }
+            Tag::TableHead | Tag::TableRow => self.table_row.clear(),
+            Tag::TableCell => self.spans.clear(),
+            Tag::Emphasis => self.style.emphasis = true,
+            Tag::Strong => self.style.strong = true,
+            Tag::Strikethrough => self.style.strikethrough = true,
+            Tag::Link(_, dest, _) => self.style.link = Some(dest.to_string()),
+            Tag::Image(..) => {}
+        }
+    }
+
+    fn end(&mut self, tag: Tag, range: Range<usize>) {
+        match tag {
+            Tag::Paragraph => {
+                let start = self.paragraph_start.take().unwrap_or(range.start);
+                let spans = std::mem::take(&mut self.spans);
+                if let Some((marker, item_start)) = self.pending_item.take() {
+                    // Primer párrafo de un ítem "loose": lleva la viñeta
+                    let start = if marker.is_some() { item_start } else { start };
+                    self.push_block(BlockKind::ListItem { marker, spans }, start..range.end);
+                    self.pending_item = Some((None, range.end));
+                } else if !self.lists.is_empty() {
+                    self.push_block(BlockKind::ListItem { marker: None, spans }, start..range.end);
+                } else {
+                    self.push_block(BlockKind::Paragraph { spans }, start..range.end);
+                }
+            }
+            Tag::Heading(..) => {
+                if let Some((level, start)) = self.heading.take() {
+                    let spans = std::mem::take(&mut self.spans);
+                    self.push_block(BlockKind::Heading { level, spans }, start..range.end);
+                }
+            }
+            Tag::BlockQuote => self.quote_depth = self.quote_depth.saturating_sub(1),
+            Tag::CodeBlock(_) => {
+                if let Some((lang, start)) = self.code_block.take() {
+                    let code = std::mem::take(&mut self.code);
+                    self.push_block(BlockKind::CodeBlock { lang, code }, start..range.end);
+                }
+            }
+            Tag::List(_) => {
+                self.lists.pop();
+            }
+            Tag::Item => {
+                let pending_has_text = !self.spans.is_empty()
+                    || matches!(self.pending_item, Some((Some(_), _)));
+                if pending_has_text {
+                    self.flush_item(range.end);
+                } else {
+                    self.pending_item = None;
+                }
+            }
+            Tag::TableCell => {
+                let cell = std::mem::take(&mut self.spans);
+                self.table_row.push(cell);
+            }
+            Tag::TableHead | Tag::TableRow => {
+                let row = std::mem::take(&mut self.table_row);
+                self.table_rows.push(row);
+            }
+            Tag::Table(_) => {
+                if let Some((alignments, start)) = self.table.take() {
+                    let rows = std::mem::take(&mut self.table_rows);
+                    self.push_block(BlockKind::Table { alignments, rows }, start..range.end);
+                }
+            }
+            Tag::Emphasis => self.style.emphasis = false,
+            Tag::Strong => self.style.strong = false,
+            Tag::Strikethrough => self.style.strikethrough = false,
+            Tag::Link(..) => self.style.link = None,
+            Tag::FootnoteDefinition(_) | Tag::Image(..) => {}
+        }
+    }
+}
+
+impl Document {
+    pub fn parse(source: &str) -> Self {
+        let parser = Parser::new_ext(source, markdown_options());
+        let mut builder = Builder::default();
+
+        for (event, range) in parser.into_offset_iter() {
+            match event {
+                Event::Start(tag) => builder.start(tag, range),
+                Event::End(tag) => builder.end(tag, range),
+                Event::Text(text) => {
+                    if builder.code_block.is_some() {
+                        builder.code.push_str(&text);
+                    } else {
+                        builder.push_text(&text);
+                    }
+                }
+                Event::Code(code) => {
+                    let previous = builder.style.clone();
+                    builder.style.code = true;
+                    builder.push_text(&code);
+                    builder.style = previous;
+                }
+                Event::SoftBreak => builder.push_text(" "),
+                Event::HardBreak => builder.push_text("\n"),
+                Event::FootnoteReference(label) => builder.push_text(&format!("[{}]", label)),
+                Event::TaskListMarker(checked) => {
+                    if let Some((marker, _)) = builder.pending_item.as_mut() {
+                        *marker = Some(ListMarker::Task(checked));
+                    }
+                }
+                Event::Rule => {
+                    builder.flush_item(range.start);
+                    builder.push_block(BlockKind::Rule, range);
+                }
+                Event::Html(_) => {}
+            }
+        }
+
+        let blocks = builder.blocks;
+        let mut headings: Vec<Heading> = Vec::new();
+        for (index, block) in blocks.iter().enumerate() {
+            if let BlockKind::Heading { level, spans } = &block.kind {
+                let text = spans_text(spans);
+                let base = slugify(&text);
+                let mut anchor = base.clone();
+                let mut suffix = 1;
+                while headings.iter().any(|h| h.anchor == anchor) {
+                    anchor = format!("{}-{}", base, suffix);
+                    suffix += 1;
+                }
+                headings.push(Heading {
+                    level: heading_level_number(*level),
+                    text,
+                    anchor,
+                    block: index,
+                });
+            }
+        }
+
+        Self { blocks, headings }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anchors(source: &str) -> Vec<String> {
+        Document::parse(source).headings.into_iter().map(|heading| heading.anchor).collect()
+    }
+
+    #[test]
+    fn slugs() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  API v2.0 "), "api-v20");
+        assert_eq!(slugify("Año_nuevo - fin"), "año_nuevo---fin");
+    }
+
+    #[test]
+    fn anchors_from_heading_text() {
+        assert_eq!(anchors("# Getting *started*\n\n## `cargo run`\n"), ["getting-started", "cargo-run"]);
+    }
+
+    #[test]
+    fn repeated_headings_get_numbered_anchors() {
+        let source = "# Notes\n\n## Notes\n\n### Notes\n\n# Notes 1\n";
+        assert_eq!(anchors(source), ["notes", "notes-1", "notes-2", "notes-1-1"]);
+    }
+}
@@ -0,0 +1,207 @@
+use encoding_rs::Encoding;
+
+/// Codificación con la que se leyó un archivo, incluyendo si traía BOM.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TextEncoding {
+    pub encoding: &'static Encoding,
+    pub bom: bool,
+}
+
+impl TextEncoding {
+    pub fn name(&self) -> &'static str {
+        self.encoding.name()
+    }
+
+    pub fn label(&self) -> String {
+        if self.bom {
+            format!("{} BOM", self.name())
+        } else {
+            self.name().to_string()
+        }
+    }
+}
+
+impl Default for TextEncoding {
+    fn default() -> Self {
+        Self {
+            encoding: encoding_rs::UTF_8,
+            bom: false,
+        }
+    }
+}
+
+pub struct DecodedText {
+    pub text: String,
+    pub encoding: TextEncoding,
+}
+
+/// Codificaciones ofrecidas en el menú "reabrir con codificación".
+pub const SELECTABLE_ENCODINGS: &[&Encoding] = &[
+    encoding_rs::UTF_8,
+    encoding_rs::UTF_16LE,
+    encoding_rs::UTF_16BE,
+    encoding_rs::WINDOWS_1252,
+    encoding_rs::ISO_8859_15,
+    encoding_rs::ISO_8859_2,
+    encoding_rs::WINDOWS_1250,
+    encoding_rs::WINDOWS_1251,
+    encoding_rs::KOI8_R,
+    encoding_rs::SHIFT_JIS,
+    encoding_rs::EUC_JP,
+    encoding_rs::GBK,
+    encoding_rs::BIG5,
+    encoding_rs::EUC_KR,
+];
+
+/// Detecta la codificación (BOM, UTF-8 válido, UTF-16 sin BOM o heurística)
+/// y convierte el contenido a UTF-8.
+pub fn decode(bytes: &[u8]) -> DecodedText {
+    decode_with(bytes, detect(bytes, true))
+}
+
+/// Detecta la codificación de un texto. Con `last` en `false` los bytes son
+/// el principio de un texto que sigue, que puede cortar un carácter al final.
+pub fn detect(bytes: &[u8], last: bool) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+
+    // UTF-16 con texto ASCII también es UTF-8 válido (bytes en cero), así que va primero.
+    // Un texto que sigue puede estar cortado a mitad de un carácter
+    let whole_units = if last { bytes } else { &bytes[..bytes.len() & !1] };
+    if let Some(encoding) = sniff_utf16(whole_units) {
+        return encoding;
+    }
+
+    // Un error sin largo es una secuencia incompleta al final
+    match std::str::from_utf8(bytes) {
+        Ok(_) => return encoding_rs::UTF_8,
+        Err(e) if !last && e.error_len().is_none() => return encoding_rs::UTF_8,
+        Err(_) => {}
+    }
+
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, last);
+    detector.guess(None, true)
+}
+
+/// Decodifica forzando una codificación. Si el archivo trae BOM se respeta
+/// el BOM, igual que hacen los navegadores.
+pub fn decode_with(bytes: &[u8], encoding: &'static Encoding) -> DecodedText {
+    let (text, used, _had_errors) = encoding.decode(bytes);
+    let bom = Encoding::for_bom(bytes).is_some_and(|(bom_encoding, _)| bom_encoding == used);
+    DecodedText {
+        text: text.into_owned(),
+        encoding: TextEncoding { encoding: used, bom },
+    }
+}
+
+/// Convierte el texto a la codificación con la que se leyó el archivo,
+/// agregando el BOM si lo tenía. Los caracteres que la codificación no puede
+/// representar quedan como referencias numéricas (`&#1234;`), que el
+/// Markdown muestra igual.
+pub fn encode(text: &str, encoding: TextEncoding) -> Vec<u8> {
+    // encoding_rs no codifica UTF-16 (el estándar de codificación solo lo
+    // decodifica), así que se arma a mano
+    let mut bytes = Vec::new();
+    if encoding.encoding == encoding_rs::UTF_16LE || encoding.encoding == encoding_rs::UTF_16BE {
+        let little_endian = encoding.encoding == encoding_rs::UTF_16LE;
+        let units = encoding.bom.then_some(0xFEFF).into_iter().chain(text.encode_utf16());
+        for unit in units {
+            if little_endian {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            } else {
+                bytes.extend_from_slice(&unit.to_be_bytes());
+            }
+        }
+        return bytes;
+    }
+
+    if encoding.bom && encoding.encoding == encoding_rs::UTF_8 {
+        bytes.extend_from_slice(b"\xEF\xBB\xBF");
+    }
+    let (encoded, _, _had_errors) = encoding.encoding.encode(text);
+    bytes.extend_from_slice(&encoded);
+    bytes
+}
+
+// Los archivos UTF-16 sin BOM tienen casi todos los bytes altos (o bajos) en cero
+// cuando el texto es mayormente ASCII.
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    if bytes.len() < 4 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+
+    let sample = &bytes[..bytes.len().min(4096)];
+    let pairs = sample.len() / 2;
+    let even_zeros = sample.iter().step_by(2).filter(|b| **b == 0).count();
+    let odd_zeros = sample.iter().skip(1).step_by(2).filter(|b| **b == 0).count();
+
+    if odd_zeros * 10 >= pairs * 7 && even_zeros * 10 <= pairs {
+        Some(encoding_rs::UTF_16LE)
+    } else if even_zeros * 10 >= pairs * 7 && odd_zeros * 10 <= pairs {
+        Some(encoding_rs::UTF_16BE)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, little_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| if little_endian { unit.to_le_bytes() } else { unit.to_be_bytes() })
+            .collect()
+    }
+
+    fn assert_round_trip(bytes: &[u8], text: &str, encoding: &'static Encoding, bom: bool) {
+        let decoded = decode(bytes);
+        assert_eq!(decoded.text, text);
+        assert_eq!(decoded.encoding, TextEncoding { encoding, bom });
+        assert_eq!(encode(&decoded.text, decoded.encoding), bytes);
+    }
+
+    #[test]
+    fn utf8_with_and_without_bom() {
+        assert_round_trip("# Título\n".as_bytes(), "# Título\n", encoding_rs::UTF_8, false);
+        assert_round_trip(b"\xEF\xBB\xBF# T\xC3\xADtulo\n", "# Título\n", encoding_rs::UTF_8, true);
+    }
+
+    #[test]
+    fn utf16_with_bom() {
+        let mut little_endian = vec![0xFF, 0xFE];
+        little_endian.extend(utf16("# Título\n", true));
+        assert_round_trip(&little_endian, "# Título\n", encoding_rs::UTF_16LE, true);
+
+        let mut big_endian = vec![0xFE, 0xFF];
+        big_endian.extend(utf16("# Título\n", false));
+        assert_round_trip(&big_endian, "# Título\n", encoding_rs::UTF_16BE, true);
+    }
+
+    #[test]
+    fn utf16_without_bom() {
+        assert_round_trip(&utf16("# Title\n\nSome text.\n", true), "# Title\n\nSome text.\n", encoding_rs::UTF_16LE, false);
+        assert_round_trip(&utf16("# Title\n\nSome text.\n", false), "# Title\n\nSome text.\n", encoding_rs::UTF_16BE, false);
+    }
+
+    #[test]
+    fn legacy_encoding() {
+        let bytes = b"# Caf\xe9\n\nUne cr\xe8me br\xfbl\xe9e tr\xe8s sucr\xe9e, d\xe9j\xe0 pr\xeate.\n";
+        assert_round_trip(bytes, "# Café\n\nUne crème brûlée très sucrée, déjà prête.\n", encoding_rs::WINDOWS_1252, false);
+    }
+
+    #[test]
+    fn unrepresentable_characters_become_references() {
+        let encoding = TextEncoding { encoding: encoding_rs::WINDOWS_1252, bom: false };
+        assert_eq!(encode("a → b", encoding), b"a &#8594; b");
+    }
+
+    #[test]
+    fn truncated_utf8_while_streaming() {
+        let bytes = "año".as_bytes();
+        assert_eq!(detect(&bytes[..2], false), encoding_rs::UTF_8);
+        assert_ne!(detect(&bytes[..2], true), encoding_rs::UTF_8);
+    }
+}
@@ -0,0 +1,583 @@
+use crate::document::{self, Document};
+use crate::encoding;
+use crate::export::{self, escape_html, ExportTheme, Reference, Rewrite};
+use crate::tab::canonical_path;
+use crate::workspace;
+use pulldown_cmark::{Event, Parser, Tag};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Nombres del índice del libro, como en mdBook y GitBook.
+const SUMMARY_NAMES: [&str; 2] = ["SUMMARY.md", "summary.md"];
+/// Encabezados que entran en la tabla de contenidos: hasta `###`.
+const NAV_DEPTH: u8 = 3;
+
+/// Metadatos del libro, tomados del front matter.
+#[derive(Default)]
+struct Metadata {
+    title: Option<String>,
+    authors: Vec<String>,
+    language: Option<String>,
+    description: Option<String>,
+    publisher: Option<String>,
+    date: Option<String>,
+    identifier: Option<String>,
+}
+
+impl Metadata {
+    /// Completa los campos que faltan con los de `other`.
+    fn or(self, other: Metadata) -> Metadata {
+        Metadata {
+            title: self.title.or(other.title),
+            authors: if self.authors.is_empty() { other.authors } else { self.authors },
+            language: self.language.or(other.language),
+            description: self.description.or(other.description),
+            publisher: self.publisher.or(other.publisher),
+            date: self.date.or(other.date),
+            identifier: self.identifier.or(other.identifier),
+        }
+    }
+}
+
+/// Archivo Markdown que forma un capítulo, con el título que le da el índice.
+struct ChapterFile {
+    path: PathBuf,
+    title: Option<String>,
+}
+
+/// Archivo del libro además de los capítulos: una imagen.
+struct Resource {
+    href: String,
+    media_type: &'static str,
+    bytes: Vec<u8>,
+}
+
+/// `mdview epub`: escribe el libro en `output`.
+pub fn export_epub_file(input: &Path, output: &Path) -> io::Result<()> {
+    fs::write(output, export_epub(input)?)
+}
+
+/// Empaqueta como un libro EPUB 3 los archivos Markdown de una carpeta, en
+/// el orden de su SUMMARY.md o por nombre, o los que enumera un archivo de
+/// índice. Cada capítulo pasa por el mismo HTML que la exportación a HTML;
+/// las imágenes locales van dentro del libro y la tabla de contenidos se
+/// arma con los encabezados.
+pub fn export_epub(input: &Path) -> io::Result<Vec<u8>> {
+    let (root, summary, files) = gather(input)?;
+    let summary_metadata = match &summary {
+        Some(path) => split_front_matter(&read(path)?).0,
+        None => Metadata::default(),
+    };
+
+    // Cada archivo se convierte en un capítulo `chapter-NNN.xhtml`, y los
+    // enlaces entre archivos del libro pasan a apuntar a su capítulo
+    let hrefs: HashMap<PathBuf, String> = files.iter().enumerate()
+        .map(|(index, file)| (canonical_path(&file.path), format!("chapter-{:03}.xhtml", index + 1)))
+        .collect();
+
+    let mut metadata = summary_metadata;
+    let mut chapters = Vec::new();
+    let mut nav = Vec::new();
+    let mut resources: Vec<Resource> = Vec::new();
+    let mut images: HashMap<PathBuf, String> = HashMap::new();
+    for (index, file) in files.iter().enumerate() {
+        let source = read(&file.path)?;
+        let (chapter_metadata, body) = split_front_matter(&source);
+        if index == 0 {
+            metadata = metadata.or(chapter_metadata);
+        }
+        let href = &hrefs[&canonical_path(&file.path)];
+        let base_dir = file.path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+        let html = export::xhtml_body(body, |reference| match reference {
+            Reference::Image(dest) => match book_image(dest, &base_dir, &mut resources, &mut images) {
+                Some(href) => Rewrite::Replace(href),
+                None => Rewrite::Remove,
+            },
+            Reference::Link(dest) => {
+                let (path, fragment) = dest.split_once('#').map_or((dest, None), |(path, fragment)| (path, Some(fragment)));
+                // Las direcciones externas y las anclas del capítulo quedan como están
+                let Some(path) = local_path(path, &base_dir) else {
+                    return Rewrite::Keep;
+                };
+                // Un archivo que no está en el libro dejaría un enlace roto: queda el texto
+                match hrefs.get(&canonical_path(&path)) {
+                    Some(chapter) => Rewrite::Replace(match fragment {
+                        Some(fragment) => format!("{}#{}", chapter, fragment),
+                        None => chapter.clone(),
+                    }),
+                    None => Rewrite::Remove,
+                }
+            }
+        });
+
+        let document = Document::parse(body);
+        let title = file.title.clone()
+            .or_else(|| document.headings.first().map(|heading| heading.text.clone()))
+            .unwrap_or_else(|| file_title(&file.path));
+        // Los encabezados más altos de cada capítulo quedan en el primer
+        // nivel, para que un capítulo no quede dentro del anterior
+        let headings: Vec<_> = document.headings.iter().filter(|heading| heading.level <= NAV_DEPTH).collect();
+        match headings.iter().map(|heading| heading.level).min() {
+            Some(top) => nav.extend(headings.iter()
+                .map(|heading| (heading.level - top + 1, heading.text.clone(), format!("{}#{}", href, heading.anchor)))),
+            None => nav.push((1, title.clone(), href.clone())),
+        }
+        chapters.push((href.clone(), title, html));
+    }
+
+    let title = metadata.title.clone().unwrap_or_else(|| file_title(&root));
+    let language = metadata.language.clone().unwrap_or_else(|| "en".to_string());
+    let identifier = metadata.identifier.clone().unwrap_or_else(|| generated_identifier(&title, &files));
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    // El tipo va primero y sin comprimir, para que se reconozca el archivo
+    // sin descomprimirlo
+    zip.start_file("mimetype", SimpleFileOptions::default().compression_method(CompressionMethod::Stored))?;
+    zip.write_all(b"application/epub+zip")?;
+
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file("META-INF/container.xml", options)?;
+    zip.write_all(CONTAINER_XML.as_bytes())?;
+    zip.start_file("OEBPS/content.opf", options)?;
+    zip.write_all(package_document(&metadata, &title, &language, &identifier, &chapters, &resources).as_bytes())?;
+    zip.start_file("OEBPS/nav.xhtml", options)?;
+    zip.write_all(xhtml_page(&title, &language, &format!("<nav epub:type=\"toc\" id=\"toc\">\n<h1>{}</h1>\n{}</nav>\n", escape_html(&title), nav_list(&nav))).as_bytes())?;
+    zip.start_file("OEBPS/style.css", options)?;
+    zip.write_all(export::stylesheet(ExportTheme::Light).as_bytes())?;
+    for (href, chapter_title, html) in &chapters {
+        zip.start_file(format!("OEBPS/{}", href), options)?;
+        zip.write_all(xhtml_page(chapter_title, &language, &format!("<main>\n{}</main>\n", html)).as_bytes())?;
+    }
+    for resource in &resources {
+        zip.start_file(format!("OEBPS/{}", resource.href), options)?;
+        zip.write_all(&resource.bytes)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Incluye en el libro una imagen local y devuelve su dirección dentro del
+/// libro. `None` para las imágenes remotas y las que no se pueden leer o no
+/// admite el formato.
+fn book_image(dest: &str, base_dir: &Path, resources: &mut Vec<Resource>, images: &mut HashMap<PathBuf, String>) -> Option<String> {
+    let path = canonical_path(&local_path(dest, base_dir)?);
+    if let Some(href) = images.get(&path) {
+        return Some(href.clone());
+    }
+    let media_type = export::image_mime(&path).filter(|mime| !matches!(*mime, "image/bmp" | "image/x-icon"))?;
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("Could not include the image {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    let extension = path.extension().map(|extension| extension.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let href = format!("images/image-{:03}.{}", resources.len() + 1, extension);
+    resources.push(Resource { href: href.clone(), media_type, bytes });
+    images.insert(path, href.clone());
+    Some(href)
+}
+
+/// Carpeta del libro, índice usado (si hay) y archivos en orden de lectura.
+fn gather(input: &Path) -> io::Result<(PathBuf, Option<PathBuf>, Vec<ChapterFile>)> {
+    let (root, summary) = if input.is_dir() {
+        let summary = SUMMARY_NAMES.iter().map(|name| input.join(name)).find(|path| path.is_file());
+        (input.to_path_buf(), summary)
+    } else {
+        let root = input.parent().map(Path::to_path_buf).unwrap_or_default();
+        (root, Some(input.to_path_buf()))
+    };
+
+    let files = match &summary {
+        Some(summary) => {
+            let files = summary_files(summary)?;
+            // Un archivo sin enlaces a otros es un libro de un solo capítulo
+            if files.is_empty() && !input.is_dir() {
+                vec![ChapterFile { path: summary.clone(), title: None }]
+            } else {
+                files
+            }
+        }
+        None => workspace::scan_markdown_files(&root).into_iter()
+            .map(|path| ChapterFile { path, title: None })
+            .collect(),
+    };
+    if files.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("no Markdown files in {}", input.display()),
+        ));
+    }
+    Ok((root, summary, files))
+}
+
+/// Archivos Markdown que enlaza el índice, en orden y sin repetir. Los
+/// enlaces a archivos que no existen se saltan con un aviso.
+fn summary_files(summary: &Path) -> io::Result<Vec<ChapterFile>> {
+    let source = read(summary)?;
+    let (_, body) = split_front_matter(&source);
+    let base_dir = summary.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    let mut files: Vec<ChapterFile> = Vec::new();
+    let mut link: Option<(PathBuf, String)> = None;
+    for event in Parser::new_ext(body, document::markdown_options()) {
+        match event {
+            Event::Start(Tag::Link(_, dest, _)) => {
+                let path = dest.split('#').next().unwrap_or_default();
+                link = local_path(path, &base_dir)
+                    .filter(|path| workspace::is_markdown_path(path))
+                    .map(|path| (path, String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, title)) = &mut link {
+                    title.push_str(&text);
+                }
+            }
+            Event::End(Tag::Link(..)) => {
+                let Some((path, title)) = link.take() else {
+                    continue;
+                };
+                if !path.is_file() {
+                    eprintln!("The summary links to a missing file: {}", path.display());
+                } else if !files.iter().any(|file| canonical_path(&file.path) == canonical_path(&path)) {
+                    let title = Some(title.trim().to_string()).filter(|title| !title.is_empty());
+                    files.push(ChapterFile { path, title });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(files)
+}
+
+fn read(path: &Path) -> io::Result<String> {
+    Ok(encoding::decode(&fs::read(path)?).text)
+}
+
+/// Ruta de un archivo enlazado desde un documento de `base_dir`. `None` para
+/// las direcciones remotas, los `data:` URIs y las anclas del mismo documento.
+fn local_path(dest: &str, base_dir: &Path) -> Option<PathBuf> {
+    if dest.is_empty() || dest.contains("://") || dest.starts_with("data:") || dest.starts_with("mailto:") || dest.starts_with('#') {
+        return None;
+    }
+    let path = dest.split(['?', '#']).next().unwrap_or_default();
+    Some(base_dir.join(crate::percent_decode(path)))
+}
+
+fn file_title(path: &Path) -> String {
+    canonical_path(path).file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_else(|| "book".to_string())
+}
+
+/// Separa el front matter YAML (entre líneas `---`) del resto del documento.
+/// Solo se leen las claves de primer nivel con un valor o una lista.
+fn split_front_matter(source: &str) -> (Metadata, &str) {
+    let Some(rest) = source.strip_prefix("---\n").or_else(|| source.strip_prefix("---\r\n")) else {
+        return (Metadata::default(), source);
+    };
+    let mut end = 0;
+    for line in rest.split_inclusive('\n') {
+        if matches!(line.trim_end(), "---" | "...") {
+            return (parse_metadata(&rest[..end]), &rest[end + line.len()..]);
+        }
+        end += line.len();
+    }
+    (Metadata::default(), source)
+}
+
+fn parse_metadata(yaml: &str) -> Metadata {
+    let mut values: Vec<(String, Vec<String>)> = Vec::new();
+    for line in yaml.lines() {
+        if let Some(item) = line.trim_start().strip_prefix("- ") {
+            // Elemento de la lista de la última clave
+            if line.starts_with(char::is_whitespace) || line.starts_with('-') {
+                if let Some((_, list)) = values.last_mut() {
+                    list.push(unquote(item));
+                }
+            }
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        let list = match value.strip_prefix('[').and_then(|value| value.strip_suffix(']')) {
+            Some(items) => items.split(',').map(unquote).filter(|item| !item.is_empty()).collect(),
+            None if value.is_empty() => Vec::new(),
+            None => vec![unquote(value)],
+        };
+        values.push((key.trim().to_lowercase(), list));
+    }
+
+    let mut metadata = Metadata::default();
+    for (key, list) in values {
+        let first = list.first().cloned();
+        match key.as_str() {
+            "title" => metadata.title = first,
+            "author" | "authors" | "creator" => metadata.authors.extend(list),
+            "lang" | "language" => metadata.language = first,
+            "description" | "summary" => metadata.description = first,
+            "publisher" => metadata.publisher = first,
+            "date" => metadata.date = first,
+            "identifier" | "isbn" => metadata.identifier = first,
+            _ => {}
+        }
+    }
+    metadata
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    let quoted = value.len() >= 2 && (value.starts_with('"') && value.ends_with('"') || value.starts_with('\'') && value.ends_with('\''));
+    if quoted { value[1..value.len() - 1].to_string() } else { value.to_string() }
+}
+
+/// Identificador estable para los libros sin uno propio: el mismo libro
+/// exportado dos veces se reconoce como el mismo en el lector.
+fn generated_identifier(title: &str, files: &[ChapterFile]) -> String {
+    let mut hashes = [0u64; 2];
+    for (seed, hash) in hashes.iter_mut().enumerate() {
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+        title.hash(&mut hasher);
+        for file in files {
+            file.path.file_name().hash(&mut hasher);
+        }
+        *hash = hasher.finish();
+    }
+    let hex = format!("{:016x}{:016x}", hashes[0], hashes[1]);
+    format!("urn:uuid:{}-{}-4{}-a{}-{}", &hex[0..8], &hex[8..12], &hex[13..16], &hex[17..20], &hex[20..32])
+}
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+fn package_document(
+    metadata: &Metadata,
+    title: &str,
+    language: &str,
+    identifier: &str,
+    chapters: &[(String, String, String)],
+    resources: &[Resource],
+) -> String {
+    let mut optional = String::new();
+    for author in &metadata.authors {
+        optional.push_str(&format!("    <dc:creator>{}</dc:creator>\n", escape_html(author)));
+    }
+    for (element, value) in [("description", &metadata.description), ("publisher", &metadata.publisher), ("date", &metadata.date)] {
+        if let Some(value) = value {
+            optional.push_str(&format!("    <dc:{0}>{1}</dc:{0}>\n", element, escape_html(value)));
+        }
+    }
+
+    let mut manifest = String::from(
+        "    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n    <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n",
+    );
+    let mut spine = String::new();
+    for (index, (href, _, _)) in chapters.iter().enumerate() {
+        manifest.push_str(&format!("    <item id=\"chapter-{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n", index + 1, href));
+        spine.push_str(&format!("    <itemref idref=\"chapter-{}\"/>\n", index + 1));
+    }
+    for (index, resource) in resources.iter().enumerate() {
+        manifest.push_str(&format!("    <item id=\"image-{}\" href=\"{}\" media-type=\"{}\"/>\n", index + 1, resource.href, resource.media_type));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id" xml:lang="{language}">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="book-id">{identifier}</dc:identifier>
+    <dc:title>{title}</dc:title>
+    <dc:language>{language}</dc:language>
+{optional}    <meta property="dcterms:modified">{modified}</meta>
+  </metadata>
+  <manifest>
+{manifest}  </manifest>
+  <spine>
+{spine}  </spine>
+</package>
+"#,
+        language = escape_html(language),
+        identifier = escape_html(identifier),
+        title = escape_html(title),
+        modified = utc_timestamp(SystemTime::now()),
+    )
+}
+
+fn xhtml_page(title: &str, language: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" xml:lang=\"{1}\" lang=\"{1}\">\n<head>\n<meta charset=\"utf-8\"/>\n<title>{0}</title>\n<link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n</head>\n<body>\n{2}</body>\n</html>\n",
+        escape_html(title),
+        escape_html(language),
+        body,
+    )
+}
+
+/// Lista anidada de la tabla de contenidos. Cada entrada lleva dentro las
+/// que le siguen con un nivel mayor, hasta la próxima de su nivel o menor.
+fn nav_list(entries: &[(u8, String, String)]) -> String {
+    let mut html = String::from("<ol>\n");
+    let mut index = 0;
+    while index < entries.len() {
+        let (level, text, href) = &entries[index];
+        let end = entries[index + 1..].iter()
+            .position(|(next, _, _)| next <= level)
+            .map_or(entries.len(), |position| index + 1 + position);
+        html.push_str(&format!("<li><a href=\"{}\">{}</a>", escape_html(href), escape_html(text)));
+        if end > index + 1 {
+            html.push('\n');
+            html.push_str(&nav_list(&entries[index + 1..end]));
+        }
+        html.push_str("</li>\n");
+        index = end;
+    }
+    html.push_str("</ol>\n");
+    html
+}
+
+/// Fecha y hora en UTC con el formato que pide `dcterms:modified`.
+fn utc_timestamp(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+    let (days, seconds) = (seconds / 86_400, seconds % 86_400);
+    // Días desde 1970 a fecha civil (algoritmo de Howard Hinnant)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, seconds / 3_600, seconds % 3_600 / 60, seconds % 60,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    // Carpeta temporal con los archivos dados; se borra al terminar
+    struct Book(PathBuf);
+
+    impl Book {
+        fn new(name: &str, files: &[(&str, &str)]) -> Self {
+            let root = std::env::temp_dir().join(format!("mdview-epub-{}-{}", name, std::process::id()));
+            for (path, content) in files {
+                let path = root.join(path);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, content).unwrap();
+            }
+            Self(root)
+        }
+
+        fn chapters(&self) -> Vec<String> {
+            let (_, _, files) = gather(&self.0).unwrap();
+            files.iter()
+                .map(|file| file.path.strip_prefix(&self.0).unwrap().to_string_lossy().replace('\\', "/"))
+                .collect()
+        }
+
+        fn entry(&self, name: &str) -> String {
+            let mut archive = zip::ZipArchive::new(Cursor::new(export_epub(&self.0).unwrap())).unwrap();
+            let mut text = String::new();
+            archive.by_name(name).unwrap().read_to_string(&mut text).unwrap();
+            text
+        }
+    }
+
+    impl Drop for Book {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn summary_sets_the_chapter_order() {
+        let book = Book::new("summary", &[
+            ("SUMMARY.md", "# Índice\n\n- [Dos](dos.md)\n- [Uno](uno.md)\n  - [Falta](falta.md)\n  - [Otra vez](dos.md#seccion)\n- [Web](https://example.com)\n"),
+            ("uno.md", "# Uno\n"),
+            ("dos.md", "# Dos\n"),
+            ("tres.md", "# Tres\n"),
+        ]);
+        assert_eq!(book.chapters(), vec!["dos.md", "uno.md"]);
+    }
+
+    #[test]
+    fn without_summary_chapters_are_sorted_by_name() {
+        let book = Book::new("sorted", &[
+            ("b.md", "# B\n"),
+            ("A.md", "# A\n"),
+            ("parte/c.md", "# C\n"),
+            ("imagen.png", "PNG"),
+        ]);
+        assert_eq!(book.chapters(), vec!["A.md", "b.md", "parte/c.md"]);
+    }
+
+    #[test]
+    fn links_between_chapters_point_to_their_chapter_files() {
+        let book = Book::new("links", &[
+            ("SUMMARY.md", "- [Uno](uno.md)\n- [Dos](dos.md)\n"),
+            ("uno.md", "# Uno\n\n[a dos](dos.md#segunda) [afuera](notas.md) [web](https://example.com) [aquí](#uno)\n"),
+            ("dos.md", "# Dos\n\n## Segunda\n"),
+            ("notas.md", "# Notas\n"),
+        ]);
+        let chapter = book.entry("OEBPS/chapter-001.xhtml");
+        assert!(chapter.contains("<a href=\"chapter-002.xhtml#segunda\">a dos</a>"), "{chapter}");
+        assert!(chapter.contains(" afuera "));
+        assert!(!chapter.contains("notas.md"));
+        assert!(chapter.contains("<a href=\"https://example.com\">web</a>"));
+        assert!(chapter.contains("<a href=\"#uno\">aquí</a>"));
+
+        let nav = book.entry("OEBPS/nav.xhtml");
+        assert!(nav.contains("<li><a href=\"chapter-002.xhtml#dos\">Dos</a>\n<ol>\n<li><a href=\"chapter-002.xhtml#segunda\">Segunda</a></li>\n</ol>\n</li>"), "{nav}");
+    }
+
+    #[test]
+    fn metadata_comes_from_the_front_matter() {
+        let book = Book::new("metadata", &[
+            ("SUMMARY.md", "---\ntitle: \"Mi libro\"\nauthors:\n  - Ana\n  - 'Luis'\n---\n- [Uno](uno.md)\n"),
+            ("uno.md", "---\nlang: es\ntitle: Ignorado\nauthor: Otro\n---\n# Uno\n"),
+        ]);
+        let package = book.entry("OEBPS/content.opf");
+        assert!(package.contains("<dc:title>Mi libro</dc:title>"));
+        assert!(package.contains("<dc:creator>Ana</dc:creator>\n    <dc:creator>Luis</dc:creator>\n"));
+        assert!(!package.contains("Otro"));
+        assert!(package.contains("<dc:language>es</dc:language>"));
+        assert!(package.contains("<dc:identifier id=\"book-id\">urn:uuid:"));
+        // El front matter no llega al capítulo
+        assert!(!book.entry("OEBPS/chapter-001.xhtml").contains("lang: es"));
+    }
+
+    #[test]
+    fn front_matter_lists_and_quotes() {
+        let (metadata, body) = split_front_matter("---\nauthor: [Ana, \"Luis\"]\ndate: 2024-01-02\n# comentario\n...\nTexto\n");
+        assert_eq!(metadata.authors, vec!["Ana", "Luis"]);
+        assert_eq!(metadata.date.as_deref(), Some("2024-01-02"));
+        assert_eq!(body, "Texto\n");
+
+        // Sin cierre no hay front matter
+        let source = "---\ntitle: x\nTexto\n";
+        let (metadata, body) = split_front_matter(source);
+        assert_eq!(metadata.title, None);
+        assert_eq!(body, source);
+    }
+}
@@ -0,0 +1,235 @@
+use crate::document::{self, Document};
+use base64::Engine;
+use pulldown_cmark::{html, CowStr, Event, Parser, Tag};
+use std::fs;
+use std::path::Path;
+
+/// Colores de los documentos exportados.
+#[derive(Clone, Copy, PartialEq, Debug, clap::ValueEnum)]
+pub enum ExportTheme {
+    Light,
+    Dark,
+}
+
+/// Genera una página HTML que se puede abrir sin nada más: los estilos van
+/// dentro del archivo y las imágenes locales como `data:` URIs. Las rutas
+/// relativas de las imágenes se resuelven desde `base_dir`.
+pub fn standalone_html(source: &str, title: &str, base_dir: &Path, theme: ExportTheme) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n<main>\n{}</main>\n</body>\n</html>\n",
+        escape_html(title),
+        stylesheet(theme),
+        body_html(source, base_dir),
+    )
+}
+
+/// Dirección de un enlace o de una imagen, para cambiarla al exportar.
+pub enum Reference<'a> {
+    Link(&'a str),
+    Image(&'a str),
+}
+
+/// Qué hacer con un enlace o una imagen al exportar.
+pub enum Rewrite {
+    /// Se deja la dirección como está.
+    Keep,
+    /// Se cambia por otra dirección.
+    Replace(String),
+    /// Se quita el enlace o la imagen y queda su texto.
+    Remove,
+}
+
+/// Cuerpo del documento en HTML, con anclas en los encabezados (las mismas
+/// que usa el visor) e imágenes locales incrustadas.
+pub fn body_html(source: &str, base_dir: &Path) -> String {
+    render_body(source, false, |reference| match reference {
+        Reference::Image(dest) => inline_image(dest, base_dir).map_or(Rewrite::Keep, Rewrite::Replace),
+        Reference::Link(_) => Rewrite::Keep,
+    })
+}
+
+/// Cuerpo del documento en XHTML, para los libros EPUB. Los encabezados
+/// conservan sus anclas pero no el enlace `#`, y el HTML escrito a mano se
+/// omite: el lector rechaza los capítulos que no son XML bien formado.
+/// `rewrite` decide qué pasa con cada enlace e imagen: las imágenes
+/// quitadas se reemplazan por su texto alternativo y los enlaces quitados,
+/// por su texto.
+pub fn xhtml_body(source: &str, rewrite: impl FnMut(Reference) -> Rewrite) -> String {
+    render_body(source, true, rewrite)
+}
+
+fn render_body(source: &str, xhtml: bool, mut rewrite: impl FnMut(Reference) -> Rewrite) -> String {
+    let anchors: Vec<String> = Document::parse(source).headings.into_iter().map(|heading| heading.anchor).collect();
+    let mut anchors = anchors.iter();
+    let mut alt_text = false;
+    let mut link_text = false;
+
+    let events = Parser::new_ext(source, document::markdown_options()).flat_map(|event| match event {
+        Event::Start(Tag::Heading(level, _, classes)) => match anchors.next() {
+            Some(anchor) if xhtml => vec![Event::Start(Tag::Heading(level, Some(anchor.as_str()), classes))],
+            Some(anchor) => vec![
+                Event::Start(Tag::Heading(level, Some(anchor.as_str()), classes)),
+                Event::Html(format!("<a class=\"anchor\" href=\"#{}\" aria-hidden=\"true\">#</a>", anchor).into()),
+            ],
+            None => vec![Event::Start(Tag::Heading(level, None, classes))],
+        },
+        Event::Start(Tag::Image(kind, dest, title)) => match rewrite(Reference::Image(&dest)) {
+            Rewrite::Keep => vec![Event::Start(Tag::Image(kind, dest, title))],
+            Rewrite::Replace(dest) => vec![Event::Start(Tag::Image(kind, dest.into(), title))],
+            Rewrite::Remove => {
+                alt_text = true;
+                Vec::new()
+            }
+        },
+        Event::End(Tag::Image(..)) if alt_text => {
+            alt_text = false;
+            Vec::new()
+        }
+        Event::Start(Tag::Link(kind, dest, title)) => match rewrite(Reference::Link(&dest)) {
+            Rewrite::Keep => vec![Event::Start(Tag::Link(kind, dest, title))],
+            Rewrite::Replace(dest) => vec![Event::Start(Tag::Link(kind, CowStr::from(dest), title))],
+            Rewrite::Remove => {
+                link_text = true;
+                Vec::new()
+            }
+        },
+        Event::End(Tag::Link(..)) if link_text => {
+            link_text = false;
+            Vec::new()
+        }
+        Event::Html(_) if xhtml => Vec::new(),
+        event => vec![event],
+    });
+
+    let mut output = String::new();
+    html::push_html(&mut output, events);
+    output
+}
+
+/// Lee una imagen local y la devuelve como `data:` URI. `None` para las
+/// direcciones remotas y los archivos que no se pueden leer o no son imágenes.
+fn inline_image(dest: &str, base_dir: &Path) -> Option<String> {
+    if dest.contains("://") || dest.starts_with("data:") {
+        return None;
+    }
+    let path = dest.split(['?', '#']).next().unwrap_or_default();
+    let path = base_dir.join(crate::percent_decode(path));
+    let mime = image_mime(&path)?;
+    match fs::read(&path) {
+        Ok(bytes) => Some(format!("data:{};base64,{}", mime, base64::engine::general_purpose::STANDARD.encode(bytes))),
+        Err(e) => {
+            eprintln!("Could not embed the image {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+pub fn image_mime(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    let mime = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Hoja de estilos con los colores de la vista renderizada.
+pub fn stylesheet(theme: ExportTheme) -> String {
+    // (fondo, texto, bordes, fondo del código, fondo del código en línea, barra de las citas, enlaces)
+    let (background, text, border, code, inline_code, quote, link) = match theme {
+        ExportTheme::Light => ("#ffffff", "#333333", "#c8c8c8", "#f8f8f8", "#f0f0f0", "#c8c8c8", "#0b6bcb"),
+        ExportTheme::Dark => ("#1b1b1b", "#d2d2d2", "#5a5a5a", "#1e1e1e", "#2d2d2d", "#5a5a5a", "#5aa9ff"),
+    };
+    let headings = match theme {
+        ExportTheme::Light => ["#333333", "#444444", "#555555", "#666666", "#777777", "#888888"],
+        ExportTheme::Dark => ["#ffffff", "#e6e6e6", "#d2d2d2", "#bebebe", "#bebebe", "#bebebe"],
+    };
+    format!(
+        r#"body {{ margin: 0; background: {background}; color: {text}; font: 14px/1.6 -apple-system, "Segoe UI", Roboto, "Helvetica Neue", Arial, sans-serif; }}
+main {{ max-width: 800px; margin: 0 auto; padding: 20px 24px 48px; }}
+h1, h2, h3, h4, h5, h6 {{ position: relative; margin: 1.4em 0 0.6em; line-height: 1.25; }}
+h1 {{ font-size: 28px; color: {h1}; border-bottom: 1px solid {border}; padding-bottom: 5px; }}
+h2 {{ font-size: 24px; color: {h2}; border-bottom: 1px solid {border}; padding-bottom: 5px; }}
+h3 {{ font-size: 20px; color: {h3}; }}
+h4 {{ font-size: 18px; color: {h4}; }}
+h5 {{ font-size: 16px; color: {h5}; }}
+h6 {{ font-size: 14px; color: {h6}; }}
+.anchor {{ position: absolute; left: -1em; width: 1em; color: {border}; text-decoration: none; visibility: hidden; }}
+h1:hover .anchor, h2:hover .anchor, h3:hover .anchor, h4:hover .anchor, h5:hover .anchor, h6:hover .anchor {{ visibility: visible; }}
+a {{ color: {link}; }}
+img {{ max-width: 100%; }}
+code {{ font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: 13px; background: {inline_code}; padding: 1px 4px; border-radius: 3px; }}
+pre {{ background: {code}; border: 1px solid {border}; padding: 10px; overflow-x: auto; }}
+pre code {{ background: none; padding: 0; }}
+blockquote {{ margin: 0.8em 0; padding: 0 0 0 11px; border-left: 3px solid {quote}; }}
+table {{ border-collapse: collapse; margin: 8px 0; }}
+th, td {{ border: 1px solid {border}; padding: 6px 8px; }}
+tbody tr:nth-child(odd) {{ background: {inline_code}; }}
+hr {{ border: none; border-top: 1px solid {border}; margin: 16px 0; }}
+li:has(> input[type="checkbox"]) {{ list-style: none; }}
+.footnote-definition {{ font-size: 13px; margin-top: 8px; }}
+.footnote-definition-label {{ vertical-align: super; font-size: 11px; margin-right: 4px; }}
+"#,
+        h1 = headings[0],
+        h2 = headings[1],
+        h3 = headings[2],
+        h4 = headings[3],
+        h5 = headings[4],
+        h6 = headings[5],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headings_get_the_viewer_anchors() {
+        let html = standalone_html("# Hola mundo\n\n## Hola mundo\n", "Prueba", Path::new("."), ExportTheme::Light);
+        assert!(html.contains("<h1 id=\"hola-mundo\"><a class=\"anchor\" href=\"#hola-mundo\" aria-hidden=\"true\">#</a>Hola mundo</h1>"));
+        assert!(html.contains("<h2 id=\"hola-mundo-1\">"));
+        assert!(html.contains("<title>Prueba</title>"));
+    }
+
+    #[test]
+    fn local_images_are_inlined() {
+        let dir = std::env::temp_dir().join(format!("mdview-export-{}", std::process::id()));
+        fs::create_dir_all(dir.join("img")).unwrap();
+        fs::write(dir.join("img/punto azul.png"), b"PNG").unwrap();
+
+        let html = standalone_html("![punto](img/punto%20azul.png)", "Prueba", &dir, ExportTheme::Light);
+        assert!(html.contains("<img src=\"data:image/png;base64,UE5H\" alt=\"punto\" />"), "{html}");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_and_remote_images_are_kept() {
+        let html = standalone_html(
+            "![falta](no-existe.png) ![remota](https://example.com/a.png)",
+            "Prueba",
+            Path::new("/mdview-no-existe"),
+            ExportTheme::Light,
+        );
+        assert!(html.contains("<img src=\"no-existe.png\" alt=\"falta\" />"));
+        assert!(html.contains("<img src=\"https://example.com/a.png\" alt=\"remota\" />"));
+    }
+
+    #[test]
+    fn theme_picks_the_colors() {
+        let light = standalone_html("texto", "Prueba", Path::new("."), ExportTheme::Light);
+        let dark = standalone_html("texto", "Prueba", Path::new("."), ExportTheme::Dark);
+        assert!(light.contains("background: #ffffff; color: #333333;"));
+        assert!(dark.contains("background: #1b1b1b; color: #d2d2d2;"));
+        assert_eq!(light.replace(&stylesheet(ExportTheme::Light), ""), dark.replace(&stylesheet(ExportTheme::Dark), ""));
+    }
+}
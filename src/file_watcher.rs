@@ -0,0 +1,231 @@
+use crate::tab::canonical_path;
+use eframe::egui;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+// Tiempo sin eventos antes de releer un archivo: los editores suelen
+// escribir en varios pasos (truncar, escribir, renombrar)
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Vigila los archivos abiertos en las pestañas.
+///
+/// Se vigila la carpeta de cada archivo y no el archivo en sí: los editores
+/// que guardan escribiendo un temporal y renombrándolo reemplazan el archivo
+/// original, y un vigilante puesto sobre él dejaría de recibir eventos.
+pub struct FileWatcher {
+    watcher: Option<RecommendedWatcher>,
+    events: Receiver<notify::Event>,
+    /// Archivos vigilados: la ruta con la que se abrieron y la canónica, que
+    /// es la que aparece en los eventos.
+    files: Vec<(PathBuf, PathBuf)>,
+    directories: Vec<PathBuf>,
+    /// Archivos con cambios y el momento del último evento recibido.
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl FileWatcher {
+    pub fn new(ctx: &egui::Context) -> Self {
+        let (sender, events) = mpsc::channel();
+        let repaint_ctx = ctx.clone();
+        let watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+            if let Ok(event) = result {
+                if sender.send(event).is_ok() {
+                    repaint_ctx.request_repaint();
+                }
+            }
+        });
+        let watcher = match watcher {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                eprintln!("No se pudieron vigilar los archivos abiertos: {}", e);
+                None
+            }
+        };
+        Self {
+            watcher,
+            events,
+            files: Vec::new(),
+            directories: Vec::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Ajusta las carpetas vigiladas a los archivos abiertos.
+    pub fn sync(&mut self, files: &[PathBuf]) {
+        if files.iter().eq(self.files.iter().map(|(path, _)| path)) {
+            return;
+        }
+        // Se vigila la carpeta real, así los eventos traen las mismas rutas
+        let files: Vec<(PathBuf, PathBuf)> = files.iter().map(|path| (path.clone(), canonical_path(path))).collect();
+        let mut directories: Vec<PathBuf> = files.iter().filter_map(|(_, canonical)| canonical.parent().map(Path::to_path_buf)).collect();
+        directories.sort();
+        directories.dedup();
+
+        if let Some(watcher) = &mut self.watcher {
+            for directory in self.directories.iter().filter(|directory| !directories.contains(directory)) {
+                let _ = watcher.unwatch(directory);
+            }
+            for directory in directories.iter().filter(|directory| !self.directories.contains(directory)) {
+                if let Err(e) = watcher.watch(directory, RecursiveMode::NonRecursive) {
+                    eprintln!("No se pudo vigilar la carpeta: {}", e);
+                }
+            }
+        }
+        self.pending.retain(|path, _| files.iter().any(|(opened, _)| opened == path));
+        self.files = files;
+        self.directories = directories;
+    }
+
+    /// Procesa los eventos pendientes y devuelve los archivos abiertos que
+    /// cambiaron y ya llevan un tiempo sin cambiar, con la ruta que se pasó a
+    /// `sync`. Si quedan cambios en espera, pide un repintado para cuando se
+    /// cumpla ese tiempo.
+    pub fn poll(&mut self, ctx: &egui::Context) -> Vec<PathBuf> {
+        let now = Instant::now();
+        while let Ok(event) = self.events.try_recv() {
+            self.record(&event, now);
+        }
+        let (ready, wait) = self.take_ready(now);
+        if let Some(wait) = wait {
+            ctx.request_repaint_after(wait);
+        }
+        ready
+    }
+
+    /// Anota como cambiados los archivos abiertos que nombra un evento.
+    fn record(&mut self, event: &notify::Event, now: Instant) {
+        if event.kind.is_access() {
+            return;
+        }
+        for path in &event.paths {
+            for (opened, _) in self.files.iter().filter(|(_, canonical)| canonical == path) {
+                self.pending.insert(opened.clone(), now);
+            }
+        }
+    }
+
+    /// Saca los archivos que ya llevan `DEBOUNCE` sin eventos y devuelve
+    /// cuánto falta para el siguiente, si queda alguno en espera.
+    fn take_ready(&mut self, now: Instant) -> (Vec<PathBuf>, Option<Duration>) {
+        let mut ready = Vec::new();
+        let mut wait = None;
+        self.pending.retain(|path, last_event| {
+            let elapsed = now.duration_since(*last_event);
+            if elapsed >= DEBOUNCE {
+                ready.push(path.clone());
+                false
+            } else {
+                let remaining = DEBOUNCE - elapsed;
+                wait = Some(wait.map_or(remaining, |wait: Duration| wait.min(remaining)));
+                true
+            }
+        });
+        (ready, wait)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, EventKind, ModifyKind, RemoveKind, RenameMode};
+
+    fn watching(files: &[PathBuf]) -> FileWatcher {
+        let (_, events) = mpsc::channel();
+        let mut watcher = FileWatcher {
+            watcher: None,
+            events,
+            files: Vec::new(),
+            directories: Vec::new(),
+            pending: HashMap::new(),
+        };
+        watcher.sync(files);
+        watcher
+    }
+
+    fn event(kind: EventKind, paths: &[PathBuf]) -> notify::Event {
+        paths.iter().fold(notify::Event::new(kind), |event, path| event.add_path(path.clone()))
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mdview-watch-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn atomic_save_reports_the_opened_path() {
+        let dir = temp_dir("rename");
+        let file = dir.join("a.md");
+        std::fs::write(&file, "# A").unwrap();
+        let opened = dir.join("sub/../a.md");
+        let mut watcher = watching(std::slice::from_ref(&opened));
+        assert_eq!(watcher.directories, vec![canonical_path(&dir)]);
+
+        // El editor escribe un temporal y lo renombra sobre el archivo
+        let now = Instant::now();
+        let temporary = canonical_path(&dir).join(".a.md.tmp");
+        watcher.record(&event(EventKind::Create(CreateKind::File), std::slice::from_ref(&temporary)), now);
+        assert!(watcher.pending.is_empty());
+        watcher.record(&event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &[temporary, canonical_path(&file)]), now);
+        assert_eq!(watcher.take_ready(now + DEBOUNCE), (vec![opened], None));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn access_and_other_files_are_ignored() {
+        let dir = temp_dir("ignored");
+        let file = dir.join("a.md");
+        std::fs::write(&file, "# A").unwrap();
+        let mut watcher = watching(std::slice::from_ref(&file));
+        let now = Instant::now();
+        watcher.record(&event(EventKind::Access(AccessKind::Any), std::slice::from_ref(&file)), now);
+        watcher.record(&event(EventKind::Modify(ModifyKind::Any), &[dir.join("b.md")]), now);
+        assert!(watcher.pending.is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn removed_file_is_reported() {
+        let dir = temp_dir("removed");
+        let file = dir.join("a.md");
+        std::fs::write(&file, "# A").unwrap();
+        let mut watcher = watching(std::slice::from_ref(&file));
+        let removed = canonical_path(&file);
+        std::fs::remove_file(&file).unwrap();
+
+        let now = Instant::now();
+        watcher.record(&event(EventKind::Remove(RemoveKind::File), &[removed]), now);
+        assert_eq!(watcher.take_ready(now + DEBOUNCE).0, vec![file]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn changes_wait_until_events_stop() {
+        let file = PathBuf::from("/mdview-watch/a.md");
+        let mut watcher = watching(std::slice::from_ref(&file));
+        let modified = event(EventKind::Modify(ModifyKind::Any), &[canonical_path(&file)]);
+        let start = Instant::now();
+        let step = DEBOUNCE / 2;
+
+        watcher.record(&modified, start);
+        assert_eq!(watcher.take_ready(start + step), (vec![], Some(DEBOUNCE - step)));
+        // Otro evento vuelve a empezar la espera
+        watcher.record(&modified, start + step);
+        assert_eq!(watcher.take_ready(start + DEBOUNCE), (vec![], Some(step)));
+        assert_eq!(watcher.take_ready(start + step + DEBOUNCE), (vec![file], None));
+        assert_eq!(watcher.take_ready(start + DEBOUNCE * 4), (vec![], None));
+    }
+
+    #[test]
+    fn closed_files_drop_their_pending_changes() {
+        let (first, second) = (PathBuf::from("/mdview-watch/a.md"), PathBuf::from("/mdview-watch/b.md"));
+        let mut watcher = watching(&[first.clone(), second.clone()]);
+        let now = Instant::now();
+        watcher.record(&event(EventKind::Modify(ModifyKind::Any), &[canonical_path(&first), canonical_path(&second)]), now);
+        watcher.sync(std::slice::from_ref(&second));
+        assert_eq!(watcher.take_ready(now + DEBOUNCE).0, vec![second]);
+    }
+}
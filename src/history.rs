@@ -0,0 +1,115 @@
+use std::path::PathBuf;
+
+// Límite de entradas por dirección para que el historial no crezca sin fin
+const MAX_HISTORY: usize = 100;
+
+/// Posición dentro de un documento: archivo y desplazamiento vertical.
+#[derive(Clone, PartialEq, Debug)]
+pub struct HistoryEntry {
+    pub path: Option<PathBuf>,
+    pub scroll_offset: f32,
+}
+
+/// Historial atrás/adelante de una pestaña, al estilo de un navegador.
+#[derive(Default)]
+pub struct NavigationHistory {
+    back: Vec<HistoryEntry>,
+    forward: Vec<HistoryEntry>,
+}
+
+impl NavigationHistory {
+    /// Registra la posición que se abandona al navegar. Descarta el historial hacia adelante.
+    pub fn push(&mut self, entry: HistoryEntry) {
+        if self.back.last() != Some(&entry) {
+            self.back.push(entry);
+            if self.back.len() > MAX_HISTORY {
+                self.back.remove(0);
+            }
+        }
+        self.forward.clear();
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+
+    /// Entrada a la que se volvería al ir atrás (o adelante), sin moverse.
+    pub fn peek(&self, forward: bool) -> Option<&HistoryEntry> {
+        if forward {
+            self.forward.last()
+        } else {
+            self.back.last()
+        }
+    }
+
+    /// Pasa a la entrada anterior y guarda `current` para poder volver
+    /// adelante. Se llama una vez abierta la entrada que devuelve `peek`.
+    pub fn go_back(&mut self, current: HistoryEntry) -> Option<HistoryEntry> {
+        let entry = self.back.pop()?;
+        self.forward.push(current);
+        Some(entry)
+    }
+
+    pub fn go_forward(&mut self, current: HistoryEntry) -> Option<HistoryEntry> {
+        let entry = self.forward.pop()?;
+        self.back.push(current);
+        Some(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(offset: f32) -> HistoryEntry {
+        HistoryEntry {
+            path: None,
+            scroll_offset: offset,
+        }
+    }
+
+    #[test]
+    fn back_and_forward() {
+        let mut history = NavigationHistory::default();
+        history.push(entry(1.0));
+        history.push(entry(2.0));
+        assert_eq!(history.peek(false), Some(&entry(2.0)));
+        assert_eq!(history.go_back(entry(3.0)), Some(entry(2.0)));
+        assert_eq!(history.go_back(entry(2.0)), Some(entry(1.0)));
+        assert_eq!(history.go_back(entry(1.0)), None);
+        assert_eq!(history.go_forward(entry(1.0)), Some(entry(2.0)));
+        assert_eq!(history.go_forward(entry(2.0)), Some(entry(3.0)));
+        assert!(!history.can_go_forward());
+    }
+
+    #[test]
+    fn push_discards_forward_entries_and_repeats() {
+        let mut history = NavigationHistory::default();
+        history.push(entry(1.0));
+        history.push(entry(2.0));
+        history.go_back(entry(3.0));
+        history.push(entry(2.0));
+        assert!(!history.can_go_forward());
+        history.push(entry(2.0));
+        assert_eq!(history.go_back(entry(4.0)), Some(entry(2.0)));
+        assert_eq!(history.go_back(entry(2.0)), Some(entry(1.0)));
+    }
+
+    #[test]
+    fn oldest_entries_are_dropped() {
+        let mut history = NavigationHistory::default();
+        for offset in 0..MAX_HISTORY + 5 {
+            history.push(entry(offset as f32));
+        }
+        let mut oldest = None;
+        while let Some(previous) = history.go_back(entry(0.0)) {
+            oldest = Some(previous);
+        }
+        assert_eq!(oldest, Some(entry(5.0)));
+        assert_eq!(history.forward.len(), MAX_HISTORY);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::actions::Action;
+use crate::clipboard::CopyFormat;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Combinación de teclas. `ctrl` es la tecla de comando de la plataforma
+/// (Ctrl en Windows y Linux, Cmd en macOS).
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Shortcut {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub key: egui::Key,
+}
+
+impl Shortcut {
+    pub const fn new(ctrl: bool, shift: bool, alt: bool, key: egui::Key) -> Self {
+        Self { ctrl, shift, alt, key }
+    }
+
+    pub fn from_event(modifiers: egui::Modifiers, key: egui::Key) -> Self {
+        Self {
+            ctrl: modifiers.command,
+            shift: modifiers.shift,
+            alt: modifiers.alt,
+            key,
+        }
+    }
+
+    // Comparación exacta: Ctrl+P no se dispara con Ctrl+Shift+P
+    fn matches(&self, modifiers: egui::Modifiers, key: egui::Key) -> bool {
+        self.key == key && self.ctrl == modifiers.command && self.shift == modifiers.shift && self.alt == modifiers.alt
+    }
+
+    /// Atajo que produce texto (una letra, con o sin Shift), que no debe
+    /// dispararse mientras se escribe en un campo de texto.
+    fn types_text(&self) -> bool {
+        !self.ctrl && !self.alt && self.key.symbol_or_name().chars().count() == 1
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        // El "+" solo puede ser la tecla, al final ("+", "Ctrl++")
+        let (modifiers, key) = match text.strip_suffix('+') {
+            Some(rest) if rest.is_empty() || rest.ends_with('+') => (rest, "+"),
+            _ => text.rsplit_once('+').unwrap_or(("", text)),
+        };
+        let mut shortcut = Self::new(false, false, false, egui::Key::from_name(key)?);
+        for part in modifiers.split('+') {
+            match part {
+                "Ctrl" | "Cmd" => shortcut.ctrl = true,
+                "Shift" => shortcut.shift = true,
+                "Alt" => shortcut.alt = true,
+                "" => {}
+                _ => return None,
+            }
+        }
+        Some(shortcut)
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "{}+", if cfg!(target_os = "macos") { "Cmd" } else { "Ctrl" })?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        // Signos de puntuación como símbolo (","), el resto por nombre ("Left", "F11")
+        let symbol = self.key.symbol_or_name();
+        if symbol.len() == 1 && symbol.is_ascii() {
+            write!(f, "{}", symbol)
+        } else {
+            write!(f, "{}", self.key.name())
+        }
+    }
+}
+
+impl From<Shortcut> for String {
+    fn from(shortcut: Shortcut) -> Self {
+        shortcut.to_string()
+    }
+}
+
+impl TryFrom<String> for Shortcut {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        Self::parse(&text).ok_or_else(|| format!("atajo inválido: {}", text))
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub action: Action,
+    pub shortcut: Option<Shortcut>,
+}
+
+/// Atajos de teclado de todas las acciones. Se guarda con la configuración.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Keymap {
+    bindings: Vec<KeyBinding>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .iter()
+                .map(|action| KeyBinding {
+                    action: *action,
+                    shortcut: default_shortcut(*action),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// Agrega las acciones que no estaban en una configuración guardada por
+    /// una versión anterior y descarta las que ya no existen.
+    pub fn merge_defaults(&mut self) {
+        let mut merged = Self::default();
+        for binding in &mut merged.bindings {
+            if let Some(saved) = self.bindings.iter().find(|saved| saved.action == binding.action) {
+                binding.shortcut = saved.shortcut;
+            }
+        }
+        *self = merged;
+    }
+
+    pub fn bindings(&self) -> &[KeyBinding] {
+        &self.bindings
+    }
+
+    pub fn shortcut(&self, action: Action) -> Option<Shortcut> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.action == action)
+            .and_then(|binding| binding.shortcut)
+    }
+
+    pub fn set(&mut self, action: Action, shortcut: Option<Shortcut>) {
+        if let Some(binding) = self.bindings.iter_mut().find(|binding| binding.action == action) {
+            binding.shortcut = shortcut;
+        }
+    }
+
+    pub fn reset(&mut self, action: Action) {
+        self.set(action, default_shortcut(action));
+    }
+
+    /// Otra acción que usa el mismo atajo que `action`, si la hay.
+    pub fn conflict(&self, action: Action) -> Option<Action> {
+        let shortcut = self.shortcut(action)?;
+        self.bindings
+            .iter()
+            .find(|binding| binding.action != action && binding.shortcut == Some(shortcut))
+            .map(|binding| binding.action)
+    }
+
+    /// Consume las teclas de este frame que corresponden a un atajo y
+    /// devuelve las acciones a ejecutar.
+    pub fn dispatch(&self, ctx: &egui::Context) -> Vec<Action> {
+        let mut actions = Vec::new();
+        let typing = ctx.wants_keyboard_input();
+        ctx.input_mut(|input| {
+            input.events.retain(|event| {
+                let egui::Event::Key { key, pressed: true, modifiers, .. } = event else {
+                    return true;
+                };
+                let binding = self.bindings.iter().find(|binding| {
+                    binding.shortcut.is_some_and(|shortcut| {
+                        shortcut.matches(*modifiers, *key) && !(typing && shortcut.types_text())
+                    })
+                });
+                match binding {
+                    Some(binding) => {
+                        actions.push(binding.action);
+                        false
+                    }
+                    None => true,
+                }
+            });
+        });
+        actions
+    }
+}
+
+/// Consume la primera tecla presionada en este frame, para grabar un atajo nuevo.
+pub fn capture(ctx: &egui::Context) -> Option<Shortcut> {
+    ctx.input_mut(|input| {
+        let position = input
+            .events
+            .iter()
+            .position(|event| matches!(event, egui::Event::Key { pressed: true, .. }))?;
+        match input.events.remove(position) {
+            egui::Event::Key { key, modifiers, .. } => Some(Shortcut::from_event(modifiers, key)),
+            _ => None,
+        }
+    })
+}
+
+pub fn default_shortcut(action: Action) -> Option<Shortcut> {
+    use egui::Key;
+    let shortcut = match action {
+        Action::OpenFile => Shortcut::new(true, false, false, Key::O),
+        Action::OpenFolder => Shortcut::new(true, true, false, Key::O),
+        Action::Save => Shortcut::new(true, false, false, Key::S),
+        Action::SaveAs => Shortcut::new(true, true, false, Key::S),
+        Action::QuickOpen => Shortcut::new(true, false, false, Key::P),
+        Action::CommandPalette => Shortcut::new(true, true, false, Key::P),
+        Action::ToggleRawView => Shortcut::new(true, false, false, Key::R),
+        Action::ToggleSplitView => Shortcut::new(true, false, false, Key::Backslash),
+        Action::ToggleEditMode => Shortcut::new(true, false, false, Key::E),
+        Action::ToggleOutline => Shortcut::new(true, false, false, Key::B),
+        Action::Find => Shortcut::new(true, false, false, Key::F),
+        Action::SearchWorkspace => Shortcut::new(true, true, false, Key::F),
+        Action::OpenSettings => Shortcut::new(true, false, false, Key::Comma),
+        Action::GoBack => Shortcut::new(false, false, true, Key::ArrowLeft),
+        Action::GoForward => Shortcut::new(false, false, true, Key::ArrowRight),
+        Action::Reload => Shortcut::new(false, false, false, Key::F5),
+        Action::NextChange => Shortcut::new(false, false, false, Key::F7),
+        Action::PreviousChange => Shortcut::new(false, true, false, Key::F7),
+        Action::CloseTab => Shortcut::new(true, false, false, Key::W),
+        Action::NextTab => Shortcut::new(true, false, false, Key::Tab),
+        Action::PreviousTab => Shortcut::new(true, true, false, Key::Tab),
+        Action::ToggleFullscreen => Shortcut::new(false, false, false, Key::F11),
+        Action::ShowShortcuts => Shortcut::new(false, false, false, Key::F1),
+        Action::CopyAs(CopyFormat::Html) => Shortcut::new(true, true, false, Key::C),
+        _ => return None,
+    };
+    Some(shortcut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_shortcuts() {
+        assert_eq!(Shortcut::parse("Ctrl+Shift+P"), Some(Shortcut::new(true, true, false, egui::Key::P)));
+        assert_eq!(Shortcut::parse("Cmd+O"), Some(Shortcut::new(true, false, false, egui::Key::O)));
+        assert_eq!(Shortcut::parse("Alt+Left"), Some(Shortcut::new(false, false, true, egui::Key::ArrowLeft)));
+        assert_eq!(Shortcut::parse("F11"), Some(Shortcut::new(false, false, false, egui::Key::F11)));
+        assert_eq!(Shortcut::parse("Ctrl++"), Some(Shortcut::new(true, false, false, egui::Key::Plus)));
+        assert_eq!(Shortcut::parse("+"), Some(Shortcut::new(false, false, false, egui::Key::Plus)));
+    }
+
+    #[test]
+    fn reject_invalid_shortcuts() {
+        assert_eq!(Shortcut::parse(""), None);
+        assert_eq!(Shortcut::parse("Ctrl+"), None);
+        assert_eq!(Shortcut::parse("Super+A"), None);
+        assert_eq!(Shortcut::parse("Ctrl+Nothing"), None);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let keys = [egui::Key::P, egui::Key::Plus, egui::Key::Minus, egui::Key::Comma, egui::Key::ArrowLeft, egui::Key::F11, egui::Key::Slash];
+        for key in keys {
+            for (ctrl, shift, alt) in [(false, false, false), (true, false, false), (true, true, false), (false, false, true)] {
+                let shortcut = Shortcut::new(ctrl, shift, alt, key);
+                assert_eq!(Shortcut::parse(&shortcut.to_string()), Some(shortcut), "{}", shortcut);
+            }
+        }
+        assert_eq!(Shortcut::new(false, true, true, egui::Key::F5).to_string(), "Shift+Alt+F5");
+        assert_eq!(Shortcut::new(false, false, false, egui::Key::Comma).to_string(), ",");
+    }
+}
@@ -1,24 +1,130 @@
 #![cfg_attr(all(target_os = "windows", not(debug_assertions)), windows_subsystem = "windows")]
-use clap::Parser;
+mod actions;
+mod changes;
+mod clipboard;
+mod convert;
+mod document;
+mod encoding;
+mod epub;
+mod export;
+mod file_watcher;
+mod history;
+mod keymap;
+mod pager;
+mod pdf;
+mod palette;
+mod render;
+mod scroll_memory;
+mod search;
+mod snapshot;
+mod split;
+mod stdin;
+mod tab;
+mod terminal;
+mod text;
+mod workspace;
+mod workspace_search;
+
+use actions::Action;
+use clap::{CommandFactory, Parser, Subcommand};
+use clipboard::{Clipboard, CopyFormat};
+use convert::{ConvertOptions, Format};
 use eframe::egui;
-use pulldown_cmark::{html, Options, Parser as MarkdownParser};
+use file_watcher::FileWatcher;
+use keymap::Keymap;
 use rfd::FileDialog;
+use scroll_memory::ScrollMemory;
 use serde::{Deserialize, Serialize};
-use std::fs;
 use std::path::PathBuf;
+use pager::{PagerCommand, PagerKeys};
+use palette::{CommandPalette, PaletteItem, PaletteMode, PaletteTarget};
+use export::ExportTheme;
+use pdf::{PageSize, PdfOptions};
+use snapshot::PngOptions;
+use split::{Pane, PaneLayout};
+use stdin::StdinReader;
+use std::ops::Range;
+use tab::{DocumentTab, ViewMode};
+use workspace::{FileNode, Workspace};
+use workspace_search::WorkspaceSearch;
 use sys_locale::get_locale;
 
 #[derive(Parser)]
 #[command(name = "mdview")]
 #[command(about = "A cross-platform markdown viewer")]
 #[command(version = "0.1.0")]
+#[command(args_conflicts_with_subcommands = true)]
 struct Args {
-    /// Markdown file to open
-    #[arg(help = "Path to the markdown file to view")]
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Path to the markdown file or folder to view, or - to read standard input
     file: Option<PathBuf>,
+    /// With -, keep reading standard input and re-render as more text arrives
+    #[arg(long, requires = "file", conflicts_with_all = ["tui", "export_html", "export_pdf"])]
+    follow: bool,
+    /// Show the file in the terminal, in a pager with scrolling and search
+    #[arg(long)]
+    tui: bool,
+    /// Export the file to a standalone HTML file and exit
+    #[arg(long, value_name = "OUTPUT", conflicts_with_all = ["export_pdf", "tui"])]
+    export_html: Option<PathBuf>,
+    /// Export the file to a PDF file and exit
+    #[arg(long, value_name = "OUTPUT", conflicts_with = "tui")]
+    export_pdf: Option<PathBuf>,
+    #[command(flatten)]
+    convert: ConvertOptions,
+}
+
+/// Modos que no abren la ventana.
+#[derive(Subcommand)]
+enum Command {
+    /// Convert a Markdown file to HTML, PDF or plain text
+    Convert {
+        /// Markdown file to convert, or - to read standard input
+        input: PathBuf,
+        /// Output file; its extension (.html, .pdf, .png, .txt) picks the format
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Output format (default: from the output file extension)
+        #[arg(long, value_enum)]
+        to: Option<Format>,
+        #[command(flatten)]
+        options: ConvertOptions,
+    },
+    /// Render a Markdown file to standard output
+    Render {
+        /// Markdown file to render, or - (the default) to read standard input
+        #[arg(default_value = "-")]
+        input: PathBuf,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = Format::Html)]
+        to: Format,
+        #[command(flatten)]
+        options: ConvertOptions,
+    },
+    /// Print a Markdown file to the terminal with colors and styles
+    Cat {
+        /// Markdown file to print, or - (the default) to read standard input
+        #[arg(default_value = "-")]
+        input: PathBuf,
+        /// Line width (default: the width of the terminal)
+        #[arg(long, value_name = "COLUMNS")]
+        width: Option<usize>,
+        /// Show the document in a pager with scrolling and search
+        #[arg(short, long)]
+        pager: bool,
+    },
+    /// Bundle a folder of Markdown files as an EPUB e-book
+    Epub {
+        /// Folder of Markdown files, or a SUMMARY.md listing the chapters in order
+        input: PathBuf,
+        /// EPUB file to write
+        #[arg(short, long)]
+        output: PathBuf,
+    },
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
 enum Language {
     English,
     Spanish,
@@ -32,7 +138,7 @@ impl Default for Language {
 }
 
 impl Language {
-    fn to_string(&self) -> &'static str {
+    fn to_string(self) -> &'static str {
         match self {
             Language::English => "English",
             Language::Spanish => "Español",
@@ -52,7 +158,7 @@ impl Language {
     fn get_effective_language(&self) -> Language {
         match self {
             Language::System => Self::detect_system_language(),
-            lang => lang.clone(),
+            lang => *lang,
         }
     }
 }
@@ -64,21 +170,103 @@ struct Translations {
     
     // Toolbar
     open_file: &'static str,
+    open_folder: &'static str,
     view_normal: &'static str,
     view_raw: &'static str,
     settings: &'static str,
+    encoding_tooltip: &'static str,
+    reopen_with_encoding: &'static str,
+    toggle_outline: &'static str,
+    close_tab: &'static str,
+    go_back: &'static str,
+    go_forward: &'static str,
+    
+    // Commands
+    action_quick_open: &'static str,
+    action_command_palette: &'static str,
+    action_toggle_raw: &'static str,
+    action_toggle_split: &'static str,
+    action_toggle_edit: &'static str,
+    action_save: &'static str,
+    action_save_as: &'static str,
+    action_find: &'static str,
+    action_reload: &'static str,
+    action_next_change: &'static str,
+    action_previous_change: &'static str,
+    action_export_html: &'static str,
+    action_export_pdf: &'static str,
+    action_export_png: &'static str,
+    action_export_visible_png: &'static str,
+    action_export_epub: &'static str,
+    action_copy_as: &'static str,
+    copy_section_as: &'static str,
+    copy_selection_as: &'static str,
+    copy_format_text: &'static str,
+    action_close_tab: &'static str,
+    action_next_tab: &'static str,
+    action_previous_tab: &'static str,
+    action_toggle_fullscreen: &'static str,
+    action_show_shortcuts: &'static str,
+    action_toggle_vim_navigation: &'static str,
+    palette_placeholder: &'static str,
+    
+    // Find bar
+    find_placeholder: &'static str,
+    find_case_sensitive: &'static str,
+    find_whole_word: &'static str,
+    find_regex: &'static str,
+    find_previous: &'static str,
+    find_next: &'static str,
+    find_close: &'static str,
+    find_no_results: &'static str,
+    find_invalid_regex: &'static str,
+    
+    // Outline sidebar
+    outline_title: &'static str,
+    outline_empty: &'static str,
+    
+    // Workspace sidebar
+    close_workspace: &'static str,
+    workspace_empty: &'static str,
+    workspace_files: &'static str,
+    workspace_search: &'static str,
+    workspace_search_placeholder: &'static str,
+    workspace_search_indexing: &'static str,
+    workspace_search_summary: &'static str,
+    
+    // Document context menu
+    show_source: &'static str,
+    reveal_in_preview: &'static str,
+    
+    // File change notices
+    file_missing_notice: &'static str,
+    file_missing_dismiss: &'static str,
+    
+    // Unsaved changes prompt
+    unsaved_title: &'static str,
+    unsaved_message: &'static str,
+    unsaved_quit_message: &'static str,
+    unsaved_save: &'static str,
+    unsaved_discard: &'static str,
+    unsaved_cancel: &'static str,
     
     // Welcome screen
     welcome_title: &'static str,
     welcome_subtitle: &'static str,
     welcome_drag_drop: &'static str,
     open_file_button: &'static str,
+    open_folder_button: &'static str,
+    recent_files: &'static str,
+    no_recent_files: &'static str,
     
     // Settings panel
     settings_title: &'static str,
     sections_title: &'static str,
     theme_section: &'static str,
     language_section: &'static str,
+    files_section: &'static str,
+    keybindings_section: &'static str,
+    export_section: &'static str,
     about_section: &'static str,
     apply_button: &'static str,
     close_button: &'static str,
@@ -98,6 +286,31 @@ struct Translations {
     language_spanish: &'static str,
     language_system: &'static str,
     
+    // Files settings
+    files_config_title: &'static str,
+    recent_files_limit: &'static str,
+    restore_session: &'static str,
+    auto_reload: &'static str,
+    clear_recent_files: &'static str,
+    
+    // Export settings
+    export_config_title: &'static str,
+    pdf_page_size: &'static str,
+    pdf_margin: &'static str,
+    png_width: &'static str,
+    png_scale: &'static str,
+    
+    // Keybindings settings
+    keybindings_config_title: &'static str,
+    keybindings_description: &'static str,
+    keybindings_recording: &'static str,
+    keybindings_unassigned: &'static str,
+    keybindings_conflict: &'static str,
+    keybindings_reset: &'static str,
+    keybindings_clear: &'static str,
+    keybindings_reset_all: &'static str,
+    vim_navigation: &'static str,
+    
     // About section
     about_title: &'static str,
     about_author: &'static str,
@@ -127,19 +340,94 @@ impl Translations {
             app_title: "MDView - Markdown Viewer",
             
             open_file: "Open file",
+            open_folder: "Open folder",
             view_normal: "Normal view",
             view_raw: "Raw view",
             settings: "Settings",
+            encoding_tooltip: "File encoding",
+            reopen_with_encoding: "Reopen with encoding",
+            toggle_outline: "Show/hide outline",
+            close_tab: "Close tab (middle click)",
+            go_back: "Back",
+            go_forward: "Forward",
+            
+            action_quick_open: "Go to file or heading",
+            action_command_palette: "Show all commands",
+            action_toggle_raw: "Toggle raw view",
+            action_toggle_split: "Toggle split view",
+            action_toggle_edit: "Toggle edit mode",
+            action_save: "Save",
+            action_save_as: "Save as…",
+            action_find: "Find in document",
+            action_reload: "Reload file",
+            action_next_change: "Next change",
+            action_previous_change: "Previous change",
+            action_export_html: "Export as HTML",
+            action_export_pdf: "Export as PDF",
+            action_export_png: "Export as PNG",
+            action_export_visible_png: "Export Visible Area as PNG",
+            action_export_epub: "Export Folder as EPUB",
+            action_copy_as: "Copy as",
+            copy_section_as: "Copy section as",
+            copy_selection_as: "Copy selection as",
+            copy_format_text: "plain text",
+            action_close_tab: "Close tab",
+            action_next_tab: "Next tab",
+            action_previous_tab: "Previous tab",
+            action_toggle_fullscreen: "Toggle full screen",
+            action_show_shortcuts: "Keyboard shortcuts",
+            action_toggle_vim_navigation: "Toggle vim-style navigation",
+            palette_placeholder: "Go to file (# for headings, > for commands)",
+            
+            find_placeholder: "Find",
+            find_case_sensitive: "Match case",
+            find_whole_word: "Match whole word",
+            find_regex: "Use regular expression",
+            find_previous: "Previous match (Shift+Enter)",
+            find_next: "Next match (Enter)",
+            find_close: "Close (Esc)",
+            find_no_results: "No results",
+            find_invalid_regex: "Invalid regular expression",
+            
+            outline_title: "Contents",
+            outline_empty: "No headings",
+            
+            close_workspace: "Close folder",
+            workspace_empty: "No Markdown files in this folder",
+            workspace_files: "Files",
+            workspace_search: "Search in folder",
+            workspace_search_placeholder: "Search in folder",
+            workspace_search_indexing: "Indexing…",
+            workspace_search_summary: "{matches} results in {files} files",
+            
+            show_source: "Show source",
+            reveal_in_preview: "Reveal in preview",
+            
+            file_missing_notice: "This file was deleted or moved. The last loaded version is still shown.",
+            file_missing_dismiss: "Dismiss",
+            
+            unsaved_title: "Unsaved changes",
+            unsaved_message: "\"{name}\" has unsaved changes. Do you want to save them?",
+            unsaved_quit_message: "{count} documents have unsaved changes. Do you want to save them before quitting?",
+            unsaved_save: "Save",
+            unsaved_discard: "Don't save",
+            unsaved_cancel: "Cancel",
             
             welcome_title: "MDView - Markdown Viewer",
             welcome_subtitle: "Click 'Open' to load a markdown file",
-            welcome_drag_drop: "Or drag a .md file directly here",
+            welcome_drag_drop: "Or drag a .md file or a folder directly here",
             open_file_button: "Open file",
+            open_folder_button: "Open folder",
+            recent_files: "Recent files",
+            no_recent_files: "No recent files",
             
             settings_title: "Settings",
             sections_title: "Sections",
             theme_section: "Theme",
             language_section: "Language",
+            files_section: "Files",
+            keybindings_section: "Keybindings",
+            export_section: "Export",
             about_section: "About",
             apply_button: "Apply",
             close_button: "Close",
@@ -157,6 +445,28 @@ impl Translations {
             language_spanish: "Spanish",
             language_system: "System",
             
+            files_config_title: "Files Configuration",
+            recent_files_limit: "Number of recent files:",
+            restore_session: "Reopen the files from the last session at startup",
+            auto_reload: "Reload files automatically when they change on disk",
+            clear_recent_files: "Clear recent files",
+            
+            export_config_title: "Export Configuration",
+            pdf_page_size: "PDF page size:",
+            pdf_margin: "PDF margins (mm):",
+            png_width: "PNG width (points):",
+            png_scale: "PNG scale (pixels per point):",
+            
+            keybindings_config_title: "Keyboard Shortcuts",
+            keybindings_description: "Click a shortcut and press the new key combination (Esc cancels):",
+            keybindings_recording: "Press a key…",
+            keybindings_unassigned: "Unassigned",
+            keybindings_conflict: "Also used by",
+            keybindings_reset: "Restore default",
+            keybindings_clear: "Remove shortcut",
+            keybindings_reset_all: "Restore all defaults",
+            vim_navigation: "Vim/less-style navigation: j/k line, d/u half page, Space/b page, gg/G top/bottom, ]]/[[ headings, / find, n/N matches",
+            
             about_title: "About MDView",
             about_author: "Author: Pablo Medina",
             about_year: "Year: 2025",
@@ -178,19 +488,94 @@ impl Translations {
             app_title: "MDView - Visor de Markdown",
             
             open_file: "Abrir archivo",
+            open_folder: "Abrir carpeta",
             view_normal: "Vista normal",
             view_raw: "Vista raw",
             settings: "Configuración",
+            encoding_tooltip: "Codificación del archivo",
+            reopen_with_encoding: "Reabrir con codificación",
+            toggle_outline: "Mostrar/ocultar índice",
+            close_tab: "Cerrar pestaña (clic central)",
+            go_back: "Atrás",
+            go_forward: "Adelante",
+            
+            action_quick_open: "Ir a archivo o encabezado",
+            action_command_palette: "Mostrar todos los comandos",
+            action_toggle_raw: "Alternar vista raw",
+            action_toggle_split: "Alternar vista dividida",
+            action_toggle_edit: "Alternar modo edición",
+            action_save: "Guardar",
+            action_save_as: "Guardar como…",
+            action_find: "Buscar en el documento",
+            action_reload: "Recargar archivo",
+            action_next_change: "Cambio siguiente",
+            action_previous_change: "Cambio anterior",
+            action_export_html: "Exportar como HTML",
+            action_export_pdf: "Exportar como PDF",
+            action_export_png: "Exportar como PNG",
+            action_export_visible_png: "Exportar el área visible como PNG",
+            action_export_epub: "Exportar carpeta como EPUB",
+            action_copy_as: "Copiar como",
+            copy_section_as: "Copiar sección como",
+            copy_selection_as: "Copiar selección como",
+            copy_format_text: "texto plano",
+            action_close_tab: "Cerrar pestaña",
+            action_next_tab: "Pestaña siguiente",
+            action_previous_tab: "Pestaña anterior",
+            action_toggle_fullscreen: "Pantalla completa",
+            action_show_shortcuts: "Atajos de teclado",
+            action_toggle_vim_navigation: "Alternar navegación estilo vim",
+            palette_placeholder: "Ir a archivo (# para encabezados, > para comandos)",
+            
+            find_placeholder: "Buscar",
+            find_case_sensitive: "Coincidir mayúsculas y minúsculas",
+            find_whole_word: "Solo palabras completas",
+            find_regex: "Usar expresión regular",
+            find_previous: "Coincidencia anterior (Shift+Enter)",
+            find_next: "Coincidencia siguiente (Enter)",
+            find_close: "Cerrar (Esc)",
+            find_no_results: "Sin resultados",
+            find_invalid_regex: "Expresión regular inválida",
+            
+            outline_title: "Contenido",
+            outline_empty: "Sin encabezados",
+            
+            close_workspace: "Cerrar carpeta",
+            workspace_empty: "No hay archivos Markdown en esta carpeta",
+            workspace_files: "Archivos",
+            workspace_search: "Buscar en la carpeta",
+            workspace_search_placeholder: "Buscar en la carpeta",
+            workspace_search_indexing: "Indexando…",
+            workspace_search_summary: "{matches} resultados en {files} archivos",
+            
+            show_source: "Ver código fuente",
+            reveal_in_preview: "Mostrar en la vista renderizada",
+            
+            file_missing_notice: "Este archivo se borró o se movió. Se sigue mostrando la última versión cargada.",
+            file_missing_dismiss: "Descartar",
+            
+            unsaved_title: "Cambios sin guardar",
+            unsaved_message: "\"{name}\" tiene cambios sin guardar. ¿Desea guardarlos?",
+            unsaved_quit_message: "{count} documentos tienen cambios sin guardar. ¿Desea guardarlos antes de salir?",
+            unsaved_save: "Guardar",
+            unsaved_discard: "No guardar",
+            unsaved_cancel: "Cancelar",
             
             welcome_title: "MDView - Visor de Markdown",
             welcome_subtitle: "Haz clic en 'Abrir' para cargar un archivo markdown",
-            welcome_drag_drop: "O arrastra un archivo .md directamente aquí",
+            welcome_drag_drop: "O arrastra un archivo .md o una carpeta directamente aquí",
             open_file_button: "Abrir archivo",
+            open_folder_button: "Abrir carpeta",
+            recent_files: "Archivos recientes",
+            no_recent_files: "No hay archivos recientes",
             
             settings_title: "Configuración",
             sections_title: "Secciones",
             theme_section: "Tema",
             language_section: "Idioma",
+            files_section: "Archivos",
+            keybindings_section: "Atajos",
+            export_section: "Exportar",
             about_section: "Acerca de",
             apply_button: "Aplicar",
             close_button: "Cerrar",
@@ -208,6 +593,28 @@ impl Translations {
             language_spanish: "Español",
             language_system: "Sistema",
             
+            files_config_title: "Configuración de Archivos",
+            recent_files_limit: "Cantidad de archivos recientes:",
+            restore_session: "Reabrir al iniciar los archivos de la última sesión",
+            auto_reload: "Recargar los archivos automáticamente cuando cambian en el disco",
+            clear_recent_files: "Borrar archivos recientes",
+            
+            export_config_title: "Configuración de Exportación",
+            pdf_page_size: "Tamaño de página del PDF:",
+            pdf_margin: "Márgenes del PDF (mm):",
+            png_width: "Ancho del PNG (puntos):",
+            png_scale: "Escala del PNG (píxeles por punto):",
+            
+            keybindings_config_title: "Atajos de Teclado",
+            keybindings_description: "Haz clic en un atajo y presiona la nueva combinación de teclas (Esc cancela):",
+            keybindings_recording: "Presiona una tecla…",
+            keybindings_unassigned: "Sin asignar",
+            keybindings_conflict: "También lo usa",
+            keybindings_reset: "Restaurar predeterminado",
+            keybindings_clear: "Quitar atajo",
+            keybindings_reset_all: "Restaurar todos los predeterminados",
+            vim_navigation: "Navegación estilo vim/less: j/k línea, d/u media página, Espacio/b página, gg/G inicio/fin, ]]/[[ encabezados, / buscar, n/N coincidencias",
+            
             about_title: "Acerca de MDView",
             about_author: "Autor: Pablo Medina",
             about_year: "Año: 2025",
@@ -233,7 +640,30 @@ impl Default for Translations {
 
 fn main() -> Result<(), eframe::Error> {
     let args = Args::parse();
+    if args.follow && args.file.as_deref().is_some_and(|file| file.as_os_str() != "-") {
+        Args::command().error(clap::error::ErrorKind::ArgumentConflict, "--follow only works when reading standard input (-)").exit();
+    }
     env_logger::init();
+    
+    // Convertir o mostrar en la terminal sin abrir la ventana
+    let result = match (&args.command, &args.export_html, &args.export_pdf) {
+        (Some(Command::Convert { input, output, to, options }), _, _) => Some(convert::convert_file(input, output, *to, options)),
+        (Some(Command::Render { input, to, options }), _, _) => Some(convert::render(input, *to, options)),
+        (Some(Command::Cat { input, pager: true, .. }), _, _) => Some(terminal::page(input)),
+        (Some(Command::Cat { input, width, .. }), _, _) => Some(terminal::cat(input, *width)),
+        (Some(Command::Epub { input, output }), _, _) => Some(epub::export_epub_file(input, output)),
+        (None, _, _) if args.tui => Some(terminal::page(args.file.as_deref().unwrap_or(std::path::Path::new("-")))),
+        (None, Some(output), _) => Some(export_file(args.file.as_deref(), output, Format::Html, &args.convert)),
+        (None, None, Some(output)) => Some(export_file(args.file.as_deref(), output, Format::Pdf, &args.convert)),
+        (None, None, None) => None,
+    };
+    if let Some(result) = result {
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
@@ -258,9 +688,15 @@ fn main() -> Result<(), eframe::Error> {
                 MarkdownViewer::default()
             };
             
-            // Si se pasó un archivo por línea de comandos, abrirlo
+            // Si se pasó un archivo o una carpeta por línea de comandos, abrirlo
             if let Some(file_path) = args.file {
-                app.open_file_from_path(file_path);
+                if file_path.as_os_str() == "-" {
+                    app.open_stdin(&cc.egui_ctx, args.follow);
+                } else if file_path.is_dir() {
+                    app.open_folder_from_path(file_path, &cc.egui_ctx);
+                } else {
+                    app.open_file_from_path(file_path);
+                }
             }
             
             Ok(Box::new(app))
@@ -268,15 +704,54 @@ fn main() -> Result<(), eframe::Error> {
     )
 }
 
+/// `--export-html` y `--export-pdf`: exporta el archivo indicado (o la
+/// entrada estándar, con `-`).
+fn export_file(input: Option<&std::path::Path>, output: &std::path::Path, format: Format, options: &ConvertOptions) -> std::io::Result<()> {
+    let Some(input) = input else {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "no file to export; pass a path, or - to read standard input"));
+    };
+    convert::convert_file(input, output, Some(format), options)
+}
+
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
 struct AppSettings {
     theme: Theme,
     language: Language,
-    show_raw_markdown: bool,
+    view_mode: ViewMode,
+    /// Ancho del panel de código fuente en la vista dividida, de 0 a 1.
+    split_ratio: f32,
     window_maximized: bool,
+    show_outline: bool,
+    outline_width: f32,
+    workspace_width: f32,
+    recent_files: Vec<PathBuf>,
+    max_recent_files: usize,
+    restore_session: bool,
+    auto_reload: bool,
+    keybindings: Keymap,
+    vim_navigation: bool,
+    pdf: PdfOptions,
+    png: PngOptions,
+}
+
+/// Archivos abiertos al cerrar la aplicación, para restaurarlos al iniciar.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Session {
+    files: Vec<SessionFile>,
+    active: usize,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Clone)]
+#[derive(Serialize, Deserialize)]
+struct SessionFile {
+    path: PathBuf,
+    scroll_offset: f32,
+    #[serde(default)]
+    view_mode: ViewMode,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
 enum Theme {
     Light,
     Dark,
@@ -288,27 +763,98 @@ impl Default for AppSettings {
         Self {
             theme: Theme::System,
             language: Language::System,
-            show_raw_markdown: false,
+            view_mode: ViewMode::Rendered,
+            split_ratio: 0.5,
             window_maximized: false,
+            show_outline: true,
+            outline_width: 220.0,
+            workspace_width: 240.0,
+            recent_files: Vec::new(),
+            max_recent_files: 10,
+            restore_session: false,
+            auto_reload: true,
+            keybindings: Keymap::default(),
+            vim_navigation: false,
+            pdf: PdfOptions::default(),
+            png: PngOptions::default(),
         }
     }
 }
 
 #[derive(Default)]
 struct MarkdownViewer {
-    markdown_content: String,
-    html_content: String,
-    current_file: Option<PathBuf>,
+    tabs: Vec<DocumentTab>,
+    active_tab: usize,
+    next_tab_id: u64,
+    find: search::FindState,
+    workspace: Option<Workspace>,
+    workspace_view: WorkspaceView,
+    workspace_search: WorkspaceSearch,
+    palette: CommandPalette,
+    scroll_memory: ScrollMemory,
+    /// Vigila los archivos abiertos; se crea en el primer frame.
+    file_watcher: Option<FileWatcher>,
     settings: AppSettings,
     show_settings: bool,
+    show_shortcuts: bool,
+    /// Acción cuyo atajo se está grabando en la configuración.
+    recording_shortcut: Option<Action>,
+    /// Operación que descartaría cambios sin guardar, esperando confirmación.
+    unsaved_prompt: Option<UnsavedPrompt>,
+    /// Menú contextual abierto sobre un bloque del documento.
+    block_menu: Option<BlockMenu>,
+    clipboard: Clipboard,
+    /// Lectura de la entrada estándar en curso (`mdview -`).
+    stdin: Option<StdinReader>,
+    /// Título actual de la ventana, para cambiarlo solo cuando hace falta.
+    window_title: String,
+    pager_keys: PagerKeys,
     selected_settings_section: SettingsSection,
     translations: Translations,
 }
 
+/// Menú contextual de un bloque: "ver código fuente" en la vista renderizada
+/// y "mostrar en la vista renderizada" en el código fuente.
+struct BlockMenu {
+    tab: u64,
+    block: usize,
+    pane: Pane,
+    position: egui::Pos2,
+}
+
+/// Operaciones que reemplazan o cierran un documento y que, si tiene cambios
+/// sin guardar, esperan a que el usuario decida qué hacer con ellos.
+enum UnsavedAction {
+    CloseTab,
+    FollowLink(String),
+    GoBack,
+    GoForward,
+    Reload,
+    ReopenWithEncoding(&'static encoding_rs::Encoding),
+    Quit,
+}
+
+struct UnsavedPrompt {
+    /// Pestaña afectada; `None` al salir, que afecta a todas.
+    tab: Option<u64>,
+    action: UnsavedAction,
+}
+
+/// Contenido del panel del espacio de trabajo.
+#[derive(Default, PartialEq)]
+enum WorkspaceView {
+    #[default]
+    Files,
+    Search,
+}
+
 #[derive(PartialEq)]
 enum SettingsSection {
     Theme,
     Language,
+    Files,
+    Keybindings,
+    Export,
     About,
 }
 
@@ -320,15 +866,58 @@ impl Default for SettingsSection {
 
 impl MarkdownViewer {
     fn from_storage(storage: &dyn eframe::Storage) -> Self {
-        let settings: AppSettings = eframe::get_value(storage, "settings").unwrap_or_default();
+        let mut settings: AppSettings = eframe::get_value(storage, "settings").unwrap_or_default();
+        settings.recent_files.retain(|path| path.is_file());
+        settings.keybindings.merge_defaults();
         let translations = Translations::new(&settings.language);
-        Self {
+        let mut app = Self {
             settings,
             translations,
+            scroll_memory: eframe::get_value(storage, "scroll_positions").unwrap_or_default(),
             ..Default::default()
+        };
+        
+        if app.settings.restore_session {
+            let session: Session = eframe::get_value(storage, "session").unwrap_or_default();
+            app.restore_session(session);
+        }
+        app
+    }
+
+    fn restore_session(&mut self, session: Session) {
+        for file in session.files {
+            if !file.path.is_file() {
+                continue;
+            }
+            if let Ok(mut tab) = DocumentTab::open(self.next_tab_id, file.path, file.view_mode) {
+                tab.restore_scroll = Some(file.scroll_offset);
+                self.next_tab_id += 1;
+                self.tabs.push(tab);
+            }
+        }
+        self.select_tab(session.active.min(self.tabs.len().saturating_sub(1)));
+    }
+
+    fn current_session(&self) -> Session {
+        Session {
+            files: self.tabs.iter().filter_map(|tab| {
+                Some(SessionFile {
+                    path: tab.current_file.clone()?,
+                    scroll_offset: tab.scroll_offset,
+                    view_mode: tab.view_mode,
+                })
+            }).collect(),
+            active: self.active_tab,
         }
     }
 
+    fn add_recent_file(&mut self, path: &std::path::Path) {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        self.settings.recent_files.retain(|recent| *recent != path);
+        self.settings.recent_files.insert(0, path);
+        self.settings.recent_files.truncate(self.settings.max_recent_files);
+    }
+
     fn open_file(&mut self) {
         if let Some(path) = FileDialog::new()
             .add_filter("Markdown", &["md", "markdown", "txt"])
@@ -338,230 +927,1495 @@ impl MarkdownViewer {
         }
     }
 
+    fn open_folder(&mut self, ctx: &egui::Context) {
+        if let Some(path) = FileDialog::new().pick_folder() {
+            self.open_folder_from_path(path, ctx);
+        }
+    }
+
+    /// Abre una carpeta como espacio de trabajo y muestra su README, si tiene.
+    fn open_folder_from_path(&mut self, path: PathBuf, ctx: &egui::Context) {
+        match Workspace::open(path, ctx) {
+            Ok(workspace) => {
+                let readme = workspace.readme();
+                self.workspace = Some(workspace);
+                self.workspace_search.clear();
+                if let Some(readme) = readme {
+                    self.open_file_from_path(readme);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error al abrir la carpeta: {}", e);
+            }
+        }
+    }
+
     fn open_file_from_path(&mut self, path: PathBuf) {
-        match fs::read_to_string(&path) {
-            Ok(content) => {
-                self.markdown_content = content;
-                self.current_file = Some(path);
-                self.update_html();
+        // Si ya está abierto, solo cambiar a su pestaña
+        if let Some(index) = self.tabs.iter().position(|tab| tab.is_file(&path)) {
+            self.select_tab(index);
+            return;
+        }
+        
+        match DocumentTab::open(self.next_tab_id, path.clone(), self.settings.view_mode) {
+            Ok(mut tab) => {
+                // La pestaña guarda la ruta canónica, que también se usa para los recientes
+                let path = tab.current_file.clone().unwrap_or(path);
+                tab.restore_position = self.scroll_memory.get(&path).cloned();
+                self.add_recent_file(&path);
+                self.next_tab_id += 1;
+                self.tabs.push(tab);
+                self.select_tab(self.tabs.len() - 1);
             }
             Err(e) => {
                 eprintln!("Error al leer el archivo: {}", e);
+                self.settings.recent_files.retain(|recent| *recent != path);
             }
         }
     }
 
-    fn update_html(&mut self) {
-        let mut options = Options::empty();
-        options.insert(Options::ENABLE_STRIKETHROUGH);
-        options.insert(Options::ENABLE_TABLES);
-        options.insert(Options::ENABLE_FOOTNOTES);
-        options.insert(Options::ENABLE_TASKLISTS);
-        options.insert(Options::ENABLE_SMART_PUNCTUATION);
+    /// Abre una pestaña con el texto de la entrada estándar, que se llena a
+    /// medida que se lee.
+    fn open_stdin(&mut self, ctx: &egui::Context, follow: bool) {
+        let mut tab = DocumentTab::new(self.next_tab_id, self.settings.view_mode);
+        tab.from_stdin = true;
+        self.stdin = Some(StdinReader::spawn(ctx, tab.id, follow));
+        self.next_tab_id += 1;
+        self.tabs.push(tab);
+        self.select_tab(self.tabs.len() - 1);
+    }
 
-        let parser = MarkdownParser::new_ext(&self.markdown_content, options);
-        let mut html_output = String::new();
-        html::push_html(&mut html_output, parser);
-        self.html_content = html_output;
+    /// Actualiza la pestaña de la entrada estándar con lo que llegó.
+    fn read_stdin(&mut self) {
+        let Some(reader) = &mut self.stdin else {
+            return;
+        };
+        let (text, finished) = reader.poll();
+        let tab = self.tabs.iter_mut().find(|tab| tab.id == reader.tab);
+        // Si se cerró la pestaña o ya se editó a mano, lo que llegue se descarta
+        if let (Some(text), Some(tab)) = (text, tab.filter(|tab| !tab.dirty)) {
+            tab.set_content(text);
+            self.find.invalidate();
+        }
+        if finished {
+            self.stdin = None;
+        }
     }
 
-    fn apply_theme(&self, ctx: &egui::Context) {
-        match self.settings.theme {
-            Theme::Light => ctx.set_visuals(egui::Visuals::light()),
-            Theme::Dark => ctx.set_visuals(egui::Visuals::dark()),
-            Theme::System => {
-                // Usar el tema del sistema (por defecto dark en este caso)
-                ctx.set_visuals(egui::Visuals::dark());
+    /// Muestra en el título de la ventana el nombre del documento activo.
+    fn update_window_title(&mut self, ctx: &egui::Context) {
+        let title = match self.active_tab().map(DocumentTab::title).filter(|title| !title.is_empty()) {
+            Some(name) => format!("{} - MDView", name),
+            None => "MDView - Visor de Markdown".to_string(),
+        };
+        if title != self.window_title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.window_title = title;
+        }
+    }
+
+    /// Abre un resultado de la búsqueda en la carpeta, desplazado hasta la coincidencia.
+    fn open_search_result(&mut self, path: PathBuf, offset: usize) {
+        self.open_file_from_path(path.clone());
+        if let Some(tab) = self.tabs.get_mut(self.active_tab).filter(|tab| tab.is_file(&path)) {
+            tab.reveal_offset(offset);
+        }
+    }
+
+    fn reopen_with_encoding(&mut self, encoding: &'static encoding_rs::Encoding) {
+        if !self.confirm_discard(self.active_tab, UnsavedAction::ReopenWithEncoding(encoding)) {
+            return;
+        }
+        if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+            if let Err(e) = tab.reopen_with_encoding(encoding) {
+                eprintln!("Error al leer el archivo: {}", e);
             }
+            self.find.invalidate();
         }
     }
 
-    fn update_translations(&mut self) {
-        self.translations = Translations::new(&self.settings.language);
+    /// Abre un enlace del documento: anclas y otros archivos Markdown navegan
+    /// dentro de la pestaña; el resto se abre con la aplicación del sistema.
+    fn follow_link(&mut self, ctx: &egui::Context, link: &str) {
+        if let Some(anchor) = link.strip_prefix('#') {
+            if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+                tab.jump_to_anchor(&percent_decode(anchor));
+            }
+            return;
+        }
+        
+        if link.contains("://") || link.starts_with("mailto:") {
+            ctx.open_url(egui::OpenUrl::new_tab(link));
+            return;
+        }
+        
+        let (target, anchor) = match link.split_once('#') {
+            Some((target, anchor)) => (target, Some(percent_decode(anchor))),
+            None => (link, None),
+        };
+        let base = self.active_tab()
+            .and_then(|tab| tab.current_file.as_ref())
+            .and_then(|path| path.parent())
+            .map(|dir| dir.to_path_buf())
+            .unwrap_or_default();
+        let path = base.join(percent_decode(target));
+        
+        if !workspace::is_markdown_path(&path) {
+            ctx.open_url(egui::OpenUrl::new_tab(format!("file://{}", path.display())));
+            return;
+        }
+        
+        if !self.confirm_discard(self.active_tab, UnsavedAction::FollowLink(link.to_string())) {
+            return;
+        }
+        self.remember_scroll(self.active_tab);
+        let Some(tab) = self.tabs.get_mut(self.active_tab) else {
+            return;
+        };
+        match tab.navigate_to(path.clone()) {
+            Ok(()) => {
+                if let Some(anchor) = anchor {
+                    tab.scroll_to_block = tab.document.headings.iter()
+                        .find(|heading| heading.anchor == anchor)
+                        .map(|heading| heading.block);
+                }
+                self.add_recent_file(&path);
+                self.find.invalidate();
+            }
+            Err(e) => {
+                eprintln!("Error al leer el archivo: {}", e);
+            }
+        }
     }
 
-    fn render_toolbar(&mut self, ui: &mut egui::Ui) {
-        ui.horizontal(|ui| {
-            ui.add_space(15.0);
-            
-            // Botón abrir archivo - estilo VSCode
-            let open_response = ui.add_sized([40.0, 40.0], 
-                egui::Button::new(egui::RichText::new(egui_phosphor::regular::FOLDER_OPEN).size(18.0))
-                    .fill(egui::Color32::TRANSPARENT)
-                    .stroke(egui::Stroke::NONE)
-                    .corner_radius(egui::CornerRadius::same(6))
-            );
-            
-            if open_response.hovered() {
-                ui.painter().rect_filled(
-                    open_response.rect, 
-                    egui::CornerRadius::same(6), 
-                    if ui.visuals().dark_mode { 
-                        egui::Color32::from_rgba_unmultiplied(255, 255, 255, 20) 
-                    } else { 
-                        egui::Color32::from_rgba_unmultiplied(0, 0, 0, 15) 
-                    }
-                );
+    fn go_back(&mut self) {
+        let leaves_file = self.active_tab().is_some_and(|tab| tab.history_leaves_file(false));
+        if leaves_file && !self.confirm_discard(self.active_tab, UnsavedAction::GoBack) {
+            return;
+        }
+        self.remember_scroll(self.active_tab);
+        if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+            if let Err(e) = tab.go_back() {
+                eprintln!("Error al leer el archivo: {}", e);
             }
-            
-            if open_response.on_hover_text(self.translations.open_file).clicked() {
-                self.open_file();
+            self.find.invalidate();
+        }
+    }
+
+    fn go_forward(&mut self) {
+        let leaves_file = self.active_tab().is_some_and(|tab| tab.history_leaves_file(true));
+        if leaves_file && !self.confirm_discard(self.active_tab, UnsavedAction::GoForward) {
+            return;
+        }
+        self.remember_scroll(self.active_tab);
+        if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+            if let Err(e) = tab.go_forward() {
+                eprintln!("Error al leer el archivo: {}", e);
             }
-            
-            ui.add_space(8.0);
-            
-            // Toggle vista raw - estilo VSCode
-            let (icon, tooltip) = if self.settings.show_raw_markdown {
-                (egui_phosphor::regular::EYE, self.translations.view_normal)
-            } else {
-                (egui_phosphor::regular::CODE, self.translations.view_raw)
+            self.find.invalidate();
+        }
+    }
+
+    fn reload(&mut self) {
+        if !self.confirm_discard(self.active_tab, UnsavedAction::Reload) {
+            return;
+        }
+        if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+            if let Err(e) = tab.reload() {
+                eprintln!("Error al leer el archivo: {}", e);
+            }
+            self.find.invalidate();
+        }
+    }
+
+    /// Recarga las pestañas cuyos archivos cambiaron en el disco y marca las
+    /// de los archivos que se borraron o movieron.
+    fn reload_changed_files(&mut self, ctx: &egui::Context) {
+        if !self.settings.auto_reload {
+            self.file_watcher = None;
+            return;
+        }
+        let watcher = self.file_watcher.get_or_insert_with(|| FileWatcher::new(ctx));
+        let files: Vec<PathBuf> = self.tabs.iter().filter_map(|tab| tab.current_file.clone()).collect();
+        watcher.sync(&files);
+        
+        let changed = watcher.poll(ctx);
+        if changed.is_empty() {
+            return;
+        }
+        for tab in &mut self.tabs {
+            let Some(path) = &tab.current_file else {
+                continue;
             };
-            
-            let view_response = ui.add_sized([40.0, 40.0], 
-                egui::Button::new(egui::RichText::new(icon).size(18.0))
-                    .fill(egui::Color32::TRANSPARENT)
-                    .stroke(egui::Stroke::NONE)
-                    .corner_radius(egui::CornerRadius::same(6))
-            );
-            
-            if view_response.hovered() {
-                ui.painter().rect_filled(
-                    view_response.rect, 
-                    egui::CornerRadius::same(6), 
-                    if ui.visuals().dark_mode { 
-                        egui::Color32::from_rgba_unmultiplied(255, 255, 255, 20) 
-                    } else { 
-                        egui::Color32::from_rgba_unmultiplied(0, 0, 0, 15) 
-                    }
-                );
+            // Las pestañas con cambios sin guardar conservan lo que se escribió
+            if tab.dirty || !changed.contains(path) {
+                continue;
             }
-            
-            if view_response.on_hover_text(tooltip).clicked() {
-                self.settings.show_raw_markdown = !self.settings.show_raw_markdown;
+            // Un guardado por renombre puede dejar el archivo ausente un instante,
+            // pero para cuando se cumple la espera ya está de vuelta
+            if path.exists() {
+                if let Err(e) = tab.reload() {
+                    eprintln!("Error al leer el archivo: {}", e);
+                }
+            } else {
+                tab.file_missing = true;
             }
-            
-            ui.add_space(8.0);
-            
-            // Botón configuración - estilo VSCode
-            let settings_response = ui.add_sized([40.0, 40.0], 
-                egui::Button::new(egui::RichText::new(egui_phosphor::regular::GEAR).size(18.0))
-                    .fill(egui::Color32::TRANSPARENT)
-                    .stroke(egui::Stroke::NONE)
-                    .corner_radius(egui::CornerRadius::same(6))
-            );
-            
-            if settings_response.hovered() {
-                ui.painter().rect_filled(
-                    settings_response.rect, 
-                    egui::CornerRadius::same(6), 
-                    if ui.visuals().dark_mode { 
-                        egui::Color32::from_rgba_unmultiplied(255, 255, 255, 20) 
-                    } else { 
-                        egui::Color32::from_rgba_unmultiplied(0, 0, 0, 15) 
-                    }
-                );
+        }
+        self.find.invalidate();
+    }
+
+    /// Exporta el documento activo a un HTML independiente con los colores
+    /// del tema actual.
+    fn export_html(&self, ctx: &egui::Context) {
+        let Some(tab) = self.active_tab() else {
+            return;
+        };
+        let title = tab.export_name();
+        if let Some(path) = FileDialog::new()
+            .add_filter("HTML", &["html", "htm"])
+            .set_file_name(format!("{}.html", title))
+            .save_file()
+        {
+            let theme = if ctx.style().visuals.dark_mode { ExportTheme::Dark } else { ExportTheme::Light };
+            let html = export::standalone_html(&tab.markdown_content, &title, &tab.base_dir(), theme);
+            if let Err(e) = std::fs::write(&path, html) {
+                eprintln!("Error al exportar el archivo: {}", e);
             }
-            
-            if settings_response.on_hover_text(self.translations.settings).clicked() {
-                self.show_settings = !self.show_settings;
+        }
+    }
+
+    /// Copia una parte del texto fuente de la pestaña activa.
+    fn copy_source(&mut self, range: Range<usize>, format: CopyFormat) {
+        let Some(tab) = self.tabs.get(self.active_tab) else {
+            return;
+        };
+        let Some(source) = tab.markdown_content.get(range) else {
+            return;
+        };
+        if let Err(e) = self.clipboard.copy(source, &tab.base_dir(), format) {
+            eprintln!("Error al copiar: {}", e);
+        }
+    }
+
+    /// Exporta el documento activo a PDF con el tamaño de página y los
+    /// márgenes de la configuración.
+    fn export_pdf(&self) {
+        let Some(tab) = self.active_tab() else {
+            return;
+        };
+        let title = tab.export_name();
+        if let Some(path) = FileDialog::new()
+            .add_filter("PDF", &["pdf"])
+            .set_file_name(format!("{}.pdf", title))
+            .save_file()
+        {
+            let result = pdf::export_pdf(&tab.document, &title, &self.settings.pdf)
+                .and_then(|bytes| std::fs::write(&path, bytes));
+            if let Err(e) = result {
+                eprintln!("Error al exportar el archivo: {}", e);
             }
-            
-            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                if let Some(path) = &self.current_file {
-                    ui.add_space(15.0);
-                    ui.horizontal(|ui| {
-                        ui.add(egui::Label::new(egui::RichText::new(egui_phosphor::regular::FILE_TEXT).size(14.0).color(egui::Color32::GRAY)));
-                        ui.add_space(6.0);
-                        ui.label(egui::RichText::new(path.file_name().unwrap_or_default().to_string_lossy()).size(14.0).color(egui::Color32::GRAY));
-                    });
-                }
-            });
-        });
+        }
     }
 
-    fn render_settings_panel(&mut self, ctx: &egui::Context) {
-        if !self.show_settings {
+    /// Exporta el documento activo, o solo lo que se ve de él, a una imagen
+    /// PNG con el ancho y la escala de la configuración.
+    fn export_png(&self, ctx: &egui::Context, visible: bool) {
+        let Some(tab) = self.active_tab() else {
             return;
+        };
+        let area = match tab.first_visible_block() {
+            Some((block, offset)) if visible => snapshot::Area::Viewport { block, offset, height: tab.viewport_height, width: tab.preview_width },
+            _ => snapshot::Area::Document,
+        };
+        let title = tab.export_name();
+        if let Some(path) = FileDialog::new()
+            .add_filter("PNG", &["png"])
+            .set_file_name(format!("{}.png", title))
+            .save_file()
+        {
+            let theme = if ctx.style().visuals.dark_mode { ExportTheme::Dark } else { ExportTheme::Light };
+            let result = snapshot::export_png(&tab.document, theme, area, &self.settings.png)
+                .and_then(|bytes| std::fs::write(&path, bytes));
+            if let Err(e) = result {
+                eprintln!("Error al exportar el archivo: {}", e);
+            }
         }
+    }
 
-        egui::Window::new(self.translations.settings_title)
-            .default_size([600.0, 400.0])
-            .resizable(true)
-            .collapsible(false)
-            .show(ctx, |ui| {
-                ui.horizontal(|ui| {
-                    // Panel izquierdo con secciones
-                    ui.vertical(|ui| {
-                        ui.set_width(150.0);
-                        ui.add_space(10.0);
-                        
-                        ui.heading(self.translations.sections_title);
-                        ui.add_space(15.0);
-                        
-                        // Botón Tema
-                        let theme_selected = self.selected_settings_section == SettingsSection::Theme;
-                        let theme_button = egui::Button::new(
-                            egui::RichText::new(format!("{} {}", egui_phosphor::regular::PALETTE, self.translations.theme_section)).size(15.0)
-                        ).corner_radius(egui::CornerRadius::same(12));
-                        
-                        let theme_button = if theme_selected {
-                            theme_button.fill(if ui.visuals().dark_mode { 
-                                egui::Color32::from_rgb(70, 130, 200) 
-                            } else { 
-                                egui::Color32::from_rgb(90, 150, 220) 
-                            })
-                        } else {
-                            theme_button
-                        };
-                        
-                        if ui.add_sized([150.0, 45.0], theme_button).clicked() {
-                            self.selected_settings_section = SettingsSection::Theme;
-                        }
-                        
-                        ui.add_space(8.0);
-                        
-                        // Botón Idioma
-                        let language_selected = self.selected_settings_section == SettingsSection::Language;
-                        let language_button = egui::Button::new(
-                            egui::RichText::new(format!("{} {}", egui_phosphor::regular::TRANSLATE, self.translations.language_section)).size(15.0)
-                        ).corner_radius(egui::CornerRadius::same(12));
-                        
-                        let language_button = if language_selected {
-                            language_button.fill(if ui.visuals().dark_mode { 
-                                egui::Color32::from_rgb(70, 130, 200) 
-                            } else { 
-                                egui::Color32::from_rgb(90, 150, 220) 
-                            })
-                        } else {
-                            language_button
-                        };
-                        
-                        if ui.add_sized([150.0, 45.0], language_button).clicked() {
-                            self.selected_settings_section = SettingsSection::Language;
+    /// Exporta como libro EPUB la carpeta abierta, o la que se elija si no
+    /// hay ninguna.
+    fn export_epub(&self) {
+        let folder = match &self.workspace {
+            Some(workspace) => Some(workspace.root.clone()),
+            None => FileDialog::new().pick_folder(),
+        };
+        let Some(folder) = folder else {
+            return;
+        };
+        let name = folder.file_name().map_or("book".into(), |name| name.to_string_lossy());
+        if let Some(path) = FileDialog::new()
+            .add_filter("EPUB", &["epub"])
+            .set_file_name(format!("{}.epub", name))
+            .save_file()
+        {
+            if let Err(e) = epub::export_epub_file(&folder, &path) {
+                eprintln!("Error al exportar el archivo: {}", e);
+            }
+        }
+    }
+
+    fn action_enabled(&self, action: Action) -> bool {
+        let has_tab = !self.tabs.is_empty();
+        match action {
+            Action::GoBack => self.active_tab().is_some_and(|tab| tab.history.can_go_back()),
+            Action::GoForward => self.active_tab().is_some_and(|tab| tab.history.can_go_forward()),
+            Action::Reload => self.active_tab().is_some_and(|tab| tab.current_file.is_some()),
+            Action::NextChange | Action::PreviousChange => self.active_tab().is_some_and(|tab| tab.changes.is_some()),
+            Action::Find | Action::ExportHtml | Action::ExportPdf | Action::ExportPng | Action::CopyAs(_) | Action::CloseTab | Action::ToggleEditMode | Action::SaveAs => has_tab,
+            Action::Save => self.active_tab().is_some_and(|tab| tab.dirty || tab.current_file.is_none()),
+            Action::NextTab | Action::PreviousTab => self.tabs.len() > 1,
+            Action::SearchWorkspace | Action::CloseWorkspace => self.workspace.is_some(),
+            // Solo hay área visible del documento renderizado
+            Action::ExportVisiblePng => self.active_tab().is_some_and(|tab| tab.view_mode != ViewMode::Raw),
+            _ => true,
+        }
+    }
+
+    fn run_action(&mut self, ctx: &egui::Context, action: Action) {
+        match action {
+            Action::OpenFile => self.open_file(),
+            Action::OpenFolder => self.open_folder(ctx),
+            Action::Save => {
+                self.save_tab(self.active_tab, false);
+            }
+            Action::SaveAs => {
+                self.save_tab(self.active_tab, true);
+            }
+            Action::ToggleEditMode => {
+                if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+                    // Se edita en la vista raw
+                    tab.editing = !tab.editing;
+                    if tab.editing {
+                        if tab.view_mode == ViewMode::Rendered {
+                            tab.view_mode = ViewMode::Raw;
                         }
+                        ctx.memory_mut(|memory| memory.request_focus(tab.raw_view_id()));
+                    }
+                }
+            }
+            Action::CloseWorkspace => self.workspace = None,
+            Action::QuickOpen => self.palette.show(""),
+            Action::CommandPalette => self.palette.show("> "),
+            Action::ToggleRawView => self.toggle_view_mode(ViewMode::Raw),
+            Action::ToggleSplitView => self.toggle_view_mode(ViewMode::Split),
+            Action::ToggleOutline => self.settings.show_outline = !self.settings.show_outline,
+            Action::Find => {
+                if !self.tabs.is_empty() {
+                    self.find.show();
+                }
+            }
+            Action::SearchWorkspace => {
+                if self.workspace.is_some() {
+                    self.workspace_view = WorkspaceView::Search;
+                    self.workspace_search.request_focus = true;
+                }
+            }
+            Action::OpenSettings => self.show_settings = !self.show_settings,
+            Action::GoBack => self.go_back(),
+            Action::GoForward => self.go_forward(),
+            Action::Reload => self.reload(),
+            Action::NextChange | Action::PreviousChange => {
+                if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+                    tab.step_change(action == Action::NextChange);
+                }
+            }
+            Action::ExportHtml => self.export_html(ctx),
+            Action::ExportPdf => self.export_pdf(),
+            Action::ExportPng => self.export_png(ctx, false),
+            Action::ExportVisiblePng => self.export_png(ctx, true),
+            Action::ExportEpub => self.export_epub(),
+            Action::CopyAs(format) => {
+                // Lo seleccionado o, si no hay nada, todo el documento
+                if let Some(tab) = self.active_tab() {
+                    let range = tab.selection(ctx).unwrap_or(0..tab.markdown_content.len());
+                    self.copy_source(range, format);
+                }
+            }
+            Action::CloseTab => self.close_tab(self.active_tab),
+            Action::NextTab => self.cycle_tab(true),
+            Action::PreviousTab => self.cycle_tab(false),
+            Action::ToggleFullscreen => {
+                let fullscreen = ctx.input(|i| i.viewport().fullscreen.unwrap_or(false));
+                ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(!fullscreen));
+            }
+            Action::ShowShortcuts => self.show_shortcuts = !self.show_shortcuts,
+            Action::ToggleVimNavigation => self.settings.vim_navigation = !self.settings.vim_navigation,
+            Action::SetTheme(theme) => {
+                self.settings.theme = theme;
+                self.apply_theme(ctx);
+            }
+            Action::SetLanguage(language) => {
+                self.settings.language = language;
+                self.update_translations();
+            }
+        }
+    }
+
+    /// Pasa a `mode`, o vuelve a la vista renderizada si ya estaba en ese modo.
+    /// El modo elegido también es el predeterminado para nuevas pestañas.
+    fn toggle_view_mode(&mut self, mode: ViewMode) {
+        let current = self.active_tab().map_or(self.settings.view_mode, |tab| tab.view_mode);
+        let mode = if current == mode { ViewMode::Rendered } else { mode };
+        self.settings.view_mode = mode;
+        if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+            tab.view_mode = mode;
+            // Al salir del código fuente no queda nada que editar
+            if mode == ViewMode::Rendered {
+                tab.editing = false;
+            }
+        }
+    }
+
+    fn run_pager_command(&mut self, command: PagerCommand) {
+        let Some(tab) = self.tabs.get_mut(self.active_tab) else {
+            return;
+        };
+        let raw = tab.view_mode.finds_in_source();
+        let page = tab.viewport_height;
+        match command {
+            PagerCommand::LineDown => tab.scroll_by(LINE_SCROLL),
+            PagerCommand::LineUp => tab.scroll_by(-LINE_SCROLL),
+            PagerCommand::HalfPageDown => tab.scroll_by(page / 2.0),
+            PagerCommand::HalfPageUp => tab.scroll_by(-page / 2.0),
+            PagerCommand::PageDown => tab.scroll_by(page - LINE_SCROLL),
+            PagerCommand::PageUp => tab.scroll_by(LINE_SCROLL - page),
+            PagerCommand::Top => tab.restore_scroll = Some(0.0),
+            PagerCommand::Bottom => tab.restore_scroll = Some(tab.max_scroll()),
+            PagerCommand::NextHeading => {
+                if let Some(offset) = tab.heading_offsets.iter().find(|offset| **offset > tab.scroll_offset + 1.0) {
+                    tab.restore_scroll = Some(offset.min(tab.max_scroll()));
+                }
+            }
+            PagerCommand::PreviousHeading => {
+                if let Some(offset) = tab.heading_offsets.iter().rfind(|offset| **offset < tab.scroll_offset - 1.0) {
+                    tab.restore_scroll = Some(*offset);
+                }
+            }
+            PagerCommand::Search => self.find.show(),
+            PagerCommand::NextMatch => self.find.next(raw),
+            PagerCommand::PreviousMatch => self.find.previous(raw),
+        }
+    }
+
+    /// Si la pestaña tiene cambios sin guardar, pide confirmación antes de
+    /// `action` y devuelve `false`. La operación se repite cuando el usuario
+    /// guarda o descarta los cambios.
+    fn confirm_discard(&mut self, index: usize, action: UnsavedAction) -> bool {
+        match self.tabs.get(index) {
+            Some(tab) if tab.dirty => {
+                self.unsaved_prompt = Some(UnsavedPrompt { tab: Some(tab.id), action });
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Guarda una pestaña en su archivo, o donde elija el usuario si no tiene
+    /// o se pidió "guardar como". Devuelve `false` si no se guardó.
+    fn save_tab(&mut self, index: usize, save_as: bool) -> bool {
+        let Some(tab) = self.tabs.get_mut(index) else {
+            return false;
+        };
+        let path = match &tab.current_file {
+            Some(path) if !save_as => path.clone(),
+            current => {
+                let mut dialog = FileDialog::new().add_filter("Markdown", &["md", "markdown", "txt"]);
+                if let Some(current) = current {
+                    if let Some(directory) = current.parent() {
+                        dialog = dialog.set_directory(directory);
+                    }
+                    dialog = dialog.set_file_name(tab.title());
+                }
+                match dialog.save_file() {
+                    Some(path) => path,
+                    None => return false,
+                }
+            }
+        };
+        match tab.save_to(path.clone()) {
+            Ok(()) => {
+                self.add_recent_file(&path);
+                true
+            }
+            Err(e) => {
+                eprintln!("Error al guardar el archivo: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Pregunta qué hacer con los cambios sin guardar antes de la operación pendiente.
+    fn render_unsaved_prompt(&mut self, ctx: &egui::Context) {
+        let Some(prompt) = &self.unsaved_prompt else {
+            return;
+        };
+        let index = prompt.tab.and_then(|id| self.tabs.iter().position(|tab| tab.id == id));
+        let message = match index {
+            Some(index) => self.translations.unsaved_message.replace("{name}", &self.tabs[index].title()),
+            None => {
+                let count = self.tabs.iter().filter(|tab| tab.dirty).count();
+                self.translations.unsaved_quit_message.replace("{count}", &count.to_string())
+            }
+        };
+        
+        #[derive(PartialEq)]
+        enum Choice {
+            Save,
+            Discard,
+            Cancel,
+        }
+        let mut choice = None;
+        let modal = egui::Modal::new(egui::Id::new("unsaved_prompt")).show(ctx, |ui| {
+            ui.set_max_width(380.0);
+            ui.heading(format!("{} {}", egui_phosphor::regular::WARNING, self.translations.unsaved_title));
+            ui.add_space(10.0);
+            ui.label(message);
+            ui.add_space(15.0);
+            ui.horizontal(|ui| {
+                if ui.button(self.translations.unsaved_save).clicked() {
+                    choice = Some(Choice::Save);
+                }
+                if ui.button(self.translations.unsaved_discard).clicked() {
+                    choice = Some(Choice::Discard);
+                }
+                if ui.button(self.translations.unsaved_cancel).clicked() {
+                    choice = Some(Choice::Cancel);
+                }
+            });
+        });
+        if modal.should_close() {
+            choice = Some(Choice::Cancel);
+        }
+        let Some(choice) = choice else {
+            return;
+        };
+        let Some(prompt) = self.unsaved_prompt.take() else {
+            return;
+        };
+        if choice == Choice::Cancel {
+            return;
+        }
+        
+        // Pestañas afectadas: la de la operación o, al salir, todas las modificadas
+        let affected: Vec<usize> = match (prompt.tab, index) {
+            (Some(_), Some(index)) => vec![index],
+            (Some(_), None) => return,
+            (None, _) => (0..self.tabs.len()).filter(|index| self.tabs[*index].dirty).collect(),
+        };
+        for index in affected {
+            if choice == Choice::Save {
+                if !self.save_tab(index, false) {
+                    return;
+                }
+            } else {
+                self.tabs[index].dirty = false;
+            }
+        }
+        
+        if let Some(index) = index {
+            self.active_tab = index;
+        }
+        match prompt.action {
+            UnsavedAction::CloseTab => {
+                if let Some(index) = index {
+                    self.close_tab(index);
+                }
+            }
+            UnsavedAction::FollowLink(link) => self.follow_link(ctx, &link),
+            UnsavedAction::GoBack => self.go_back(),
+            UnsavedAction::GoForward => self.go_forward(),
+            UnsavedAction::Reload => self.reload(),
+            UnsavedAction::ReopenWithEncoding(encoding) => self.reopen_with_encoding(encoding),
+            UnsavedAction::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+        }
+    }
+
+    /// Guarda la posición de lectura del archivo de una pestaña.
+    fn remember_scroll(&mut self, index: usize) {
+        let Some(tab) = self.tabs.get(index) else {
+            return;
+        };
+        if let (Some(path), Some(position)) = (&tab.current_file, tab.current_position()) {
+            self.scroll_memory.remember(path, position);
+        }
+    }
+
+    fn active_tab(&self) -> Option<&DocumentTab> {
+        self.tabs.get(self.active_tab)
+    }
+
+    fn select_tab(&mut self, index: usize) {
+        if index < self.tabs.len() {
+            self.active_tab = index;
+            self.find.invalidate();
+        }
+    }
+
+    fn close_tab(&mut self, index: usize) {
+        if index >= self.tabs.len() || !self.confirm_discard(index, UnsavedAction::CloseTab) {
+            return;
+        }
+        self.remember_scroll(index);
+        self.tabs.remove(index);
+        if self.active_tab > index || self.active_tab >= self.tabs.len() {
+            self.active_tab = self.active_tab.saturating_sub(1);
+        }
+        self.find.invalidate();
+        if self.tabs.is_empty() {
+            self.find.close();
+        }
+    }
+
+    fn cycle_tab(&mut self, forward: bool) {
+        let count = self.tabs.len();
+        if count > 1 {
+            let next = if forward {
+                (self.active_tab + 1) % count
+            } else {
+                (self.active_tab + count - 1) % count
+            };
+            self.select_tab(next);
+        }
+    }
+
+    fn move_tab(&mut self, from: usize, to: usize) {
+        if from == to || from >= self.tabs.len() || to >= self.tabs.len() {
+            return;
+        }
+        let active_id = self.tabs[self.active_tab].id;
+        let tab = self.tabs.remove(from);
+        self.tabs.insert(to, tab);
+        self.active_tab = self.tabs.iter().position(|tab| tab.id == active_id).unwrap_or(0);
+    }
+
+    fn apply_theme(&self, ctx: &egui::Context) {
+        match self.settings.theme {
+            Theme::Light => ctx.set_visuals(egui::Visuals::light()),
+            Theme::Dark => ctx.set_visuals(egui::Visuals::dark()),
+            Theme::System => {
+                // Usar el tema del sistema (por defecto dark en este caso)
+                ctx.set_visuals(egui::Visuals::dark());
+            }
+        }
+    }
+
+    fn update_translations(&mut self) {
+        self.translations = Translations::new(&self.settings.language);
+    }
+
+    fn toolbar_button(ui: &mut egui::Ui, icon: &str) -> egui::Response {
+        let response = ui.add_sized([40.0, 40.0], 
+            egui::Button::new(egui::RichText::new(icon).size(18.0))
+                .fill(egui::Color32::TRANSPARENT)
+                .stroke(egui::Stroke::NONE)
+                .corner_radius(egui::CornerRadius::same(6))
+        );
+        
+        if response.hovered() {
+            ui.painter().rect_filled(
+                response.rect, 
+                egui::CornerRadius::same(6), 
+                if ui.visuals().dark_mode { 
+                    egui::Color32::from_rgba_unmultiplied(255, 255, 255, 20) 
+                } else { 
+                    egui::Color32::from_rgba_unmultiplied(0, 0, 0, 15) 
+                }
+            );
+        }
+        
+        response
+    }
+
+    /// Botón de la barra de herramientas que ejecuta una acción.
+    fn toolbar_action(&mut self, ui: &mut egui::Ui, action: Action) {
+        let enabled = self.action_enabled(action);
+        let response = ui.add_enabled_ui(enabled, |ui| Self::toolbar_button(ui, action.icon())).inner;
+        if response.on_hover_text(action.tooltip(&self.translations, &self.settings.keybindings)).clicked() {
+            self.run_action(ui.ctx(), action);
+        }
+    }
+
+    fn render_toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.add_space(15.0);
+            
+            // Navegación atrás/adelante
+            self.toolbar_action(ui, Action::GoBack);
+            self.toolbar_action(ui, Action::GoForward);
+            
+            ui.add_space(8.0);
+            
+            // Botón abrir archivo - estilo VSCode
+            self.toolbar_action(ui, Action::OpenFile);
+            
+            ui.add_space(8.0);
+            
+            // Abrir carpeta como espacio de trabajo
+            self.toolbar_action(ui, Action::OpenFolder);
+            
+            ui.add_space(8.0);
+            
+            // Archivos recientes
+            let recent_response = Self::toolbar_button(ui, egui_phosphor::regular::CLOCK_COUNTER_CLOCKWISE).on_hover_text(self.translations.recent_files);
+            let recent_popup = ui.make_persistent_id("recent_files_popup");
+            if recent_response.clicked() {
+                ui.memory_mut(|memory| memory.toggle_popup(recent_popup));
+            }
+            let mut open_recent = None;
+            egui::popup_below_widget(ui, recent_popup, &recent_response, egui::PopupCloseBehavior::CloseOnClick, |ui| {
+                ui.set_min_width(280.0);
+                open_recent = self.render_recent_files_list(ui);
+            });
+            if let Some(path) = open_recent {
+                self.open_file_from_path(path);
+            }
+            
+            ui.add_space(8.0);
+            
+            // Toggle vista raw - estilo VSCode
+            let show_raw = self.active_tab().map_or(self.settings.view_mode, |tab| tab.view_mode) == ViewMode::Raw;
+            let (icon, tooltip) = if show_raw {
+                (egui_phosphor::regular::EYE, self.translations.view_normal)
+            } else {
+                (egui_phosphor::regular::CODE, self.translations.view_raw)
+            };
+            
+            if Self::toolbar_button(ui, icon).on_hover_text(tooltip).clicked() {
+                self.run_action(ui.ctx(), Action::ToggleRawView);
+            }
+            
+            // Código fuente y vista renderizada lado a lado
+            self.toolbar_action(ui, Action::ToggleSplitView);
+            
+            ui.add_space(8.0);
+            
+            // Modo edición y guardar
+            self.toolbar_action(ui, Action::ToggleEditMode);
+            if self.active_tab().is_some_and(|tab| tab.editing) {
+                self.toolbar_action(ui, Action::Save);
+            }
+            
+            ui.add_space(8.0);
+            
+            // Toggle índice lateral
+            self.toolbar_action(ui, Action::ToggleOutline);
+            
+            ui.add_space(8.0);
+            
+            // Navegación entre los cambios de la última recarga
+            if self.action_enabled(Action::NextChange) {
+                self.toolbar_action(ui, Action::PreviousChange);
+                self.toolbar_action(ui, Action::NextChange);
+                
+                ui.add_space(8.0);
+            }
+            
+            // Botón configuración - estilo VSCode
+            self.toolbar_action(ui, Action::OpenSettings);
+            
+            let mut reopen_encoding = None;
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if let Some(path) = self.active_tab().and_then(|tab| tab.current_file.as_ref()) {
+                    ui.add_space(15.0);
+                    ui.horizontal(|ui| {
+                        ui.add(egui::Label::new(egui::RichText::new(egui_phosphor::regular::FILE_TEXT).size(14.0).color(egui::Color32::GRAY)));
+                        ui.add_space(6.0);
+                        ui.label(egui::RichText::new(path.file_name().unwrap_or_default().to_string_lossy()).size(14.0).color(egui::Color32::GRAY));
+                    });
+
+                    // Codificación detectada, con opción de reabrir con otra
+                    if let Some(current) = self.active_tab().and_then(|tab| tab.current_encoding) {
+                        ui.add_space(12.0);
+                        ui.menu_button(egui::RichText::new(current.label()).size(12.0).color(egui::Color32::GRAY), |ui| {
+                            ui.label(egui::RichText::new(self.translations.reopen_with_encoding).strong());
+                            ui.separator();
+                            for encoding in encoding::SELECTABLE_ENCODINGS {
+                                if ui.selectable_label(current.encoding == *encoding, encoding.name()).clicked() {
+                                    reopen_encoding = Some(*encoding);
+                                    ui.close_menu();
+                                }
+                            }
+                        }).response.on_hover_text(self.translations.encoding_tooltip);
+                    }
+                }
+            });
+
+            if let Some(encoding) = reopen_encoding {
+                self.reopen_with_encoding(encoding);
+            }
+        });
+    }
+
+    fn render_tab_strip(&mut self, ctx: &egui::Context) {
+        if self.tabs.is_empty() {
+            return;
+        }
+        
+        let mut select = None;
+        let mut close = None;
+        let mut reorder = None;
+        
+        egui::TopBottomPanel::top("tabs").show(ctx, |ui| {
+            egui::ScrollArea::horizontal().show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add_space(15.0);
+                    for (index, tab) in self.tabs.iter().enumerate() {
+                        let drag_id = egui::Id::new(("document_tab", tab.id));
+                        let is_active = index == self.active_tab;
                         
-                        ui.add_space(8.0);
-                        
-                        // Botón Acerca de
-                        let about_selected = self.selected_settings_section == SettingsSection::About;
-                        let about_button = egui::Button::new(
-                            egui::RichText::new(format!("{} {}", egui_phosphor::regular::INFO, self.translations.about_section)).size(15.0)
-                        ).corner_radius(egui::CornerRadius::same(12));
+                        let response = ui.dnd_drag_source(drag_id, index, |ui| {
+                            ui.horizontal(|ui| {
+                                let icon = if tab.editing { egui_phosphor::regular::PENCIL_SIMPLE } else { egui_phosphor::regular::FILE_TEXT };
+                                let title = if tab.dirty { format!("{} {} ●", icon, tab.title()) } else { format!("{} {}", icon, tab.title()) };
+                                let label = ui.selectable_label(is_active, title);
+                                if label.clicked() {
+                                    select = Some(index);
+                                }
+                                if label.middle_clicked() {
+                                    close = Some(index);
+                                }
+                                if ui.small_button(egui_phosphor::regular::X).on_hover_text(self.translations.close_tab).clicked() {
+                                    close = Some(index);
+                                }
+                            });
+                        }).response;
                         
-                        let about_button = if about_selected {
-                            about_button.fill(if ui.visuals().dark_mode { 
-                                egui::Color32::from_rgb(70, 130, 200) 
-                            } else { 
-                                egui::Color32::from_rgb(90, 150, 220) 
-                            })
-                        } else {
-                            about_button
-                        };
+                        // Soltar una pestaña arrastrada sobre otra la mueve a esa posición
+                        if let Some(from) = response.dnd_release_payload::<usize>() {
+                            reorder = Some((*from, index));
+                        }
+                        if response.middle_clicked() {
+                            close = Some(index);
+                        }
                         
-                        if ui.add_sized([150.0, 45.0], about_button).clicked() {
-                            self.selected_settings_section = SettingsSection::About;
+                        ui.add_space(4.0);
+                    }
+                });
+            });
+        });
+        
+        if let Some((from, to)) = reorder {
+            self.move_tab(from, to);
+        }
+        if let Some(index) = select {
+            self.select_tab(index);
+        }
+        if let Some(index) = close {
+            self.close_tab(index);
+        }
+    }
+
+    fn render_workspace(&mut self, ctx: &egui::Context) {
+        let Some(workspace) = &self.workspace else {
+            return;
+        };
+        let active_file = self.tabs.get(self.active_tab).and_then(|tab| tab.current_file.clone());
+        let mut open = None;
+        let mut open_result = None;
+        let mut close = false;
+        
+        let panel = egui::SidePanel::left("workspace")
+            .resizable(true)
+            .default_width(self.settings.workspace_width)
+            .width_range(140.0..=500.0)
+            .show(ctx, |ui| {
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(format!("{} {}", egui_phosphor::regular::FOLDERS, workspace.name())).size(15.0).strong())
+                        .on_hover_text(workspace.root.display().to_string());
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.small_button(egui_phosphor::regular::X).on_hover_text(self.translations.close_workspace).clicked() {
+                            close = true;
                         }
                     });
+                });
+                ui.add_space(4.0);
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.workspace_view, WorkspaceView::Files, egui_phosphor::regular::TREE_STRUCTURE)
+                        .on_hover_text(self.translations.workspace_files);
+                    ui.selectable_value(&mut self.workspace_view, WorkspaceView::Search, egui_phosphor::regular::MAGNIFYING_GLASS)
+                        .on_hover_text(Action::SearchWorkspace.tooltip(&self.translations, &self.settings.keybindings));
+                });
+                ui.separator();
+                
+                if self.workspace_view == WorkspaceView::Search {
+                    open_result = render_workspace_search(ui, workspace, &mut self.workspace_search, &self.translations);
+                    return;
+                }
+                
+                if workspace.tree.is_empty() {
+                    ui.add_space(10.0);
+                    ui.label(egui::RichText::new(self.translations.workspace_empty).italics().color(egui::Color32::GRAY));
+                    return;
+                }
+                
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false; 2])
+                    .show(ui, |ui| {
+                        open = render_file_tree(ui, &workspace.tree, active_file.as_deref());
+                    });
+            });
+        
+        self.settings.workspace_width = panel.response.rect.width();
+        if close {
+            self.workspace = None;
+        }
+        if let Some(path) = open {
+            self.open_file_from_path(path);
+        }
+        if let Some((path, offset)) = open_result {
+            self.open_search_result(path, offset);
+        }
+    }
+
+    fn render_outline(&mut self, ctx: &egui::Context) {
+        let visible = self.settings.show_outline && !self.tabs.is_empty();
+        let Some(tab) = self.tabs.get_mut(self.active_tab) else {
+            return;
+        };
+        let mut target = None;
+        let mut copy = None;
+        
+        let panel = egui::SidePanel::left("outline")
+            .resizable(true)
+            .default_width(self.settings.outline_width)
+            .width_range(140.0..=500.0)
+            .show_animated(ctx, visible, |ui| {
+                ui.add_space(10.0);
+                ui.label(egui::RichText::new(format!("{} {}", egui_phosphor::regular::LIST_BULLETS, self.translations.outline_title)).size(15.0).strong());
+                ui.add_space(6.0);
+                ui.separator();
+                
+                if tab.document.headings.is_empty() {
+                    ui.add_space(10.0);
+                    ui.label(egui::RichText::new(self.translations.outline_empty).italics().color(egui::Color32::GRAY));
+                    return;
+                }
+                
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false; 2])
+                    .show(ui, |ui| {
+                        for (index, heading) in tab.document.headings.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.add_space((heading.level.saturating_sub(1)) as f32 * 12.0);
+                                let is_active = tab.active_heading == Some(index);
+                                let text = egui::RichText::new(&heading.text).size(if heading.level == 1 { 14.0 } else { 13.0 });
+                                let response = ui.selectable_label(is_active, text);
+                                
+                                // Mantener visible la entrada activa mientras se desplaza el documento
+                                if is_active && tab.outline_scrolled_to != Some(index) {
+                                    response.scroll_to_me(None);
+                                    tab.outline_scrolled_to = Some(index);
+                                }
+                                if response.clicked() {
+                                    target = Some(heading.block);
+                                }
+                                response.context_menu(|ui| {
+                                    for format in CopyFormat::ALL {
+                                        let label = format!("{} {} {}", egui_phosphor::regular::COPY, self.translations.copy_section_as, format.name(&self.translations));
+                                        if ui.button(label).clicked() {
+                                            copy = Some((heading.block, format));
+                                            ui.close_menu();
+                                        }
+                                    }
+                                });
+                            });
+                        }
+                    });
+            });
+        
+        if let Some(panel) = panel {
+            self.settings.outline_width = panel.response.rect.width();
+        }
+        if let Some(block) = target {
+            tab.jump_to_block(block);
+        }
+        if let Some((block, format)) = copy {
+            let range = tab.section_range(block);
+            self.copy_source(range, format);
+        }
+    }
+
+    /// Entradas de la paleta según el modo: archivos, encabezados o comandos.
+    fn palette_items(&self) -> Vec<PaletteItem> {
+        match self.palette.mode() {
+            PaletteMode::Commands => Action::ALL.iter()
+                .filter(|action| **action != Action::CommandPalette && self.action_enabled(**action))
+                .map(|action| PaletteItem::new(
+                    format!("{} {}", action.icon(), action.label(&self.translations)),
+                    self.settings.keybindings.shortcut(*action).map(|shortcut| shortcut.to_string()).unwrap_or_default(),
+                    PaletteTarget::Action(*action),
+                ))
+                .collect(),
+            PaletteMode::Headings => self.active_tab()
+                .map(|tab| tab.document.headings.iter()
+                    .map(|heading| PaletteItem::new(
+                        heading.text.clone(),
+                        format!("H{}", heading.level),
+                        PaletteTarget::Heading(heading.block),
+                    ))
+                    .collect())
+                .unwrap_or_default(),
+            PaletteMode::Files => {
+                let mut items = Vec::new();
+                if let Some(workspace) = &self.workspace {
+                    items.extend(workspace.files.iter().map(|path| PaletteItem::new(
+                        workspace_search::display_path(&workspace.root, path),
+                        String::new(),
+                        PaletteTarget::File(path.clone()),
+                    )));
+                }
+                // Archivos recientes que no están en la carpeta abierta
+                let in_workspace = |path: &PathBuf| self.workspace.as_ref().is_some_and(|workspace| workspace.files.contains(path));
+                items.extend(self.settings.recent_files.iter().filter(|path| !in_workspace(path)).map(|path| PaletteItem::new(
+                    path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                    path.parent().map(|parent| parent.display().to_string()).unwrap_or_default(),
+                    PaletteTarget::File(path.clone()),
+                )));
+                items
+            }
+        }
+    }
+
+    fn render_command_palette(&mut self, ctx: &egui::Context) {
+        if !self.palette.open {
+            return;
+        }
+        
+        let items = palette::filter_items(self.palette_items(), self.palette.search_text());
+        self.palette.selected = self.palette.selected.min(items.len().saturating_sub(1));
+        
+        // Flechas, Enter y Esc se atienden antes de que los reciba el campo de texto
+        let (up, down, enter, escape) = ctx.input_mut(|i| (
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+        ));
+        if escape {
+            self.palette.close();
+            return;
+        }
+        if up {
+            self.palette.select_previous(items.len());
+        }
+        if down {
+            self.palette.select_next(items.len());
+        }
+        let mut chosen = if enter { items.get(self.palette.selected).map(|item| item.target.clone()) } else { None };
+        
+        let area = egui::Area::new(egui::Id::new("command_palette"))
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(560.0);
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut self.palette.query)
+                            .hint_text(self.translations.palette_placeholder)
+                            .desired_width(f32::INFINITY)
+                    );
+                    if self.palette.request_focus {
+                        response.request_focus();
+                        self.palette.request_focus = false;
+                    }
+                    if response.changed() {
+                        self.palette.selected = 0;
+                    }
+                    ui.separator();
+                    
+                    if items.is_empty() {
+                        ui.label(egui::RichText::new(self.translations.find_no_results).italics().color(egui::Color32::GRAY));
+                        return;
+                    }
+                    
+                    let font_id = egui::TextStyle::Body.resolve(ui.style());
+                    egui::ScrollArea::vertical()
+                        .max_height(360.0)
+                        .show(ui, |ui| {
+                            for (index, item) in items.iter().enumerate() {
+                                let highlights: Vec<render::Highlight> = item.matched.iter()
+                                    .map(|range| render::Highlight { range: range.clone(), current: false })
+                                    .collect();
+                                let mut job = render::highlight_job(ui, &item.label, &highlights, font_id.clone());
+                                if !item.detail.is_empty() {
+                                    job.append(&item.detail, 12.0, egui::TextFormat::simple(font_id.clone(), egui::Color32::GRAY));
+                                }
+                                
+                                let is_selected = index == self.palette.selected;
+                                let response = ui.add_sized(
+                                    [ui.available_width(), 24.0],
+                                    egui::SelectableLabel::new(is_selected, job),
+                                );
+                                if is_selected && (up || down) {
+                                    response.scroll_to_me(None);
+                                }
+                                if response.clicked() {
+                                    chosen = Some(item.target.clone());
+                                }
+                            }
+                        });
+                });
+            });
+        
+        if area.response.clicked_elsewhere() {
+            self.palette.close();
+        }
+        if let Some(target) = chosen {
+            self.palette.close();
+            match target {
+                PaletteTarget::File(path) => self.open_file_from_path(path),
+                PaletteTarget::Heading(block) => {
+                    if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+                        tab.jump_to_block(block);
+                    }
+                }
+                PaletteTarget::Action(action) => self.run_action(ctx, action),
+            }
+        }
+    }
+
+    fn render_file_notice(&mut self, ctx: &egui::Context) {
+        let Some(tab) = self.tabs.get_mut(self.active_tab) else {
+            return;
+        };
+        if !tab.file_missing {
+            return;
+        }
+        
+        egui::TopBottomPanel::top("file_notice").show(ctx, |ui| {
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                ui.add_space(15.0);
+                ui.label(egui::RichText::new(egui_phosphor::regular::WARNING).size(16.0).color(egui::Color32::from_rgb(230, 160, 40)));
+                ui.label(self.translations.file_missing_notice);
+                if ui.button(self.translations.file_missing_dismiss).clicked() {
+                    tab.file_missing = false;
+                }
+            });
+            ui.add_space(4.0);
+        });
+    }
+
+    fn render_find_bar(&mut self, ctx: &egui::Context) {
+        if !self.find.open {
+            return;
+        }
+        
+        let raw = self.active_tab().is_some_and(|tab| tab.view_mode.finds_in_source());
+        egui::TopBottomPanel::top("find_bar").show(ctx, |ui| {
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                ui.add_space(15.0);
+                ui.label(egui::RichText::new(egui_phosphor::regular::MAGNIFYING_GLASS).size(16.0));
+                
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.find.query)
+                        .hint_text(self.translations.find_placeholder)
+                        .desired_width(260.0)
+                );
+                if self.find.request_focus {
+                    response.request_focus();
+                    self.find.request_focus = false;
+                }
+                
+                // Enter: siguiente, Shift+Enter: anterior
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    if ui.input(|i| i.modifiers.shift) {
+                        self.find.previous(raw);
+                    } else {
+                        self.find.next(raw);
+                    }
+                    response.request_focus();
+                }
+                
+                ui.toggle_value(&mut self.find.options.case_sensitive, "Aa").on_hover_text(self.translations.find_case_sensitive);
+                ui.toggle_value(&mut self.find.options.whole_word, "|ab|").on_hover_text(self.translations.find_whole_word);
+                ui.toggle_value(&mut self.find.options.regex, ".*").on_hover_text(self.translations.find_regex);
+                
+                ui.add_space(8.0);
+                let status = if self.find.invalid_regex {
+                    self.translations.find_invalid_regex.to_string()
+                } else if let Some(current) = self.find.current_index(raw) {
+                    format!("{}/{}", current + 1, self.find.match_count(raw))
+                } else if self.find.query.is_empty() {
+                    String::new()
+                } else {
+                    self.translations.find_no_results.to_string()
+                };
+                ui.label(egui::RichText::new(status).color(egui::Color32::GRAY));
+                
+                if ui.button(egui_phosphor::regular::ARROW_UP).on_hover_text(self.translations.find_previous).clicked() {
+                    self.find.previous(raw);
+                }
+                if ui.button(egui_phosphor::regular::ARROW_DOWN).on_hover_text(self.translations.find_next).clicked() {
+                    self.find.next(raw);
+                }
+                if ui.button(egui_phosphor::regular::X).on_hover_text(self.translations.find_close).clicked() {
+                    self.find.close();
+                }
+            });
+            ui.add_space(4.0);
+        });
+    }
+
+    /// Lista de archivos recientes. Devuelve el archivo elegido.
+    fn render_recent_files_list(&self, ui: &mut egui::Ui) -> Option<PathBuf> {
+        if self.settings.recent_files.is_empty() {
+            ui.label(egui::RichText::new(self.translations.no_recent_files).italics().color(egui::Color32::GRAY));
+            return None;
+        }
+        
+        let mut selected = None;
+        for path in &self.settings.recent_files {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let response = ui.add(egui::Button::new(format!("{} {}", egui_phosphor::regular::FILE_TEXT, name)).frame(false))
+                .on_hover_text(path.display().to_string());
+            if response.clicked() {
+                selected = Some(path.clone());
+            }
+        }
+        selected
+    }
+
+    fn render_welcome(&mut self, ui: &mut egui::Ui) {
+        let full_width = ui.available_width();
+        let avail_height = ui.available_height();
+        let top_space = (avail_height * 0.18).clamp(60.0, 260.0);
+        ui.allocate_ui_with_layout(
+            egui::Vec2::new(full_width, 0.0),
+            egui::Layout::top_down(egui::Align::Center),
+            |ui| {
+                ui.add_space(top_space);
+                ui.centered_and_justified(|ui| {
+                    let title_text = format!("{} {}", egui_phosphor::regular::BOOK_OPEN, self.translations.welcome_title);
+                    ui.add(egui::Label::new(
+                        egui::RichText::new(title_text)
+                            .size(32.0)
+                            .strong()
+                            .color(egui::Color32::from_rgb(51, 102, 153))
+                    ));
+                });
+                ui.add_space(30.0);
+                ui.add(egui::Label::new(
+                    egui::RichText::new(self.translations.welcome_subtitle)
+                        .size(16.0)
+                        .color(egui::Color32::GRAY)
+                ));
+                ui.add_space(20.0);
+                ui.add(egui::Label::new(
+                    egui::RichText::new(self.translations.welcome_drag_drop)
+                        .size(14.0)
+                        .italics()
+                        .color(egui::Color32::GRAY)
+                ));
+                ui.add_space(30.0);
+                if ui.add_sized([160.0, 45.0], 
+                    egui::Button::new(
+                        egui::RichText::new(format!("{} {}", egui_phosphor::regular::FOLDER_OPEN, self.translations.open_file_button))
+                            .size(16.0)
+                    ).corner_radius(egui::CornerRadius::same(12))
+                ).clicked() {
+                    self.open_file();
+                }
+                ui.add_space(10.0);
+                if ui.add_sized([160.0, 45.0], 
+                    egui::Button::new(
+                        egui::RichText::new(format!("{} {}", egui_phosphor::regular::FOLDERS, self.translations.open_folder_button))
+                            .size(16.0)
+                    ).corner_radius(egui::CornerRadius::same(12))
+                ).clicked() {
+                    self.open_folder(ui.ctx());
+                }
+                
+                // Archivos recientes
+                if !self.settings.recent_files.is_empty() {
+                    ui.add_space(40.0);
+                    ui.label(egui::RichText::new(format!("{} {}", egui_phosphor::regular::CLOCK_COUNTER_CLOCKWISE, self.translations.recent_files)).size(16.0).strong());
+                    ui.add_space(10.0);
+                    if let Some(path) = self.render_recent_files_list(ui) {
+                        self.open_file_from_path(path);
+                    }
+                }
+            }
+        );
+    }
+
+    fn render_document_view(&mut self, ui: &mut egui::Ui) {
+        let Some(tab) = self.tabs.get_mut(self.active_tab) else {
+            return;
+        };
+        
+        // En la vista dividida, la posición de la pestaña es la del panel renderizado
+        let restore = tab.restore_scroll.take();
+        let id = tab.id;
+        let (primary, secondary) = match tab.view_mode {
+            ViewMode::Rendered => (show_preview_pane(ui, tab, &self.find, ("document", id, Pane::Preview), restore), None),
+            ViewMode::Raw => (show_source_pane(ui, tab, &self.find, ("document", id, Pane::Source), restore, true), None),
+            ViewMode::Split => {
+                let (source, preview) = show_split_view(ui, tab, &self.find, &mut self.settings.split_ratio, restore);
+                (preview, Some(source))
+            }
+        };
+        
+        if primary.edited || secondary.as_ref().is_some_and(|pane| pane.edited) {
+            self.find.invalidate();
+        }
+        tab.scroll_offset = primary.offset;
+        tab.viewport_top = primary.inner_rect.top();
+        tab.viewport_height = primary.inner_rect.height();
+        tab.content_height = primary.content_height;
+        
+        // Encabezados en coordenadas del contenido; el activo es el último que
+        // ya pasó por el borde superior
+        tab.heading_offsets = primary.heading_tops.iter()
+            .map(|top| primary.content_y(*top))
+            .collect();
+        tab.active_heading = tab.heading_offsets.iter().rposition(|offset| *offset <= tab.scroll_offset + 40.0);
+        
+        // Marcas de los bloques que cambiaron en la última recarga, que se desvanecen
+        if let Some(changes) = &mut tab.changes {
+            let opacity = changes.opacity();
+            if opacity > 0.0 {
+                for pane in std::iter::once(&primary).chain(&secondary) {
+                    let painter = ui.painter_at(pane.inner_rect);
+                    render::paint_change_markers(&painter, pane.inner_rect.left() + 6.0, &pane.block_spans, &changes.changes, opacity);
+                }
+                ui.ctx().request_repaint();
+            }
+        }
+        
+        // La posición recordada se resuelve cuando ya se sabe dónde quedó cada encabezado
+        if let Some(position) = tab.restore_position.take() {
+            tab.restore_scroll = Some(tab.resolve_position(&position));
+            ui.ctx().request_repaint();
+        }
+        
+        if let Some(pane) = std::iter::once(&primary).chain(&secondary).find(|pane| pane.context_click.is_some()) {
+            if let Some((block, position)) = pane.context_click {
+                self.block_menu = Some(BlockMenu { tab: id, block, pane: pane.pane, position });
+            }
+        }
+        self.render_block_menu(ui.ctx());
+        
+        if let Some(link) = primary.clicked_link {
+            self.follow_link(ui.ctx(), &link);
+        }
+    }
+
+    fn render_block_menu(&mut self, ctx: &egui::Context) {
+        let Some(menu) = &self.block_menu else {
+            return;
+        };
+        let Some(tab) = self.tabs.get_mut(self.active_tab).filter(|tab| tab.id == menu.tab) else {
+            self.block_menu = None;
+            return;
+        };
+        
+        // Se copia lo seleccionado en el panel; si no hay selección, la sección del bloque
+        let selection = match menu.pane {
+            Pane::Source => tab.source_selection(ctx),
+            Pane::Preview => tab.preview_selection_range(ctx),
+        };
+        let copy_label = if selection.is_some() { self.translations.copy_selection_as } else { self.translations.copy_section_as };
+        
+        let mut chosen = false;
+        let mut copy = None;
+        let area = egui::Area::new(egui::Id::new("block_menu"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(menu.position)
+            .show(ctx, |ui| {
+                egui::Frame::menu(ui.style()).show(ui, |ui| {
+                    let label = match menu.pane {
+                        Pane::Preview => format!("{} {}", egui_phosphor::regular::CODE, self.translations.show_source),
+                        Pane::Source => format!("{} {}", egui_phosphor::regular::EYE, self.translations.reveal_in_preview),
+                    };
+                    chosen = ui.button(label).clicked();
+                    ui.separator();
+                    for format in CopyFormat::ALL {
+                        let label = format!("{} {} {}", egui_phosphor::regular::COPY, copy_label, format.name(&self.translations));
+                        if ui.button(label).clicked() {
+                            copy = Some(format);
+                        }
+                    }
+                });
+            });
+        
+        if let Some(format) = copy {
+            let range = selection.unwrap_or_else(|| tab.section_range(menu.block));
+            self.block_menu = None;
+            self.copy_source(range, format);
+            return;
+        }
+        if chosen {
+            match menu.pane {
+                // Pasa a una vista con el código fuente, si no se veía
+                Pane::Preview => {
+                    if tab.view_mode == ViewMode::Rendered {
+                        tab.view_mode = ViewMode::Raw;
+                    }
+                    tab.show_block_source(menu.block);
+                }
+                Pane::Source => {
+                    if tab.view_mode == ViewMode::Raw {
+                        tab.view_mode = ViewMode::Rendered;
+                    }
+                    tab.reveal_in_preview(menu.block);
+                }
+            }
+            self.block_menu = None;
+            return;
+        }
+        
+        // Un clic fuera lo cierra; otro clic derecho ya lo movió a otra parte
+        let escape = ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape));
+        let reopened = ctx.input(|i| i.pointer.secondary_clicked());
+        if escape || (area.response.clicked_elsewhere() && !reopened) {
+            self.block_menu = None;
+        }
+    }
+
+    fn render_settings_panel(&mut self, ctx: &egui::Context) {
+        if !self.show_settings {
+            return;
+        }
+
+        egui::Window::new(self.translations.settings_title)
+            .default_size([600.0, 400.0])
+            .resizable(true)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    // Panel izquierdo con secciones
+                    ui.vertical(|ui| {
+                        ui.set_width(150.0);
+                        ui.add_space(10.0);
+                        
+                        ui.heading(self.translations.sections_title);
+                        ui.add_space(15.0);
+                        
+                        self.settings_section_button(ui, SettingsSection::Theme, egui_phosphor::regular::PALETTE, self.translations.theme_section);
+                        ui.add_space(8.0);
+                        self.settings_section_button(ui, SettingsSection::Language, egui_phosphor::regular::TRANSLATE, self.translations.language_section);
+                        ui.add_space(8.0);
+                        self.settings_section_button(ui, SettingsSection::Files, egui_phosphor::regular::FILES, self.translations.files_section);
+                        ui.add_space(8.0);
+                        self.settings_section_button(ui, SettingsSection::Keybindings, egui_phosphor::regular::KEYBOARD, self.translations.keybindings_section);
+                        ui.add_space(8.0);
+                        self.settings_section_button(ui, SettingsSection::Export, egui_phosphor::regular::EXPORT, self.translations.export_section);
+                        ui.add_space(8.0);
+                        self.settings_section_button(ui, SettingsSection::About, egui_phosphor::regular::INFO, self.translations.about_section);
+                    });
                     
                     ui.separator();
                     
@@ -573,6 +2427,9 @@ impl MarkdownViewer {
                         match self.selected_settings_section {
                             SettingsSection::Theme => self.render_theme_settings(ui, ctx),
                             SettingsSection::Language => self.render_language_settings(ui, ctx),
+                            SettingsSection::Files => self.render_files_settings(ui),
+                            SettingsSection::Keybindings => self.render_keybindings_settings(ui),
+                            SettingsSection::Export => self.render_export_settings(ui),
                             SettingsSection::About => self.render_about_section(ui),
                         }
                     });
@@ -602,6 +2459,26 @@ impl MarkdownViewer {
             });
     }
 
+    fn settings_section_button(&mut self, ui: &mut egui::Ui, section: SettingsSection, icon: &str, label: &str) {
+        let button = egui::Button::new(
+            egui::RichText::new(format!("{} {}", icon, label)).size(15.0)
+        ).corner_radius(egui::CornerRadius::same(12));
+        
+        let button = if self.selected_settings_section == section {
+            button.fill(if ui.visuals().dark_mode { 
+                egui::Color32::from_rgb(70, 130, 200) 
+            } else { 
+                egui::Color32::from_rgb(90, 150, 220) 
+            })
+        } else {
+            button
+        };
+        
+        if ui.add_sized([150.0, 45.0], button).clicked() {
+            self.selected_settings_section = section;
+        }
+    }
+
     fn render_theme_settings(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
         ui.heading(format!("{} {}", egui_phosphor::regular::PALETTE, self.translations.theme_config_title));
         ui.add_space(20.0);
@@ -678,28 +2555,194 @@ impl MarkdownViewer {
             }
         });
         
-        ui.add_space(20.0);
-        ui.separator();
+        ui.add_space(20.0);
+        ui.separator();
+        ui.add_space(10.0);
+        
+        // Vista previa del idioma
+        ui.label(self.translations.preview_label);
+        ui.add_space(5.0);
+        
+        egui::Frame::new()
+            .fill(ui.visuals().window_fill())
+            .stroke(ui.visuals().window_stroke())
+            .inner_margin(15.0)
+            .show(ui, |ui| {
+                ui.label(egui::RichText::new(self.translations.welcome_title).size(20.0).strong());
+                ui.add_space(5.0);
+                ui.label(self.translations.welcome_subtitle);
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(egui_phosphor::regular::DOT_OUTLINE).strong());
+                    ui.label(self.translations.about_feature_interface);
+                });
+            });
+    }
+
+    fn render_files_settings(&mut self, ui: &mut egui::Ui) {
+        ui.heading(format!("{} {}", egui_phosphor::regular::FILES, self.translations.files_config_title));
+        ui.add_space(20.0);
+        
+        ui.horizontal(|ui| {
+            ui.label(self.translations.recent_files_limit);
+            if ui.add(egui::DragValue::new(&mut self.settings.max_recent_files).range(1..=50)).changed() {
+                self.settings.recent_files.truncate(self.settings.max_recent_files);
+            }
+        });
+        ui.add_space(10.0);
+        
+        ui.checkbox(&mut self.settings.restore_session, self.translations.restore_session);
+        ui.add_space(10.0);
+        
+        ui.checkbox(&mut self.settings.auto_reload, self.translations.auto_reload);
+        ui.add_space(20.0);
+        
+        if ui.add_enabled(!self.settings.recent_files.is_empty(), egui::Button::new(
+            format!("{} {}", egui_phosphor::regular::TRASH, self.translations.clear_recent_files)
+        ).corner_radius(egui::CornerRadius::same(10))).clicked() {
+            self.settings.recent_files.clear();
+        }
+    }
+
+    fn render_export_settings(&mut self, ui: &mut egui::Ui) {
+        ui.heading(format!("{} {}", egui_phosphor::regular::EXPORT, self.translations.export_config_title));
+        ui.add_space(20.0);
+        
+        ui.label(self.translations.pdf_page_size);
+        ui.add_space(10.0);
+        ui.vertical(|ui| {
+            for page_size in PageSize::ALL {
+                ui.radio_value(&mut self.settings.pdf.page_size, page_size, page_size.label());
+                ui.add_space(5.0);
+            }
+        });
+        ui.add_space(15.0);
+        
+        ui.horizontal(|ui| {
+            ui.label(self.translations.pdf_margin);
+            ui.add(egui::DragValue::new(&mut self.settings.pdf.margin).range(5.0..=50.0).speed(0.5));
+        });
+        ui.add_space(15.0);
+        
+        ui.horizontal(|ui| {
+            ui.label(self.translations.png_width);
+            ui.add(egui::DragValue::new(&mut self.settings.png.width).range(300.0..=2000.0).speed(10.0));
+        });
         ui.add_space(10.0);
         
-        // Vista previa del idioma
-        ui.label(self.translations.preview_label);
-        ui.add_space(5.0);
+        ui.horizontal(|ui| {
+            ui.label(self.translations.png_scale);
+            ui.add(egui::DragValue::new(&mut self.settings.png.scale).range(0.5..=4.0).speed(0.1));
+        });
+    }
+
+    fn render_keybindings_settings(&mut self, ui: &mut egui::Ui) {
+        ui.heading(format!("{} {}", egui_phosphor::regular::KEYBOARD, self.translations.keybindings_config_title));
+        ui.add_space(20.0);
         
-        egui::Frame::new()
-            .fill(ui.visuals().window_fill())
-            .stroke(ui.visuals().window_stroke())
-            .inner_margin(15.0)
+        ui.checkbox(&mut self.settings.vim_navigation, self.translations.vim_navigation);
+        ui.add_space(15.0);
+        
+        ui.label(self.translations.keybindings_description);
+        ui.add_space(10.0);
+        
+        let mut record = None;
+        let mut reset = None;
+        let mut clear = None;
+        egui::ScrollArea::vertical()
+            .max_height(320.0)
             .show(ui, |ui| {
-                ui.label(egui::RichText::new(self.translations.welcome_title).size(20.0).strong());
-                ui.add_space(5.0);
-                ui.label(self.translations.welcome_subtitle);
-                ui.add_space(5.0);
-                ui.horizontal(|ui| {
-                    ui.label(egui::RichText::new(egui_phosphor::regular::DOT_OUTLINE).strong());
-                    ui.label(self.translations.about_feature_interface);
-                });
+                egui::Grid::new("keybindings")
+                    .num_columns(3)
+                    .spacing([16.0, 6.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for binding in self.settings.keybindings.bindings() {
+                            let action = binding.action;
+                            ui.label(format!("{} {}", action.icon(), action.label(&self.translations)));
+                            
+                            let text = if self.recording_shortcut == Some(action) {
+                                self.translations.keybindings_recording.to_string()
+                            } else {
+                                binding.shortcut.map_or(self.translations.keybindings_unassigned.to_string(), |shortcut| shortcut.to_string())
+                            };
+                            if ui.add(egui::Button::new(egui::RichText::new(text).monospace()).min_size(egui::vec2(150.0, 0.0))).clicked() {
+                                record = Some(action);
+                            }
+                            
+                            ui.horizontal(|ui| {
+                                if ui.small_button(egui_phosphor::regular::ARROW_COUNTER_CLOCKWISE).on_hover_text(self.translations.keybindings_reset).clicked() {
+                                    reset = Some(action);
+                                }
+                                if ui.add_enabled(binding.shortcut.is_some(), egui::Button::new(egui_phosphor::regular::X).small())
+                                    .on_hover_text(self.translations.keybindings_clear).clicked() {
+                                    clear = Some(action);
+                                }
+                                if let Some(other) = self.settings.keybindings.conflict(action) {
+                                    ui.label(egui::RichText::new(format!(
+                                        "{} {} {}",
+                                        egui_phosphor::regular::WARNING,
+                                        self.translations.keybindings_conflict,
+                                        other.label(&self.translations)
+                                    )).color(egui::Color32::from_rgb(220, 120, 60)));
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+            });
+        
+        if let Some(action) = record {
+            self.recording_shortcut = Some(action);
+        }
+        if let Some(action) = reset {
+            self.settings.keybindings.reset(action);
+        }
+        if let Some(action) = clear {
+            self.settings.keybindings.set(action, None);
+        }
+        
+        ui.add_space(10.0);
+        if ui.add(egui::Button::new(
+            format!("{} {}", egui_phosphor::regular::ARROW_COUNTER_CLOCKWISE, self.translations.keybindings_reset_all)
+        ).corner_radius(egui::CornerRadius::same(10))).clicked() {
+            self.settings.keybindings = Keymap::default();
+            self.recording_shortcut = None;
+        }
+    }
+
+    /// Ventana de ayuda con todos los atajos asignados (F1).
+    fn render_shortcuts_help(&mut self, ctx: &egui::Context) {
+        if !self.show_shortcuts {
+            return;
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+            self.show_shortcuts = false;
+            return;
+        }
+        
+        let mut open = self.show_shortcuts;
+        egui::Window::new(format!("{} {}", egui_phosphor::regular::KEYBOARD, self.translations.action_show_shortcuts))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                egui::Grid::new("shortcuts_help")
+                    .num_columns(2)
+                    .spacing([32.0, 6.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for binding in self.settings.keybindings.bindings() {
+                            if let Some(shortcut) = binding.shortcut {
+                                ui.label(binding.action.label(&self.translations));
+                                ui.label(egui::RichText::new(shortcut.to_string()).monospace().strong());
+                                ui.end_row();
+                            }
+                        }
+                    });
             });
+        self.show_shortcuts = open;
     }
 
     fn render_about_section(&self, ui: &mut egui::Ui) {
@@ -761,238 +2804,84 @@ impl MarkdownViewer {
             });
         });
     }
-
-    fn render_markdown_as_ui(&self, ui: &mut egui::Ui) {
-        let mut options = Options::empty();
-        options.insert(Options::ENABLE_STRIKETHROUGH);
-        options.insert(Options::ENABLE_TABLES);
-        options.insert(Options::ENABLE_FOOTNOTES);
-        options.insert(Options::ENABLE_TASKLISTS);
-
-        let parser = MarkdownParser::new_ext(&self.markdown_content, options);
-        
-        let mut current_text = String::new();
-        let mut in_code_block = false;
-        let mut code_block_content = String::new();
-        let mut in_list = false;
-        let mut list_item_level = 0;
-        let mut in_emphasis = false;
-        let mut in_strong = false;
-        let mut in_strikethrough = false;
-        
-        for event in parser {
-            match event {
-                pulldown_cmark::Event::Start(tag) => {
-                    match tag {
-                        pulldown_cmark::Tag::Heading(level, _, _) => {
-                            ui.add_space(20.0);
-                            current_text.clear();
-                        }
-                        pulldown_cmark::Tag::Paragraph => {
-                            if !in_list {
-                                ui.add_space(8.0);
-                            }
-                            current_text.clear();
-                        }
-                        pulldown_cmark::Tag::CodeBlock(kind) => {
-                            ui.add_space(10.0);
-                            in_code_block = true;
-                            code_block_content.clear();
-                            
-                            if let pulldown_cmark::CodeBlockKind::Fenced(lang) = kind {
-                                if !lang.is_empty() {
-                                    ui.add(egui::Label::new(
-                                        egui::RichText::new(format!("```{}", lang))
-                                            .monospace()
-                                            .size(12.0)
-                                            .color(egui::Color32::GRAY)
-                                    ));
-                                }
-                            }
-                        }
-                        pulldown_cmark::Tag::List(_) => {
-                            ui.add_space(8.0);
-                            in_list = true;
-                            list_item_level = 0;
-                        }
-                        pulldown_cmark::Tag::Item => {
-                            current_text.clear();
-                        }
-                        pulldown_cmark::Tag::Emphasis => {
-                            in_emphasis = true;
-                        }
-                        pulldown_cmark::Tag::Strong => {
-                            in_strong = true;
-                        }
-                        pulldown_cmark::Tag::Strikethrough => {
-                            in_strikethrough = true;
-                        }
-                        pulldown_cmark::Tag::BlockQuote => {
-                            ui.add_space(8.0);
-                            ui.separator();
-                        }
-                        _ => {}
-                    }
-                }
-                pulldown_cmark::Event::Text(text) => {
-                    if in_code_block {
-                        code_block_content.push_str(&text);
-                    } else {
-                        current_text.push_str(&text);
-                    }
-                }
-                pulldown_cmark::Event::Code(code) => {
-                    let mut rich_text = egui::RichText::new(code.as_ref())
-                        .monospace()
-                        .background_color(egui::Color32::from_rgb(240, 240, 240));
-                    
-                    if ui.visuals().dark_mode {
-                        rich_text = rich_text.background_color(egui::Color32::from_rgb(45, 45, 45));
-                    }
-                    
-                    ui.add(egui::Label::new(rich_text));
-                }
-                pulldown_cmark::Event::SoftBreak => {
-                    current_text.push(' ');
-                }
-                pulldown_cmark::Event::HardBreak => {
-                    current_text.push('\n');
-                }
-                pulldown_cmark::Event::End(tag) => {
-                    match tag {
-                        pulldown_cmark::Tag::Heading(level, _, _) => {
-                            if !current_text.is_empty() {
-                                let mut rich_text = egui::RichText::new(&current_text).strong();
-                                
-                                match level {
-                                    pulldown_cmark::HeadingLevel::H1 => {
-                                        rich_text = rich_text.size(28.0).color(egui::Color32::from_rgb(51, 51, 51));
-                                    }
-                                    pulldown_cmark::HeadingLevel::H2 => {
-                                        rich_text = rich_text.size(24.0).color(egui::Color32::from_rgb(68, 68, 68));
-                                    }
-                                    pulldown_cmark::HeadingLevel::H3 => {
-                                        rich_text = rich_text.size(20.0).color(egui::Color32::from_rgb(85, 85, 85));
-                                    }
-                                    pulldown_cmark::HeadingLevel::H4 => {
-                                        rich_text = rich_text.size(18.0).color(egui::Color32::from_rgb(102, 102, 102));
-                                    }
-                                    pulldown_cmark::HeadingLevel::H5 => {
-                                        rich_text = rich_text.size(16.0).color(egui::Color32::from_rgb(119, 119, 119));
-                                    }
-                                    pulldown_cmark::HeadingLevel::H6 => {
-                                        rich_text = rich_text.size(14.0).color(egui::Color32::from_rgb(136, 136, 136));
-                                    }
-                                }
-                                
-                                if ui.visuals().dark_mode {
-                                    rich_text = match level {
-                                        pulldown_cmark::HeadingLevel::H1 => rich_text.color(egui::Color32::WHITE),
-                                        pulldown_cmark::HeadingLevel::H2 => rich_text.color(egui::Color32::from_rgb(230, 230, 230)),
-                                        pulldown_cmark::HeadingLevel::H3 => rich_text.color(egui::Color32::from_rgb(210, 210, 210)),
-                                        _ => rich_text.color(egui::Color32::from_rgb(190, 190, 190)),
-                                    };
-                                }
-                                
-                                ui.add(egui::Label::new(rich_text).wrap());
-                                
-                                if matches!(level, pulldown_cmark::HeadingLevel::H1 | pulldown_cmark::HeadingLevel::H2) {
-                                    ui.add_space(5.0);
-                                    ui.separator();
-                                }
-                                ui.add_space(10.0);
-                            }
-                            current_text.clear();
-                        }
-                        pulldown_cmark::Tag::Paragraph => {
-                            if !current_text.is_empty() {
-                                let mut rich_text = egui::RichText::new(&current_text).size(14.0);
-                                
-                                if in_emphasis {
-                                    rich_text = rich_text.italics();
-                                }
-                                if in_strong {
-                                    rich_text = rich_text.strong();
-                                }
-                                if in_strikethrough {
-                                    rich_text = rich_text.strikethrough();
-                                }
-                                
-                                ui.add(egui::Label::new(rich_text).wrap());
-                                if !in_list {
-                                    ui.add_space(8.0);
-                                }
-                            }
-                            current_text.clear();
-                        }
-                        pulldown_cmark::Tag::CodeBlock(_) => {
-                            if !code_block_content.is_empty() {
-                                egui::Frame::new()
-                                    .fill(if ui.visuals().dark_mode { 
-                                        egui::Color32::from_rgb(30, 30, 30) 
-                                    } else { 
-                                        egui::Color32::from_rgb(248, 248, 248) 
-                                    })
-                                    .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(200, 200, 200)))
-                                    .inner_margin(10.0)
-                                    .show(ui, |ui| {
-                                        ui.add(
-                                            egui::TextEdit::multiline(&mut code_block_content.as_str())
-                                                .font(egui::TextStyle::Monospace)
-                                                .desired_width(f32::INFINITY)
-                                                .desired_rows(code_block_content.lines().count().max(1))
-                                        );
-                                    });
-                                ui.add_space(10.0);
-                            }
-                            in_code_block = false;
-                            code_block_content.clear();
-                        }
-                        pulldown_cmark::Tag::List(_) => {
-                            in_list = false;
-                            ui.add_space(8.0);
-                        }
-                        pulldown_cmark::Tag::Item => {
-                            if !current_text.is_empty() {
-                                ui.horizontal(|ui| {
-                                    ui.add_space(list_item_level as f32 * 20.0);
-                                    ui.add(egui::Label::new(egui_phosphor::regular::DOT_OUTLINE));
-                                    ui.add_space(5.0);
-                                    ui.add(egui::Label::new(&current_text).wrap());
-                                });
-                            }
-                            current_text.clear();
-                        }
-                        pulldown_cmark::Tag::Emphasis => {
-                            in_emphasis = false;
-                        }
-                        pulldown_cmark::Tag::Strong => {
-                            in_strong = false;
-                        }
-                        pulldown_cmark::Tag::Strikethrough => {
-                            in_strikethrough = false;
-                        }
-                        pulldown_cmark::Tag::BlockQuote => {
-                            ui.add_space(8.0);
-                        }
-                        _ => {}
-                    }
-                }
-                _ => {}
-            }
-        }
-    }
 }
 
 impl eframe::App for MarkdownViewer {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        for index in 0..self.tabs.len() {
+            self.remember_scroll(index);
+        }
         eframe::set_value(storage, "settings", &self.settings);
+        eframe::set_value(storage, "session", &self.current_session());
+        eframe::set_value(storage, "scroll_positions", &self.scroll_memory);
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Aplicar tema
         self.apply_theme(ctx);
 
+        // Atajos de teclado. Mientras se graba un atajo en la configuración,
+        // la tecla presionada se asigna en lugar de ejecutarse.
+        if let Some(action) = self.recording_shortcut {
+            if let Some(shortcut) = keymap::capture(ctx) {
+                if shortcut != keymap::Shortcut::new(false, false, false, egui::Key::Escape) {
+                    self.settings.keybindings.set(action, Some(shortcut));
+                }
+                self.recording_shortcut = None;
+            }
+        } else {
+            for action in self.settings.keybindings.dispatch(ctx) {
+                if self.action_enabled(action) {
+                    self.run_action(ctx, action);
+                }
+            }
+        }
+        
+        // Navegación estilo vim/less, salvo mientras se escribe en un campo de texto
+        if self.settings.vim_navigation && !self.palette.open && self.recording_shortcut.is_none() {
+            let focused = ctx.memory(|memory| memory.focused());
+            let raw_view = self.active_tab().filter(|tab| !tab.editing).map(|tab| tab.raw_view_id());
+            if focused.is_none() || focused == raw_view {
+                for command in self.pager_keys.read(ctx) {
+                    self.run_pager_command(command);
+                }
+            }
+        }
+        
+        // Historial con los botones laterales del mouse
+        let (back, forward) = ctx.input(|i| (
+            i.pointer.button_pressed(egui::PointerButton::Extra1),
+            i.pointer.button_pressed(egui::PointerButton::Extra2),
+        ));
+        if back {
+            self.go_back();
+        }
+        if forward {
+            self.go_forward();
+        }
+        
+        // Al cerrar la ventana con cambios sin guardar, preguntar antes
+        if ctx.input(|i| i.viewport().close_requested()) && self.tabs.iter().any(|tab| tab.dirty) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.unsaved_prompt = Some(UnsavedPrompt { tab: None, action: UnsavedAction::Quit });
+        }
+        
+        // Documentos editados: se vuelven a analizar cuando se deja de escribir
+        for tab in &mut self.tabs {
+            if tab.apply_edits(ctx) {
+                self.find.invalidate();
+            }
+        }
+        
+        // Paleta de comandos, ayuda de atajos y confirmación de cambios sin guardar
+        self.render_command_palette(ctx);
+        self.render_shortcuts_help(ctx);
+        self.render_unsaved_prompt(ctx);
+        
+        if self.find.open && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+            self.find.close();
+        }
+
         // Toolbar superior
         egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
             ui.add_space(5.0);
@@ -1000,109 +2889,505 @@ impl eframe::App for MarkdownViewer {
             ui.add_space(5.0);
         });
 
+        // Pestañas de documentos
+        self.render_tab_strip(ctx);
+
+        // Barra de búsqueda
+        self.render_find_bar(ctx);
+        if let Some(tab) = self.tabs.get(self.active_tab) {
+            self.find.refresh(&tab.document, &tab.markdown_content);
+        }
+
+        // Aviso de archivo borrado o movido
+        self.render_file_notice(ctx);
+
         // Panel de configuración
         self.render_settings_panel(ctx);
 
+        // Cambios en el disco de los archivos abiertos y texto nuevo en la entrada estándar
+        self.reload_changed_files(ctx);
+        self.read_stdin();
+        self.update_window_title(ctx);
+
+        // Árbol de la carpeta abierta e índice lateral
+        if let Some(workspace) = &mut self.workspace {
+            workspace.poll_changes();
+        }
+        self.render_workspace(ctx);
+        self.render_outline(ctx);
+
         // Contenido principal
         egui::CentralPanel::default().show(ctx, |ui| {
-            if self.markdown_content.is_empty() {
-                let full_width = ui.available_width();
-                let avail_height = ui.available_height();
-                let top_space = (avail_height * 0.18).max(60.0).min(260.0);
-                ui.allocate_ui_with_layout(
-                    egui::Vec2::new(full_width, 0.0),
-                    egui::Layout::top_down(egui::Align::Center),
-                    |ui| {
-                        ui.add_space(top_space);
-                        ui.centered_and_justified(|ui| {
-                            let title_text = format!("{} {}", egui_phosphor::regular::BOOK_OPEN, self.translations.welcome_title);
-                            ui.add(egui::Label::new(
-                                egui::RichText::new(title_text)
-                                    .size(32.0)
-                                    .strong()
-                                    .color(egui::Color32::from_rgb(51, 102, 153))
-                            ));
-                        });
-                        ui.add_space(30.0);
-                        ui.add(egui::Label::new(
-                            egui::RichText::new(self.translations.welcome_subtitle)
-                                .size(16.0)
-                                .color(egui::Color32::GRAY)
-                        ));
-                        ui.add_space(20.0);
-                        ui.add(egui::Label::new(
-                            egui::RichText::new(self.translations.welcome_drag_drop)
-                                .size(14.0)
-                                .italics()
-                                .color(egui::Color32::GRAY)
-                        ));
-                        ui.add_space(30.0);
-                        if ui.add_sized([160.0, 45.0], 
-                            egui::Button::new(
-                                egui::RichText::new(format!("{} {}", egui_phosphor::regular::FOLDER_OPEN, self.translations.open_file_button))
-                                    .size(16.0)
-                            ).corner_radius(egui::CornerRadius::same(12))
-                        ).clicked() {
-                            self.open_file();
-                        }
-                    }
-                );
+            if self.tabs.is_empty() {
+                self.render_welcome(ui);
             } else {
-                egui::ScrollArea::vertical()
-                    .auto_shrink([false; 2])
+                self.render_document_view(ui);
+            }
+        });
+
+        self.find.scroll_to_current = false;
+
+        // Drag and drop: cada archivo se abre en su propia pestaña y una carpeta
+        // se abre como espacio de trabajo
+        let dropped: Vec<PathBuf> = ctx.input(|i| {
+            i.raw.dropped_files.iter().filter_map(|file| file.path.clone()).collect()
+        });
+        for path in dropped {
+            if path.is_dir() {
+                self.open_folder_from_path(path, ctx);
+            } else if workspace::is_markdown_path(&path) {
+                self.open_file_from_path(path);
+            }
+        }
+    }
+}
+
+/// Lo que deja un panel del documento después de dibujarse.
+struct PaneOutput {
+    /// Área visible del panel, en coordenadas de pantalla.
+    inner_rect: egui::Rect,
+    offset: f32,
+    content_height: f32,
+    /// Posición en pantalla de cada encabezado y del comienzo de cada bloque.
+    heading_tops: Vec<f32>,
+    block_tops: Vec<f32>,
+    /// Alto en pantalla de cada bloque; solo si hay cambios que marcar.
+    block_spans: Vec<Range<f32>>,
+    /// Se editó el código fuente en este frame.
+    edited: bool,
+    clicked_link: Option<String>,
+    pane: Pane,
+    /// Bloque sobre el que se hizo clic derecho, y dónde.
+    context_click: Option<(usize, egui::Pos2)>,
+}
+
+impl PaneOutput {
+    /// Convierte una posición en pantalla a coordenadas del contenido.
+    fn content_y(&self, top: f32) -> f32 {
+        top - self.inner_rect.top() + self.offset
+    }
+
+    fn layout(&self) -> PaneLayout {
+        PaneLayout {
+            offset: self.offset,
+            max_offset: (self.content_height - self.inner_rect.height()).max(0.0),
+            content_height: self.content_height,
+            block_tops: self.block_tops.iter().map(|top| self.content_y(*top)).collect(),
+        }
+    }
+}
+
+/// Código fuente del documento, editable en modo edición. `find_current`
+/// indica si la coincidencia actual de la búsqueda se resalta y se sigue aquí.
+fn show_source_pane(ui: &mut egui::Ui, tab: &mut DocumentTab, find: &search::FindState, id_salt: impl std::hash::Hash, restore: Option<f32>, find_current: bool) -> PaneOutput {
+    let mut scroll_area = egui::ScrollArea::vertical()
+        .id_salt(id_salt)
+        .auto_shrink([false; 2]);
+    if let Some(offset) = restore {
+        scroll_area = scroll_area.vertical_scroll_offset(offset);
+    }
+    
+    let show_changes = tab.changes.is_some();
+    let mut block_tops = Vec::new();
+    let mut block_spans = Vec::new();
+    let mut edited = false;
+    let mut context_offset = None;
+    let output = scroll_area.show(ui, |ui| {
+        let max_width = ui.available_width().min(800.0);
+        ui.allocate_ui_with_layout(
+            egui::Vec2::new(max_width, ui.available_height()),
+            egui::Layout::top_down(egui::Align::LEFT),
+            |ui| {
+                ui.add_space(20.0);
+                egui::Frame::new()
+                    .fill(if ui.visuals().dark_mode { 
+                        egui::Color32::from_rgb(25, 25, 25) 
+                    } else { 
+                        egui::Color32::from_rgb(252, 252, 252) 
+                    })
+                    .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(200, 200, 200)))
+                    .inner_margin(15.0)
                     .show(ui, |ui| {
-                        let max_width = ui.available_width().min(800.0);
-                        ui.allocate_ui_with_layout(
-                            egui::Vec2::new(max_width, ui.available_height()),
-                            egui::Layout::top_down(egui::Align::LEFT),
-                            |ui| {
-                                ui.add_space(20.0);
-                                
-                                if self.settings.show_raw_markdown {
-                                    egui::Frame::new()
-                                        .fill(if ui.visuals().dark_mode { 
-                                            egui::Color32::from_rgb(25, 25, 25) 
-                                        } else { 
-                                            egui::Color32::from_rgb(252, 252, 252) 
-                                        })
-                                        .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(200, 200, 200)))
-                                        .inner_margin(15.0)
-                                        .show(ui, |ui| {
-                                            ui.add(
-                                                egui::TextEdit::multiline(&mut self.markdown_content.as_str())
-                                                    .font(egui::TextStyle::Monospace)
-                                                    .desired_width(f32::INFINITY)
-                                            );
-                                        });
-                                } else {
-                                    ui.horizontal(|ui| {
-                                        ui.add_space(20.0);
-                                        ui.vertical(|ui| {
-                                            self.render_markdown_as_ui(ui);
-                                            ui.add_space(50.0);
-                                        });
-                                        ui.add_space(20.0);
-                                    });
-                                }
+                        let current = if find_current { find.current_index(true) } else { None };
+                        let highlights: Vec<render::Highlight> = find.raw_matches.iter().enumerate()
+                            .map(|(index, range)| render::Highlight { range: range.clone(), current: current == Some(index) })
+                            .collect();
+                        let mut layouter = render::highlight_layouter(&highlights);
+                        
+                        let raw_view_id = tab.raw_view_id();
+                        // Selección pedida desde la vista renderizada ("ver código fuente")
+                        if let Some(range) = tab.select_source.take() {
+                            let text = tab.markdown_content.as_str();
+                            let start = text.get(..range.start).map_or(0, |before| before.chars().count());
+                            let end = start + text.get(range).map_or(0, |selected| selected.chars().count());
+                            let mut state = egui::text_edit::TextEditState::load(ui.ctx(), raw_view_id).unwrap_or_default();
+                            state.cursor.set_char_range(Some(egui::text::CCursorRange::two(
+                                egui::text::CCursor::new(start),
+                                egui::text::CCursor::new(end),
+                            )));
+                            state.store(ui.ctx(), raw_view_id);
+                            ui.memory_mut(|memory| memory.request_focus(raw_view_id));
+                        }
+                        
+                        // En modo edición el código fuente se modifica; si no, es de solo lectura
+                        let mut read_only;
+                        let source: &mut dyn egui::TextBuffer = if tab.editing {
+                            &mut tab.markdown_content
+                        } else {
+                            read_only = tab.markdown_content.as_str();
+                            &mut read_only
+                        };
+                        let mut editor = egui::TextEdit::multiline(source)
+                            .id(raw_view_id)
+                            .font(egui::TextStyle::Monospace)
+                            .desired_width(f32::INFINITY);
+                        if !highlights.is_empty() {
+                            editor = editor.layouter(&mut layouter);
+                        }
+                        // Un clic derecho no borra la selección, para poder copiarla desde el menú
+                        let kept_selection = ui.input(|i| i.pointer.button_pressed(egui::PointerButton::Secondary))
+                            .then(|| egui::text_edit::TextEditState::load(ui.ctx(), raw_view_id))
+                            .flatten()
+                            .filter(|state| state.cursor.char_range().is_some_and(|range| range.primary.index != range.secondary.index));
+                        let output = editor.show(ui);
+                        if let Some(state) = kept_selection {
+                            state.store(ui.ctx(), raw_view_id);
+                        }
+                        
+                        // Clic derecho: posición del código fuente bajo el puntero
+                        let right_click = ui.input(|i| i.pointer.secondary_clicked().then_some(i.pointer.interact_pos()).flatten());
+                        if let Some(position) = right_click.filter(|position| output.response.rect.contains(*position)) {
+                            let cursor = output.galley.cursor_from_pos(position - output.galley_pos);
+                            let offset = tab.markdown_content.char_indices()
+                                .nth(cursor.ccursor.index)
+                                .map_or(tab.markdown_content.len(), |(offset, _)| offset);
+                            context_offset = Some((offset, position));
+                        }
+                        if output.response.changed() {
+                            tab.mark_edited();
+                            edited = true;
+                        }
+                        
+                        let source = &tab.markdown_content;
+                        let starts: Vec<usize> = tab.document.blocks.iter().map(|block| block.range.start).collect();
+                        block_tops = render::text_offset_tops(&output, source, &starts);
+                        
+                        if show_changes {
+                            // Desde la primera fila de cada bloque hasta el final de la última
+                            let last_chars: Vec<usize> = tab.document.blocks.iter()
+                                .map(|block| {
+                                    let text = source.get(block.range.clone()).unwrap_or_default().trim_end();
+                                    block.range.start + text.char_indices().last().map_or(0, |(index, _)| index)
+                                })
+                                .collect();
+                            let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                            let last_tops = render::text_offset_tops(&output, source, &last_chars);
+                            block_spans = block_tops.iter().zip(&last_tops)
+                                .map(|(top, last_top)| *top..last_top + row_height)
+                                .collect();
+                        }
+                        
+                        if let Some(offset) = tab.scroll_to_source.take() {
+                            render::scroll_to_text_offset(ui, &output, source, offset);
+                        }
+                        if find.scroll_to_current {
+                            if let Some(current) = highlights.iter().find(|h| h.current) {
+                                render::scroll_to_text_offset(ui, &output, source, current.range.start);
                             }
-                        );
+                        }
+                    });
+            }
+        );
+    });
+    
+    let heading_tops = tab.document.headings.iter()
+        .map(|heading| block_tops.get(heading.block).copied().unwrap_or(f32::INFINITY))
+        .collect();
+    let context_click = context_offset
+        .filter(|(_, position)| output.inner_rect.contains(*position))
+        .and_then(|(offset, position)| Some((tab.block_at_offset(offset)?, position)));
+    PaneOutput {
+        inner_rect: output.inner_rect,
+        offset: output.state.offset.y,
+        content_height: output.content_size.y,
+        heading_tops,
+        block_tops,
+        block_spans,
+        edited,
+        clicked_link: None,
+        pane: Pane::Source,
+        context_click,
+    }
+}
+
+/// Documento renderizado.
+fn show_preview_pane(ui: &mut egui::Ui, tab: &mut DocumentTab, find: &search::FindState, id_salt: impl std::hash::Hash, restore: Option<f32>) -> PaneOutput {
+    let mut scroll_area = egui::ScrollArea::vertical()
+        .id_salt(id_salt)
+        .auto_shrink([false; 2]);
+    if let Some(offset) = restore {
+        scroll_area = scroll_area.vertical_scroll_offset(offset);
+    }
+    
+    let mut clicked_link = None;
+    let output = scroll_area.show(ui, |ui| {
+        let max_width = ui.available_width().min(800.0);
+        ui.allocate_ui_with_layout(
+            egui::Vec2::new(max_width, ui.available_height()),
+            egui::Layout::top_down(egui::Align::LEFT),
+            |ui| {
+                ui.add_space(20.0);
+                ui.horizontal(|ui| {
+                    ui.add_space(20.0);
+                    ui.vertical(|ui| {
+                        tab.preview_width = ui.available_width();
+                        let options = render::RenderOptions {
+                            scroll_to_block: tab.scroll_to_block.take(),
+                            matches: &find.rendered_matches,
+                            current_match: find.current_index(false),
+                            scroll_to_match: find.scroll_to_current,
+                        };
+                        let output = render::render_document(ui, &tab.document, &options);
+                        tab.block_rects = output.block_rects;
+                        clicked_link = output.clicked_link;
+                        ui.add_space(50.0);
                     });
+                    ui.add_space(20.0);
+                });
             }
+        );
+    });
+    
+    let heading_tops = tab.document.headings.iter()
+        .map(|heading| tab.block_rects.get(heading.block).map_or(f32::INFINITY, |rect| rect.top()))
+        .collect();
+    let block_spans = if tab.changes.is_some() {
+        tab.block_rects.iter().map(|rect| rect.top()..rect.bottom()).collect()
+    } else {
+        Vec::new()
+    };
+    
+    tab.track_preview_selection(ui.ctx(), output.inner_rect);
+    
+    // Bloque elegido con "mostrar en la vista renderizada", resaltado mientras se desvanece
+    if let Some((block, since)) = tab.flash_block {
+        let fade = since.elapsed().as_secs_f32() / 1.5;
+        match tab.block_rects.get(block) {
+            Some(rect) if fade < 1.0 => {
+                let color = ui.visuals().selection.bg_fill.gamma_multiply(0.5 * (1.0 - fade));
+                ui.painter_at(output.inner_rect).rect_filled(rect.expand(4.0), 4.0, color);
+                ui.ctx().request_repaint();
+            }
+            _ => tab.flash_block = None,
+        }
+    }
+    
+    // Clic derecho: bloque a la altura del puntero
+    let right_click = ui.input(|i| i.pointer.secondary_clicked().then_some(i.pointer.interact_pos()).flatten());
+    let context_click = right_click
+        .filter(|position| output.inner_rect.contains(*position))
+        .and_then(|position| {
+            let block = tab.block_rects.iter().position(|rect| rect.y_range().contains(position.y))?;
+            Some((block, position))
         });
+    PaneOutput {
+        inner_rect: output.inner_rect,
+        offset: output.state.offset.y,
+        content_height: output.content_size.y,
+        heading_tops,
+        block_tops: tab.block_rects.iter().map(|rect| rect.top()).collect(),
+        block_spans,
+        edited: false,
+        clicked_link,
+        pane: Pane::Preview,
+        context_click,
+    }
+}
+
+/// Código fuente y documento renderizado lado a lado, separados por un
+/// divisor que se puede arrastrar. El panel que el usuario mueve guía al
+/// otro, que se desplaza al mismo bloque. Devuelve los dos paneles.
+fn show_split_view(ui: &mut egui::Ui, tab: &mut DocumentTab, find: &search::FindState, split_ratio: &mut f32, restore: Option<f32>) -> (PaneOutput, PaneOutput) {
+    let rect = ui.available_rect_before_wrap();
+    let divider_x = rect.left() + rect.width() * *split_ratio;
+    let divider_rect = egui::Rect::from_x_y_ranges(divider_x - 3.0..=divider_x + 3.0, rect.y_range());
+    let divider = ui.interact(divider_rect, ui.id().with("split_divider"), egui::Sense::drag())
+        .on_hover_cursor(egui::CursorIcon::ResizeHorizontal);
+    if divider.dragged() {
+        if let Some(pointer) = divider.interact_pointer_pos() {
+            *split_ratio = ((pointer.x - rect.left()) / rect.width()).clamp(0.2, 0.8);
+        }
+    }
+    let stroke = if divider.hovered() || divider.dragged() {
+        ui.visuals().widgets.hovered.fg_stroke
+    } else {
+        ui.visuals().widgets.noninteractive.bg_stroke
+    };
+    ui.painter().vline(divider_x, rect.y_range(), stroke);
+    
+    // Los saltos pedidos por la aplicación (índice, historial, teclas) los guía la vista renderizada
+    if restore.is_some() {
+        tab.split.leader = Pane::Preview;
+    }
+    let source_rect = egui::Rect::from_min_max(rect.min, egui::pos2(divider_x - 4.0, rect.bottom()));
+    let preview_rect = egui::Rect::from_min_max(egui::pos2(divider_x + 4.0, rect.top()), rect.max);
+    let source_restore = tab.split.source_restore.take();
+    let preview_restore = restore.or(tab.split.preview_restore.take());
+    let id = tab.id;
+    let source = ui.allocate_new_ui(egui::UiBuilder::new().max_rect(source_rect), |ui| {
+        show_source_pane(ui, tab, find, ("document", id, Pane::Source), source_restore, false)
+    }).inner;
+    let preview = ui.allocate_new_ui(egui::UiBuilder::new().max_rect(preview_rect), |ui| {
+        show_preview_pane(ui, tab, find, ("document", id, Pane::Preview), preview_restore)
+    }).inner;
+    
+    // Guía el panel que se mueve con la rueda o la barra de desplazamiento, o el
+    // código fuente si se desplazó solo (al escribir o mover el cursor de texto)
+    let (pointer, scrolling) = ui.input(|i| (i.pointer.hover_pos(), i.smooth_scroll_delta.y != 0.0 || i.pointer.primary_down()));
+    let source_moved = tab.split.source_moved(source.offset);
+    match pointer {
+        Some(pointer) if scrolling && source_rect.contains(pointer) => tab.split.leader = Pane::Source,
+        Some(pointer) if scrolling && preview_rect.contains(pointer) => tab.split.leader = Pane::Preview,
+        _ if source.edited || (source_moved && restore.is_none()) => tab.split.leader = Pane::Source,
+        _ => {}
+    }
+    if tab.split.follow(&source.layout(), &preview.layout()) {
+        ui.ctx().request_repaint();
+    }
+    (source, preview)
+}
 
-        // Drag and drop
-        ctx.input(|i| {
-            if !i.raw.dropped_files.is_empty() {
-                for file in &i.raw.dropped_files {
-                    if let Some(path) = &file.path {
-                        if path.extension().map_or(false, |ext| {
-                            matches!(ext.to_str(), Some("md") | Some("markdown") | Some("txt"))
-                        }) {
-                            self.open_file_from_path(path.clone());
+/// Panel de búsqueda en todos los archivos de la carpeta. Devuelve el
+/// archivo y la posición del resultado elegido.
+fn render_workspace_search(ui: &mut egui::Ui, workspace: &Workspace, search: &mut WorkspaceSearch, translations: &Translations) -> Option<(PathBuf, usize)> {
+    let response = ui.add(
+        egui::TextEdit::singleline(&mut search.query)
+            .hint_text(translations.workspace_search_placeholder)
+            .desired_width(f32::INFINITY)
+    );
+    if search.request_focus {
+        response.request_focus();
+        search.request_focus = false;
+    }
+    ui.horizontal(|ui| {
+        ui.toggle_value(&mut search.options.case_sensitive, "Aa").on_hover_text(translations.find_case_sensitive);
+        ui.toggle_value(&mut search.options.whole_word, "|ab|").on_hover_text(translations.find_whole_word);
+        ui.toggle_value(&mut search.options.regex, ".*").on_hover_text(translations.find_regex);
+    });
+    search.refresh(&workspace.index, &workspace.files);
+    
+    let status = if search.invalid_regex {
+        translations.find_invalid_regex.to_string()
+    } else if workspace.index.is_indexing() {
+        translations.workspace_search_indexing.to_string()
+    } else if search.query.is_empty() {
+        String::new()
+    } else if search.results.is_empty() {
+        translations.find_no_results.to_string()
+    } else {
+        translations.workspace_search_summary
+            .replace("{matches}", &search.match_count().to_string())
+            .replace("{files}", &search.results.len().to_string())
+    };
+    if !status.is_empty() {
+        ui.label(egui::RichText::new(status).color(egui::Color32::GRAY));
+    }
+    ui.separator();
+    
+    let mut selected = None;
+    let font_id = egui::TextStyle::Body.resolve(ui.style());
+    egui::ScrollArea::vertical()
+        .auto_shrink([false; 2])
+        .show(ui, |ui| {
+            for file in &search.results {
+                let title = format!(
+                    "{} {} ({})",
+                    egui_phosphor::regular::FILE_TEXT,
+                    workspace_search::display_path(&workspace.root, &file.path),
+                    file.lines.len()
+                );
+                egui::CollapsingHeader::new(title)
+                    .id_salt(("workspace_search", &file.path))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        for line in &file.lines {
+                            ui.horizontal(|ui| {
+                                ui.label(egui::RichText::new(line.line_number.to_string()).monospace().color(egui::Color32::GRAY));
+                                let highlights: Vec<render::Highlight> = line.ranges.iter()
+                                    .map(|range| render::Highlight { range: range.clone(), current: false })
+                                    .collect();
+                                let job = render::highlight_job(ui, &line.snippet, &highlights, font_id.clone());
+                                let response = ui.add(egui::Label::new(job).truncate().sense(egui::Sense::click()))
+                                    .on_hover_cursor(egui::CursorIcon::PointingHand);
+                                if response.clicked() {
+                                    selected = Some((file.path.clone(), line.offset));
+                                }
+                            });
                         }
-                    }
-                }
+                    });
             }
         });
+    selected
+}
+
+/// Dibuja el árbol de archivos del espacio de trabajo. Devuelve el archivo elegido.
+fn render_file_tree(ui: &mut egui::Ui, nodes: &[FileNode], active_file: Option<&std::path::Path>) -> Option<PathBuf> {
+    let mut selected = None;
+    for node in nodes {
+        if node.is_dir {
+            let header = egui::CollapsingHeader::new(format!("{} {}", egui_phosphor::regular::FOLDER, node.name))
+                .id_salt(&node.path)
+                .default_open(active_file.is_some_and(|file| file.starts_with(&node.path)))
+                .show(ui, |ui| render_file_tree(ui, &node.children, active_file));
+            if let Some(Some(path)) = header.body_returned {
+                selected = Some(path);
+            }
+        } else {
+            let is_active = active_file == Some(node.path.as_path());
+            if ui.selectable_label(is_active, format!("{} {}", egui_phosphor::regular::FILE_TEXT, node.name)).clicked() {
+                selected = Some(node.path.clone());
+            }
+        }
+    }
+    selected
+}
+
+// Desplazamiento de una línea con j/k
+const LINE_SCROLL: f32 = 24.0;
+
+/// Decodifica secuencias `%XX` de los enlaces (por ejemplo `mi%20archivo.md`).
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_decoding() {
+        assert_eq!(percent_decode("mi%20archivo.md"), "mi archivo.md");
+        assert_eq!(percent_decode("a%C3%B1o%2Fb"), "año/b");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%+1%4"), "%zz%+1%4");
     }
-}
\ No newline at end of file
+}
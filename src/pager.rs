@@ -0,0 +1,143 @@
+use eframe::egui;
+
+/// Comando de navegación al estilo de `less`/vim.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PagerCommand {
+    LineDown,
+    LineUp,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    Top,
+    Bottom,
+    NextHeading,
+    PreviousHeading,
+    Search,
+    NextMatch,
+    PreviousMatch,
+}
+
+/// Lee las teclas de navegación. Guarda la primera tecla de las
+/// combinaciones de dos (`gg`, `]]`, `[[`) hasta el frame siguiente.
+#[derive(Default)]
+pub struct PagerKeys {
+    pending: Option<char>,
+}
+
+impl PagerKeys {
+    /// Consume los caracteres escritos en este frame que forman comandos.
+    pub fn read(&mut self, ctx: &egui::Context) -> Vec<PagerCommand> {
+        let mut commands = Vec::new();
+        ctx.input_mut(|input| {
+            input.events.retain(|event| {
+                let egui::Event::Text(text) = event else {
+                    return true;
+                };
+                let mut consumed = false;
+                for c in text.chars() {
+                    consumed |= self.feed(c, &mut commands);
+                }
+                !consumed
+            });
+        });
+        commands
+    }
+
+    /// Interpreta un carácter escrito. Devuelve `false` si no forma parte de
+    /// ningún comando.
+    pub fn feed(&mut self, c: char, commands: &mut Vec<PagerCommand>) -> bool {
+        if let Some(pending) = self.pending.take() {
+            let command = match (pending, c) {
+                ('g', 'g') => Some(PagerCommand::Top),
+                (']', ']') => Some(PagerCommand::NextHeading),
+                ('[', '[') => Some(PagerCommand::PreviousHeading),
+                _ => None,
+            };
+            if let Some(command) = command {
+                commands.push(command);
+                return true;
+            }
+        }
+
+        let command = match c {
+            'j' => PagerCommand::LineDown,
+            'k' => PagerCommand::LineUp,
+            'd' => PagerCommand::HalfPageDown,
+            'u' => PagerCommand::HalfPageUp,
+            ' ' => PagerCommand::PageDown,
+            'b' => PagerCommand::PageUp,
+            'G' => PagerCommand::Bottom,
+            '/' => PagerCommand::Search,
+            'n' => PagerCommand::NextMatch,
+            'N' => PagerCommand::PreviousMatch,
+            'g' | ']' | '[' => {
+                self.pending = Some(c);
+                return true;
+            }
+            _ => return false,
+        };
+        commands.push(command);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PagerCommand::*;
+
+    fn feed(keys: &mut PagerKeys, text: &str) -> Vec<PagerCommand> {
+        let mut commands = Vec::new();
+        for c in text.chars() {
+            keys.feed(c, &mut commands);
+        }
+        commands
+    }
+
+    #[test]
+    fn single_keys() {
+        let mut keys = PagerKeys::default();
+        assert_eq!(
+            feed(&mut keys, "jkdu bG/nN"),
+            vec![LineDown, LineUp, HalfPageDown, HalfPageUp, PageDown, PageUp, Bottom, Search, NextMatch, PreviousMatch]
+        );
+    }
+
+    #[test]
+    fn two_key_combinations() {
+        let mut keys = PagerKeys::default();
+        assert_eq!(feed(&mut keys, "gg]][["), vec![Top, NextHeading, PreviousHeading]);
+    }
+
+    #[test]
+    fn prefix_waits_for_the_next_key() {
+        let mut keys = PagerKeys::default();
+        assert_eq!(feed(&mut keys, "g"), vec![]);
+        assert_eq!(feed(&mut keys, "g"), vec![Top]);
+    }
+
+    #[test]
+    fn other_key_resets_the_prefix() {
+        let mut keys = PagerKeys::default();
+        // La `j` no completa `g`, se interpreta sola y la siguiente `g` empieza de nuevo
+        assert_eq!(feed(&mut keys, "gjg"), vec![LineDown]);
+        assert_eq!(feed(&mut keys, "]["), vec![]);
+        assert_eq!(feed(&mut keys, "["), vec![PreviousHeading]);
+        assert_eq!(feed(&mut keys, "]G"), vec![Bottom]);
+        assert_eq!(feed(&mut keys, "]"), vec![]);
+    }
+
+    #[test]
+    fn unknown_keys_are_not_consumed() {
+        let mut keys = PagerKeys::default();
+        let mut commands = Vec::new();
+        assert!(!keys.feed('x', &mut commands));
+        assert!(keys.feed('g', &mut commands));
+        // Un prefijo seguido de otra tecla se descarta y la tecla sigue su curso
+        assert!(!keys.feed('x', &mut commands));
+        assert!(commands.is_empty());
+        assert!(keys.feed('n', &mut commands));
+        assert_eq!(commands, vec![NextMatch]);
+    }
+}
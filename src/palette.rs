@@ -0,0 +1,204 @@
+use crate::actions::Action;
+use std::ops::Range;
+use std::path::PathBuf;
+
+// Cantidad máxima de entradas listadas a la vez
+pub const MAX_ITEMS: usize = 50;
+
+/// Lo que hace una entrada de la paleta al elegirla.
+#[derive(Clone)]
+pub enum PaletteTarget {
+    File(PathBuf),
+    Heading(usize),
+    Action(Action),
+}
+
+pub struct PaletteItem {
+    pub label: String,
+    pub detail: String,
+    pub target: PaletteTarget,
+    /// Caracteres de `label` que coinciden con la consulta, en bytes.
+    pub matched: Vec<Range<usize>>,
+    score: i64,
+}
+
+impl PaletteItem {
+    pub fn new(label: String, detail: String, target: PaletteTarget) -> Self {
+        Self {
+            label,
+            detail,
+            target,
+            matched: Vec::new(),
+            score: 0,
+        }
+    }
+}
+
+/// Qué lista la paleta, según el prefijo de la consulta.
+#[derive(PartialEq, Debug)]
+pub enum PaletteMode {
+    /// Archivos del espacio de trabajo y recientes.
+    Files,
+    /// Encabezados del documento actual (`#`).
+    Headings,
+    /// Comandos de la aplicación (`>`).
+    Commands,
+}
+
+/// Paleta de comandos (Ctrl+P / Ctrl+Shift+P).
+#[derive(Default)]
+pub struct CommandPalette {
+    pub open: bool,
+    pub query: String,
+    pub selected: usize,
+    pub request_focus: bool,
+}
+
+impl CommandPalette {
+    /// Abre la paleta con un prefijo inicial: `""` para archivos, `"> "` para comandos.
+    pub fn show(&mut self, prefix: &str) {
+        self.open = true;
+        self.query = prefix.to_string();
+        self.selected = 0;
+        self.request_focus = true;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        self.query.clear();
+    }
+
+    pub fn mode(&self) -> PaletteMode {
+        if self.query.starts_with('>') {
+            PaletteMode::Commands
+        } else if self.query.starts_with('#') {
+            PaletteMode::Headings
+        } else {
+            PaletteMode::Files
+        }
+    }
+
+    /// Consulta sin el prefijo de modo.
+    pub fn search_text(&self) -> &str {
+        match self.mode() {
+            PaletteMode::Files => self.query.trim(),
+            _ => self.query[1..].trim(),
+        }
+    }
+
+    pub fn select_next(&mut self, count: usize) {
+        if count > 0 {
+            self.selected = (self.selected + 1) % count;
+        }
+    }
+
+    pub fn select_previous(&mut self, count: usize) {
+        if count > 0 {
+            self.selected = (self.selected + count - 1) % count;
+        }
+    }
+}
+
+/// Filtra y ordena las entradas por coincidencia difusa con la consulta.
+/// Con la consulta vacía se conserva el orden original.
+pub fn filter_items(items: Vec<PaletteItem>, query: &str) -> Vec<PaletteItem> {
+    let mut items: Vec<PaletteItem> = items
+        .into_iter()
+        .filter_map(|mut item| {
+            let (score, matched) = fuzzy_match(query, &item.label)?;
+            item.score = score;
+            item.matched = matched;
+            Some(item)
+        })
+        .collect();
+    // sort_by_key es estable: a igual puntaje se mantiene el orden original
+    items.sort_by_key(|item| std::cmp::Reverse(item.score));
+    items.truncate(MAX_ITEMS);
+    items
+}
+
+/// Coincidencia difusa: todos los caracteres de la consulta deben aparecer en
+/// orden. Puntúa más los caracteres consecutivos y los inicios de palabra.
+/// Devuelve el puntaje y los rangos de los caracteres que coincidieron.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<(i64, Vec<Range<usize>>)> {
+    if query.trim().is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    let mut score = 0;
+    let mut matched: Vec<Range<usize>> = Vec::new();
+    let mut candidate_chars = candidate.char_indices();
+    let mut previous: Option<char> = None;
+    let mut last_match_end: Option<usize> = None;
+
+    for query_char in query.chars().filter(|c| !c.is_whitespace()) {
+        loop {
+            let (index, candidate_char) = candidate_chars.next()?;
+            let is_match = candidate_char.to_lowercase().eq(query_char.to_lowercase());
+            let word_start = previous.is_none_or(|c| !c.is_alphanumeric())
+                || (previous.is_some_and(|c| c.is_lowercase()) && candidate_char.is_uppercase());
+            previous = Some(candidate_char);
+
+            if is_match {
+                score += 1;
+                if last_match_end == Some(index) {
+                    score += 5;
+                }
+                if word_start {
+                    score += 8;
+                }
+                let end = index + candidate_char.len_utf8();
+                match matched.last_mut() {
+                    Some(range) if range.end == index => range.end = end,
+                    _ => matched.push(index..end),
+                }
+                last_match_end = Some(end);
+                break;
+            }
+        }
+    }
+
+    // A igual coincidencia, preferir los textos más cortos
+    score -= candidate.chars().count() as i64 / 16;
+    Some((score, matched))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(query: &str, candidate: &str) -> i64 {
+        fuzzy_match(query, candidate).unwrap().0
+    }
+
+    #[test]
+    fn characters_must_appear_in_order() {
+        assert!(fuzzy_match("rdm", "README.md").is_some());
+        assert!(fuzzy_match("mdr", "README.md").is_none());
+        assert!(fuzzy_match("readmes", "README.md").is_none());
+    }
+
+    #[test]
+    fn matched_ranges_merge_consecutive_characters() {
+        // Cada carácter toma la primera coincidencia que encuentra
+        let (_, matched) = fuzzy_match("read md", "README.md").unwrap();
+        assert_eq!(matched, [0..5, 8..9]);
+        let (_, matched) = fuzzy_match("ñv", "año nuevo").unwrap();
+        assert_eq!(matched, [1..3, 8..9]);
+    }
+
+    #[test]
+    fn consecutive_and_word_start_matches_score_higher() {
+        assert!(score("ins", "install.md") > score("ins", "mainsail.md"));
+        assert!(score("gs", "getting-started.md") > score("gs", "logs.md"));
+        assert!(score("os", "openSettings") > score("os", "photos"));
+    }
+
+    #[test]
+    fn empty_query_keeps_the_original_order() {
+        assert_eq!(fuzzy_match(" ", "anything"), Some((0, Vec::new())));
+        let items = ["b.md", "a.md"].map(|label| PaletteItem::new(label.to_string(), String::new(), PaletteTarget::Heading(0)));
+        let labels: Vec<String> = filter_items(items.into(), "").into_iter().map(|item| item.label).collect();
+        assert_eq!(labels, ["b.md", "a.md"]);
+    }
+}
@@ -0,0 +1,1061 @@
+use crate::document::{Block, BlockKind, Document, Heading, ListMarker, Span};
+use eframe::egui;
+use printpdf::lopdf::{self, Dictionary, Object, ObjectId, StringFormat};
+use printpdf::path::PaintMode;
+use printpdf::{
+    Color, CustomPdfConformance, IndirectFontRef, Line, Mm, PdfConformance, PdfDocument, PdfDocumentReference,
+    PdfLayerIndex, PdfLayerReference, PdfPageIndex, Point, Pt, Rect, Rgb, TextMatrix, TextRenderingMode,
+};
+use pulldown_cmark::{Alignment, HeadingLevel};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+
+// Tamaños en puntos tipográficos
+const BODY_SIZE: f32 = 10.5;
+const CODE_SIZE: f32 = 9.0;
+const FOOTER_SIZE: f32 = 9.0;
+const LINE_SPACING: f32 = 1.4;
+const LIST_INDENT: f32 = 16.0;
+const QUOTE_INDENT: f32 = 12.0;
+const CELL_PADDING: f32 = 5.0;
+const CODE_PADDING: f32 = 6.0;
+// Inclinación del texto en cursiva: la fuente incrustada no tiene variante cursiva
+const ITALIC_SKEW: f32 = 0.2;
+
+const TEXT_COLOR: [f32; 3] = [0.2, 0.2, 0.2];
+const MUTED_COLOR: [f32; 3] = [0.5, 0.5, 0.5];
+const LINK_COLOR: [f32; 3] = [0.04, 0.42, 0.8];
+const BORDER_COLOR: [f32; 3] = [0.78, 0.78, 0.78];
+const CODE_BACKGROUND: [f32; 3] = [0.97, 0.97, 0.97];
+const INLINE_CODE_BACKGROUND: [f32; 3] = [0.94, 0.94, 0.94];
+
+/// Tamaño de página de los PDF exportados.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize, clap::ValueEnum)]
+pub enum PageSize {
+    #[default]
+    A4,
+    Letter,
+}
+
+impl PageSize {
+    pub const ALL: [PageSize; 2] = [PageSize::A4, PageSize::Letter];
+
+    /// Ancho y alto en puntos.
+    fn dimensions(self) -> (f32, f32) {
+        match self {
+            PageSize::A4 => (595.28, 841.89),
+            PageSize::Letter => (612.0, 792.0),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PageSize::A4 => "A4",
+            PageSize::Letter => "Letter",
+        }
+    }
+}
+
+/// Opciones de página de la exportación a PDF.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfOptions {
+    pub page_size: PageSize,
+    /// Márgenes en milímetros.
+    pub margin: f32,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        Self {
+            page_size: PageSize::A4,
+            margin: 20.0,
+        }
+    }
+}
+
+/// Genera un PDF paginado del documento. Las fuentes van incrustadas, los
+/// encabezados forman el índice (marcadores) y los enlaces se pueden pulsar.
+pub fn export_pdf(document: &Document, title: &str, options: &PdfOptions) -> io::Result<Vec<u8>> {
+    let (regular_bytes, mono_bytes) = font_bytes()?;
+    let mut writer = PdfWriter::new(title, options, &regular_bytes, &mono_bytes)?;
+    for (index, block) in document.blocks.iter().enumerate() {
+        writer.block(index, block);
+    }
+    writer.page_numbers();
+
+    let PdfWriter { pdf, links, headings, height, .. } = writer;
+    let bytes = pdf.save_to_bytes().map_err(io::Error::other)?;
+    add_navigation(&bytes, document, &headings, &links, height)
+}
+
+/// Las mismas fuentes que usa la interfaz: texto normal y monoespaciada.
+fn font_bytes() -> io::Result<(Vec<u8>, Vec<u8>)> {
+    let definitions = egui::FontDefinitions::default();
+    let font_bytes = |name: &str| {
+        definitions.font_data.get(name).map(|data| data.font.to_vec())
+            .ok_or_else(|| io::Error::other(format!("font {} not found", name)))
+    };
+    Ok((font_bytes("Ubuntu-Light")?, font_bytes("Hack")?))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FontKind {
+    Regular,
+    Mono,
+}
+
+#[derive(Clone, PartialEq)]
+struct TextStyle {
+    font: FontKind,
+    size: f32,
+    bold: bool,
+    italic: bool,
+    strikethrough: bool,
+    color: [f32; 3],
+    background: Option<[f32; 3]>,
+    link: Option<String>,
+}
+
+impl TextStyle {
+    fn body() -> Self {
+        Self {
+            font: FontKind::Regular,
+            size: BODY_SIZE,
+            bold: false,
+            italic: false,
+            strikethrough: false,
+            color: TEXT_COLOR,
+            background: None,
+            link: None,
+        }
+    }
+
+    fn code() -> Self {
+        Self {
+            font: FontKind::Mono,
+            size: CODE_SIZE,
+            ..Self::body()
+        }
+    }
+
+    // Estilo de un fragmento del documento sobre el estilo del bloque
+    fn with_span(&self, span: &Span) -> Self {
+        let mut style = self.clone();
+        style.bold |= span.style.strong;
+        style.italic |= span.style.emphasis;
+        style.strikethrough |= span.style.strikethrough;
+        if span.style.code {
+            style.font = FontKind::Mono;
+            style.size = self.size * CODE_SIZE / BODY_SIZE;
+            style.background = Some(INLINE_CODE_BACKGROUND);
+        }
+        if let Some(link) = &span.style.link {
+            style.color = LINK_COLOR;
+            style.link = Some(link.clone());
+        }
+        style
+    }
+}
+
+/// Texto de una línea con un mismo estilo, en su posición horizontal.
+struct Fragment {
+    x: f32,
+    width: f32,
+    text: String,
+    style: TextStyle,
+}
+
+struct TextLine {
+    fragments: Vec<Fragment>,
+    width: f32,
+    height: f32,
+}
+
+impl TextLine {
+    fn new(height: f32) -> Self {
+        Self {
+            fragments: Vec::new(),
+            width: 0.0,
+            height,
+        }
+    }
+
+    fn max_size(&self) -> f32 {
+        self.fragments.iter().map(|fragment| fragment.style.size).fold(0.0, f32::max)
+    }
+}
+
+/// Medidas de una fuente, para partir las líneas.
+struct FontFace<'a> {
+    face: ttf_parser::Face<'a>,
+    units_per_em: f32,
+}
+
+impl<'a> FontFace<'a> {
+    fn parse(bytes: &'a [u8]) -> io::Result<Self> {
+        let face = ttf_parser::Face::parse(bytes, 0).map_err(io::Error::other)?;
+        let units_per_em = face.units_per_em() as f32;
+        Ok(Self { face, units_per_em })
+    }
+
+    /// Ancho del texto. Los caracteres que la fuente no tiene no se dibujan y no ocupan lugar.
+    fn width(&self, text: &str, size: f32) -> f32 {
+        let units: f32 = text.chars()
+            .filter_map(|c| self.face.glyph_index(c))
+            .filter_map(|glyph| self.face.glyph_hor_advance(glyph))
+            .map(f32::from)
+            .sum();
+        units / self.units_per_em * size
+    }
+}
+
+/// Enlace a crear sobre una zona de una página, en coordenadas PDF.
+struct PageLink {
+    page: usize,
+    rect: [f32; 4],
+    target: String,
+}
+
+struct PdfWriter<'a> {
+    pdf: PdfDocumentReference,
+    fonts: [IndirectFontRef; 2],
+    faces: [FontFace<'a>; 2],
+    width: f32,
+    height: f32,
+    margin: f32,
+    pages: Vec<(PdfPageIndex, PdfLayerIndex)>,
+    /// Posición vertical desde el borde superior de la página.
+    y: f32,
+    links: Vec<PageLink>,
+    /// Página y altura de cada encabezado escrito, por índice de bloque.
+    headings: HashMap<usize, (usize, f32)>,
+}
+
+impl<'a> PdfWriter<'a> {
+    fn new(title: &str, options: &PdfOptions, regular_bytes: &'a [u8], mono_bytes: &'a [u8]) -> io::Result<Self> {
+        let pdf = PdfDocument::empty(title).with_conformance(PdfConformance::Custom(CustomPdfConformance {
+            requires_icc_profile: false,
+            requires_xmp_metadata: false,
+            ..Default::default()
+        }));
+        let regular = pdf.add_external_font(regular_bytes).map_err(io::Error::other)?;
+        let mono = pdf.add_external_font(mono_bytes).map_err(io::Error::other)?;
+
+        let (width, height) = options.page_size.dimensions();
+        let mut writer = PdfWriter {
+            pdf,
+            fonts: [regular, mono],
+            faces: [FontFace::parse(regular_bytes)?, FontFace::parse(mono_bytes)?],
+            width,
+            height,
+            margin: Pt::from(Mm(options.margin)).0,
+            pages: Vec::new(),
+            y: 0.0,
+            links: Vec::new(),
+            headings: HashMap::new(),
+        };
+        writer.new_page();
+        Ok(writer)
+    }
+
+    fn new_page(&mut self) {
+        let name = format!("Page {}", self.pages.len() + 1);
+        let page = self.pdf.add_page(Mm::from(Pt(self.width)), Mm::from(Pt(self.height)), name);
+        self.pages.push(page);
+        self.y = self.margin;
+    }
+
+    fn layer(&self, page: usize) -> PdfLayerReference {
+        let (page, layer) = self.pages[page];
+        self.pdf.get_page(page).get_layer(layer)
+    }
+
+    fn current_layer(&self) -> PdfLayerReference {
+        self.layer(self.pages.len() - 1)
+    }
+
+    fn page(&self) -> usize {
+        self.pages.len() - 1
+    }
+
+    fn content_width(&self) -> f32 {
+        self.width - 2.0 * self.margin
+    }
+
+    fn bottom(&self) -> f32 {
+        self.height - self.margin
+    }
+
+    /// Pasa a una página nueva si lo que sigue no entra en esta.
+    fn ensure(&mut self, height: f32) {
+        if self.y + height > self.bottom() && self.y > self.margin {
+            self.new_page();
+        }
+    }
+
+    /// Espacio entre bloques; se omite al principio de una página.
+    fn space(&mut self, amount: f32) {
+        if self.y > self.margin {
+            self.y += amount;
+        }
+    }
+
+    fn face(&self, font: FontKind) -> &FontFace<'_> {
+        match font {
+            FontKind::Regular => &self.faces[0],
+            FontKind::Mono => &self.faces[1],
+        }
+    }
+
+    fn text_width(&self, text: &str, style: &TextStyle) -> f32 {
+        self.face(style.font).width(text, style.size)
+    }
+
+    fn block(&mut self, index: usize, block: &Block) {
+        let left = self.margin + QUOTE_INDENT * block.quote_depth as f32;
+        let width = self.content_width() - QUOTE_INDENT * block.quote_depth as f32;
+        let start = (self.page(), self.y);
+
+        match &block.kind {
+            BlockKind::Heading { level, spans } => {
+                let major = matches!(level, HeadingLevel::H1 | HeadingLevel::H2);
+                self.space(if major { 16.0 } else { 12.0 });
+                let style = TextStyle {
+                    size: heading_size(*level),
+                    bold: true,
+                    color: heading_color(*level),
+                    ..TextStyle::body()
+                };
+                let lines = self.layout(spans, &style, width);
+                // El encabezado no queda solo al pie de la página
+                let text_height: f32 = lines.iter().map(|line| line.height).sum();
+                self.ensure(text_height + BODY_SIZE * LINE_SPACING * 2.0);
+                self.headings.insert(index, (self.page(), self.y));
+                self.lines(&lines, left, width, None);
+                if major {
+                    self.y += 3.0;
+                    self.rule(left, width);
+                    self.y += 4.0;
+                }
+                self.y += 4.0;
+            }
+            BlockKind::Paragraph { spans } => {
+                self.space(4.0);
+                let lines = self.layout(spans, &TextStyle::body(), width);
+                self.lines(&lines, left, width, None);
+                self.y += 4.0;
+            }
+            BlockKind::ListItem { marker, spans } => {
+                let marker_x = left + LIST_INDENT * block.indent as f32;
+                let text_x = marker_x + LIST_INDENT;
+                let lines = self.layout(spans, &TextStyle::body(), left + width - text_x);
+                self.space(2.0);
+                if let (Some(marker), Some(first)) = (marker, lines.first()) {
+                    self.ensure(first.height);
+                    self.list_marker(marker, marker_x, first.height);
+                }
+                self.lines(&lines, text_x, left + width - text_x, None);
+                self.y += 2.0;
+            }
+            BlockKind::CodeBlock { lang, code } => {
+                self.space(6.0);
+                if !lang.is_empty() {
+                    let style = TextStyle { color: MUTED_COLOR, ..TextStyle::code() };
+                    let height = CODE_SIZE * LINE_SPACING;
+                    self.ensure(height + CODE_SIZE * LINE_SPACING + 2.0 * CODE_PADDING);
+                    self.text(&format!("```{}", lang), left, self.y, height, &style);
+                    self.y += height;
+                }
+                self.code_block(code, left, width);
+                self.y += 6.0;
+            }
+            BlockKind::Table { alignments, rows } => {
+                self.space(6.0);
+                self.table(alignments, rows, left, width);
+                self.y += 6.0;
+            }
+            BlockKind::Rule => {
+                self.space(8.0);
+                self.ensure(1.0);
+                self.rule(left, width);
+                self.y += 8.0;
+            }
+        }
+
+        if block.quote_depth > 0 {
+            self.quote_bars(block.quote_depth, start);
+        }
+    }
+
+    /// Parte los fragmentos en líneas del ancho dado, palabra por palabra.
+    fn layout(&self, spans: &[Span], base: &TextStyle, width: f32) -> Vec<TextLine> {
+        let empty_height = base.size * LINE_SPACING;
+        let mut lines = Vec::new();
+        let mut line = TextLine::new(empty_height);
+
+        for span in spans {
+            let style = base.with_span(span);
+            for (index, paragraph) in span.text.split('\n').enumerate() {
+                // Salto de línea forzado dentro del texto
+                if index > 0 {
+                    lines.push(finish_line(line, self));
+                    line = TextLine::new(empty_height);
+                }
+                for word in paragraph.split_inclusive(' ') {
+                    let word_width = self.text_width(word.trim_end(), &style);
+                    if line.width > 0.0 && line.width + word_width > width {
+                        lines.push(finish_line(line, self));
+                        line = TextLine::new(empty_height);
+                    }
+                    if word_width > width {
+                        // Una palabra más larga que la línea se corta donde haga falta
+                        for c in word.chars() {
+                            let mut buffer = [0; 4];
+                            let c = c.encode_utf8(&mut buffer);
+                            let char_width = self.text_width(c, &style);
+                            if line.width > 0.0 && line.width + char_width > width {
+                                lines.push(finish_line(line, self));
+                                line = TextLine::new(empty_height);
+                            }
+                            self.push_text(&mut line, c, &style);
+                        }
+                    } else {
+                        self.push_text(&mut line, word, &style);
+                    }
+                }
+            }
+        }
+        if !line.fragments.is_empty() || lines.is_empty() {
+            lines.push(finish_line(line, self));
+        }
+        lines
+    }
+
+    fn push_text(&self, line: &mut TextLine, text: &str, style: &TextStyle) {
+        // Los espacios al principio de una línea no se ven
+        let text = if line.fragments.is_empty() { text.trim_start() } else { text };
+        if text.is_empty() {
+            return;
+        }
+        let width = self.text_width(text, style);
+        line.height = line.height.max(style.size * LINE_SPACING);
+        match line.fragments.last_mut() {
+            Some(last) if last.style == *style => {
+                last.text.push_str(text);
+                last.width += width;
+            }
+            _ => line.fragments.push(Fragment {
+                x: line.width,
+                width,
+                text: text.to_string(),
+                style: style.clone(),
+            }),
+        }
+        line.width += width;
+    }
+
+    /// Dibuja líneas de texto una debajo de otra, pasando de página cuando hace falta.
+    fn lines(&mut self, lines: &[TextLine], left: f32, width: f32, alignment: Option<Alignment>) {
+        for line in lines {
+            self.ensure(line.height);
+            self.line(line, left, width, self.y, alignment);
+            self.y += line.height;
+        }
+    }
+
+    fn line(&mut self, line: &TextLine, left: f32, width: f32, top: f32, alignment: Option<Alignment>) {
+        let offset = match alignment {
+            Some(Alignment::Center) => (width - line.width) / 2.0,
+            Some(Alignment::Right) => width - line.width,
+            _ => 0.0,
+        };
+        let max_size = line.max_size();
+        for fragment in &line.fragments {
+            let x = left + offset + fragment.x;
+            self.text(&fragment.text, x, top, line.height, &fragment.style);
+
+            let baseline = baseline(top, line.height, max_size);
+            if let Some(link) = &fragment.style.link {
+                self.stroke_line(x, baseline + 1.5, x + fragment.width, baseline + 1.5, 0.5, LINK_COLOR);
+                self.links.push(PageLink {
+                    page: self.page(),
+                    rect: [x, self.height - top - line.height, x + fragment.width, self.height - top],
+                    target: link.clone(),
+                });
+            }
+            if fragment.style.strikethrough {
+                let y = baseline - fragment.style.size * 0.3;
+                self.stroke_line(x, y, x + fragment.width, y, 0.6, fragment.style.color);
+            }
+        }
+    }
+
+    /// Escribe un texto con su línea base centrada en una fila de alto `height`.
+    fn text(&mut self, text: &str, x: f32, top: f32, height: f32, style: &TextStyle) {
+        let layer = self.current_layer();
+        if let Some(background) = style.background {
+            let box_height = style.size * 1.3;
+            let width = self.text_width(text, style);
+            self.fill_rect(x - 1.0, top + (height - box_height) / 2.0, width + 2.0, box_height, background);
+        }
+
+        let font = match style.font {
+            FontKind::Regular => &self.fonts[0],
+            FontKind::Mono => &self.fonts[1],
+        };
+        let baseline = self.height - baseline(top, height, style.size);
+        let skew = if style.italic { ITALIC_SKEW } else { 0.0 };
+        layer.begin_text_section();
+        layer.set_font(font, style.size);
+        layer.set_fill_color(rgb(style.color));
+        if style.bold {
+            // La fuente incrustada no tiene variante negrita: se engrosa el trazo
+            layer.set_text_rendering_mode(TextRenderingMode::FillStroke);
+            layer.set_outline_color(rgb(style.color));
+            layer.set_outline_thickness(style.size * 0.04);
+        }
+        layer.set_text_matrix(TextMatrix::Raw([1.0, 0.0, skew, 1.0, x, baseline]));
+        layer.write_text(text, font);
+        if style.bold {
+            layer.set_text_rendering_mode(TextRenderingMode::Fill);
+        }
+        layer.end_text_section();
+    }
+
+    fn list_marker(&mut self, marker: &ListMarker, x: f32, height: f32) {
+        let style = TextStyle::body();
+        match marker {
+            ListMarker::Bullet => self.text("•", x + 3.0, self.y, height, &style),
+            ListMarker::Ordered(number) => self.text(&format!("{}.", number), x, self.y, height, &style),
+            ListMarker::Task(checked) => {
+                let size = BODY_SIZE * 0.8;
+                let top = self.y + (height - size) / 2.0;
+                self.stroke_rect(x, top, size, size, 0.7, TEXT_COLOR);
+                if *checked {
+                    self.fill_rect(x + 2.0, top + 2.0, size - 4.0, size - 4.0, TEXT_COLOR);
+                }
+            }
+        }
+    }
+
+    /// Bloque de código con fondo. Las líneas largas se parten en el ancho
+    /// disponible; si el bloque no entra, sigue en la página siguiente.
+    fn code_block(&mut self, code: &str, left: f32, width: f32) {
+        let style = TextStyle::code();
+        let row_height = CODE_SIZE * LINE_SPACING;
+        let text_width = width - 2.0 * CODE_PADDING;
+        let mut rows = Vec::new();
+        for line in code.trim_end_matches('\n').split('\n') {
+            let mut row = String::new();
+            let mut row_width = 0.0;
+            for c in line.chars() {
+                let mut buffer = [0; 4];
+                let char_width = self.text_width(c.encode_utf8(&mut buffer), &style);
+                if row_width + char_width > text_width && !row.is_empty() {
+                    rows.push(std::mem::take(&mut row));
+                    row_width = 0.0;
+                }
+                row.push(c);
+                row_width += char_width;
+            }
+            rows.push(row);
+        }
+
+        let mut rest = &rows[..];
+        while !rest.is_empty() {
+            self.ensure(row_height + 2.0 * CODE_PADDING);
+            let available = self.bottom() - self.y - 2.0 * CODE_PADDING;
+            let count = ((available / row_height).floor() as usize).clamp(1, rest.len());
+            let height = count as f32 * row_height + 2.0 * CODE_PADDING;
+            self.fill_rect(left, self.y, width, height, CODE_BACKGROUND);
+            self.stroke_rect(left, self.y, width, height, 0.5, BORDER_COLOR);
+            for (index, row) in rest[..count].iter().enumerate() {
+                let top = self.y + CODE_PADDING + index as f32 * row_height;
+                self.text(row, left + CODE_PADDING, top, row_height, &style);
+            }
+            self.y += height;
+            rest = &rest[count..];
+            if !rest.is_empty() {
+                self.new_page();
+            }
+        }
+    }
+
+    /// Tabla con bordes. Si no entra en la página, sigue en la siguiente
+    /// repitiendo la fila de cabecera.
+    fn table(&mut self, alignments: &[Alignment], rows: &[Vec<Vec<Span>>], left: f32, width: f32) {
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+        let header_style = TextStyle { bold: true, ..TextStyle::body() };
+        let style_of = |row: usize| if row == 0 { header_style.clone() } else { TextStyle::body() };
+
+        // Ancho natural (sin partir líneas) y mínimo (la palabra más larga) de cada columna
+        let mut natural = vec![0.0f32; columns];
+        let mut minimum = vec![0.0f32; columns];
+        for (row_index, row) in rows.iter().enumerate() {
+            let base = style_of(row_index);
+            for (column, cell) in row.iter().enumerate() {
+                let line_width: f32 = self.layout(cell, &base, f32::INFINITY).iter().map(|line| line.width).fold(0.0, f32::max);
+                let word_width = cell.iter()
+                    .flat_map(|span| {
+                        let style = base.with_span(span);
+                        span.text.split_whitespace().map(move |word| (word, style.clone())).collect::<Vec<_>>()
+                    })
+                    .map(|(word, style)| self.text_width(word, &style))
+                    .fold(0.0, f32::max);
+                natural[column] = natural[column].max(line_width + 2.0 * CELL_PADDING);
+                minimum[column] = minimum[column].max(word_width + 2.0 * CELL_PADDING);
+            }
+        }
+        let widths = column_widths(&natural, &minimum, width);
+
+        let layouts: Vec<Vec<Vec<TextLine>>> = rows.iter().enumerate()
+            .map(|(row_index, row)| {
+                let base = style_of(row_index);
+                row.iter().enumerate()
+                    .map(|(column, cell)| self.layout(cell, &base, widths[column] - 2.0 * CELL_PADDING))
+                    .collect()
+            })
+            .collect();
+        let heights: Vec<f32> = layouts.iter()
+            .map(|row| {
+                row.iter().map(|lines| lines.iter().map(|line| line.height).sum::<f32>()).fold(0.0, f32::max) + 2.0 * CELL_PADDING
+            })
+            .collect();
+
+        for (row_index, row) in layouts.iter().enumerate() {
+            let page = self.page();
+            self.ensure(heights[row_index]);
+            if row_index > 0 && self.page() != page {
+                self.table_row(&layouts[0], 0, heights[0], &widths, alignments, left);
+            }
+            self.table_row(row, row_index, heights[row_index], &widths, alignments, left);
+        }
+    }
+
+    fn table_row(&mut self, row: &[Vec<TextLine>], row_index: usize, height: f32, widths: &[f32], alignments: &[Alignment], left: f32) {
+        let mut x = left;
+        for (column, width) in widths.iter().enumerate() {
+            if row_index == 0 {
+                self.fill_rect(x, self.y, *width, height, INLINE_CODE_BACKGROUND);
+            } else if row_index.is_multiple_of(2) {
+                self.fill_rect(x, self.y, *width, height, CODE_BACKGROUND);
+            }
+            self.stroke_rect(x, self.y, *width, height, 0.5, BORDER_COLOR);
+            if let Some(lines) = row.get(column) {
+                let mut top = self.y + CELL_PADDING;
+                for line in lines {
+                    let alignment = alignments.get(column).copied();
+                    self.line(line, x + CELL_PADDING, width - 2.0 * CELL_PADDING, top, alignment);
+                    top += line.height;
+                }
+            }
+            x += width;
+        }
+        self.y += height;
+    }
+
+    fn rule(&mut self, left: f32, width: f32) {
+        self.stroke_line(left, self.y, left + width, self.y, 0.7, BORDER_COLOR);
+    }
+
+    /// Barras verticales de las citas, desde `start` hasta la posición
+    /// actual, en todas las páginas que ocupe el bloque.
+    fn quote_bars(&mut self, depth: usize, start: (usize, f32)) {
+        let end = (self.page(), self.y);
+        for page in start.0..=end.0 {
+            let top = if page == start.0 { start.1 } else { self.margin };
+            let bottom = if page == end.0 { end.1 } else { self.bottom() };
+            let layer = self.layer(page);
+            layer.set_outline_color(rgb(BORDER_COLOR));
+            layer.set_outline_thickness(2.5);
+            for level in 0..depth {
+                let x = self.margin + QUOTE_INDENT * level as f32 + 3.0;
+                layer.add_line(self.path(&[(x, top), (x, bottom)]));
+            }
+        }
+    }
+
+    /// Número de página centrado al pie de cada página.
+    fn page_numbers(&mut self) {
+        let total = self.pages.len();
+        let style = TextStyle { size: FOOTER_SIZE, color: MUTED_COLOR, ..TextStyle::body() };
+        for page in 0..total {
+            let text = format!("{} / {}", page + 1, total);
+            let x = (self.width - self.text_width(&text, &style)) / 2.0;
+            let font = &self.fonts[0];
+            let layer = self.layer(page);
+            layer.begin_text_section();
+            layer.set_font(font, style.size);
+            layer.set_fill_color(rgb(style.color));
+            layer.set_text_matrix(TextMatrix::Raw([1.0, 0.0, 0.0, 1.0, x, self.margin / 2.0]));
+            layer.write_text(text, font);
+            layer.end_text_section();
+        }
+    }
+
+    fn fill_rect(&self, x: f32, top: f32, width: f32, height: f32, color: [f32; 3]) {
+        let layer = self.current_layer();
+        layer.set_fill_color(rgb(color));
+        layer.add_rect(self.rect(x, top, width, height));
+    }
+
+    fn stroke_rect(&self, x: f32, top: f32, width: f32, height: f32, thickness: f32, color: [f32; 3]) {
+        let layer = self.current_layer();
+        layer.set_outline_color(rgb(color));
+        layer.set_outline_thickness(thickness);
+        layer.add_rect(self.rect(x, top, width, height).with_mode(PaintMode::Stroke));
+    }
+
+    fn stroke_line(&self, x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: [f32; 3]) {
+        let layer = self.current_layer();
+        layer.set_outline_color(rgb(color));
+        layer.set_outline_thickness(thickness);
+        layer.add_line(self.path(&[(x1, y1), (x2, y2)]));
+    }
+
+    // Rectángulo dado desde el borde superior de la página
+    fn rect(&self, x: f32, top: f32, width: f32, height: f32) -> Rect {
+        Rect::new(
+            Mm::from(Pt(x)),
+            Mm::from(Pt(self.height - top - height)),
+            Mm::from(Pt(x + width)),
+            Mm::from(Pt(self.height - top)),
+        )
+    }
+
+    fn path(&self, points: &[(f32, f32)]) -> Line {
+        Line {
+            points: points.iter().map(|(x, y)| (Point::new(Mm::from(Pt(*x)), Mm::from(Pt(self.height - y))), false)).collect(),
+            is_closed: false,
+        }
+    }
+}
+
+// Quita los espacios del final de la línea, que no deben contar para alinearla
+fn finish_line(mut line: TextLine, writer: &PdfWriter) -> TextLine {
+    if let Some(last) = line.fragments.last_mut() {
+        let trimmed = last.text.trim_end().len();
+        if trimmed < last.text.len() {
+            last.text.truncate(trimmed);
+            last.width = writer.text_width(&last.text, &last.style);
+            line.width = last.x + last.width;
+        }
+    }
+    line
+}
+
+/// Línea base del texto centrado verticalmente en una fila.
+fn baseline(top: f32, height: f32, size: f32) -> f32 {
+    top + height / 2.0 + size * 0.35
+}
+
+/// Reparte el ancho entre las columnas: cada una recibe su ancho natural si
+/// entra todo; si no, su mínimo más una parte de lo que sobra.
+fn column_widths(natural: &[f32], minimum: &[f32], width: f32) -> Vec<f32> {
+    let natural_total: f32 = natural.iter().sum();
+    if natural_total <= width {
+        return natural.to_vec();
+    }
+    let minimum_total: f32 = minimum.iter().sum();
+    if minimum_total >= width {
+        return minimum.iter().map(|minimum| minimum * width / minimum_total).collect();
+    }
+    let extra = width - minimum_total;
+    let wanted: f32 = natural.iter().zip(minimum).map(|(natural, minimum)| natural - minimum).sum();
+    natural.iter().zip(minimum)
+        .map(|(natural, minimum)| minimum + extra * (natural - minimum) / wanted)
+        .collect()
+}
+
+fn heading_size(level: HeadingLevel) -> f32 {
+    match level {
+        HeadingLevel::H1 => 22.0,
+        HeadingLevel::H2 => 18.0,
+        HeadingLevel::H3 => 15.0,
+        HeadingLevel::H4 => 13.0,
+        HeadingLevel::H5 => 12.0,
+        HeadingLevel::H6 => 10.5,
+    }
+}
+
+fn heading_color(level: HeadingLevel) -> [f32; 3] {
+    let gray = match level {
+        HeadingLevel::H1 => 51.0,
+        HeadingLevel::H2 => 68.0,
+        HeadingLevel::H3 => 85.0,
+        HeadingLevel::H4 => 102.0,
+        HeadingLevel::H5 => 119.0,
+        HeadingLevel::H6 => 136.0,
+    } / 255.0;
+    [gray; 3]
+}
+
+fn rgb([r, g, b]: [f32; 3]) -> Color {
+    Color::Rgb(Rgb::new(r, g, b, None))
+}
+
+/// Agrega al PDF ya generado el índice de encabezados y los enlaces, que
+/// necesitan referencias a las páginas finales.
+fn add_navigation(bytes: &[u8], document: &Document, headings: &HashMap<usize, (usize, f32)>, links: &[PageLink], page_height: f32) -> io::Result<Vec<u8>> {
+    let mut pdf = lopdf::Document::load_mem(bytes).map_err(io::Error::other)?;
+    let pages: Vec<ObjectId> = pdf.get_pages().into_values().collect();
+    let destination = |(page, y): (usize, f32)| -> Object {
+        Object::Array(vec![
+            Object::Reference(pages[page]),
+            Object::Name(b"XYZ".to_vec()),
+            Object::Null,
+            Object::Real(page_height - y + 4.0),
+            Object::Null,
+        ])
+    };
+
+    // Enlaces: direcciones externas y anclas a encabezados del documento
+    for link in links {
+        let mut annotation = Dictionary::new();
+        annotation.set("Type", Object::Name(b"Annot".to_vec()));
+        annotation.set("Subtype", Object::Name(b"Link".to_vec()));
+        annotation.set("Rect", Object::Array(link.rect.iter().map(|value| Object::Real(*value)).collect()));
+        annotation.set("Border", Object::Array(vec![0.into(), 0.into(), 0.into()]));
+        if let Some(anchor) = link.target.strip_prefix('#') {
+            let anchor = crate::percent_decode(anchor);
+            let Some(heading) = document.headings.iter().find(|heading| heading.anchor == anchor) else {
+                continue;
+            };
+            let Some(position) = headings.get(&heading.block) else {
+                continue;
+            };
+            annotation.set("Dest", destination(*position));
+        } else if link.target.contains("://") || link.target.starts_with("mailto:") {
+            let mut action = Dictionary::new();
+            action.set("S", Object::Name(b"URI".to_vec()));
+            action.set("URI", Object::String(link.target.as_bytes().to_vec(), StringFormat::Literal));
+            annotation.set("A", action);
+        } else {
+            continue;
+        }
+        let id = pdf.add_object(annotation);
+        let page = pdf.get_dictionary_mut(pages[link.page]).map_err(io::Error::other)?;
+        match page.get_mut(b"Annots") {
+            Ok(Object::Array(annotations)) => annotations.push(Object::Reference(id)),
+            _ => page.set("Annots", Object::Array(vec![Object::Reference(id)])),
+        }
+    }
+
+    // Índice con los encabezados que llegaron a escribirse
+    let outline: Vec<(&Heading, (usize, f32))> = document.headings.iter()
+        .filter_map(|heading| Some((heading, *headings.get(&heading.block)?)))
+        .collect();
+    if !outline.is_empty() {
+        let outlines_id = pdf.new_object_id();
+        let ids: Vec<ObjectId> = outline.iter().map(|_| pdf.new_object_id()).collect();
+        let levels: Vec<u8> = outline.iter().map(|(heading, _)| heading.level).collect();
+        let parents = outline_parents(&levels);
+
+        let children = |parent: Option<usize>| -> Vec<usize> {
+            (0..outline.len()).filter(|index| parents[*index] == parent).collect()
+        };
+
+        for index in 0..outline.len() {
+            let siblings = children(parents[index]);
+            let position = siblings.iter().position(|sibling| *sibling == index).unwrap_or(0);
+            let own_children = children(Some(index));
+            let mut item = Dictionary::new();
+            item.set("Title", Object::String(utf16_text(&outline[index].0.text), StringFormat::Hexadecimal));
+            item.set("Parent", Object::Reference(parents[index].map_or(outlines_id, |parent| ids[parent])));
+            item.set("Dest", destination(outline[index].1));
+            if position > 0 {
+                item.set("Prev", Object::Reference(ids[siblings[position - 1]]));
+            }
+            if let Some(next) = siblings.get(position + 1) {
+                item.set("Next", Object::Reference(ids[*next]));
+            }
+            if let (Some(first), Some(last)) = (own_children.first(), own_children.last()) {
+                item.set("First", Object::Reference(ids[*first]));
+                item.set("Last", Object::Reference(ids[*last]));
+                item.set("Count", Object::Integer(outline_descendants(&levels, index) as i64));
+            }
+            pdf.objects.insert(ids[index], Object::Dictionary(item));
+        }
+
+        let top_level = children(None);
+        let mut outlines = Dictionary::new();
+        outlines.set("Type", Object::Name(b"Outlines".to_vec()));
+        outlines.set("First", Object::Reference(ids[top_level[0]]));
+        outlines.set("Last", Object::Reference(ids[*top_level.last().unwrap_or(&top_level[0])]));
+        outlines.set("Count", Object::Integer(outline.len() as i64));
+        pdf.objects.insert(outlines_id, Object::Dictionary(outlines));
+
+        let catalog = pdf.catalog_mut().map_err(io::Error::other)?;
+        // Reemplaza el índice vacío que escribe printpdf
+        let empty_outlines = catalog.get(b"Outlines").and_then(Object::as_reference).ok();
+        catalog.set("Outlines", Object::Reference(outlines_id));
+        catalog.set("PageMode", Object::Name(b"UseOutlines".to_vec()));
+        if let Some(id) = empty_outlines {
+            pdf.objects.remove(&id);
+        }
+    }
+
+    let mut output = Vec::new();
+    pdf.save_to(&mut output)?;
+    Ok(output)
+}
+
+/// Padre de cada entrada del índice: el último encabezado anterior de nivel
+/// superior, o `None` en el primer nivel.
+fn outline_parents(levels: &[u8]) -> Vec<Option<usize>> {
+    let mut parents = vec![None; levels.len()];
+    let mut stack: Vec<usize> = Vec::new();
+    for index in 0..levels.len() {
+        while stack.last().is_some_and(|last| levels[*last] >= levels[index]) {
+            stack.pop();
+        }
+        parents[index] = stack.last().copied();
+        stack.push(index);
+    }
+    parents
+}
+
+// Los descendientes son los encabezados de nivel inferior que siguen
+fn outline_descendants(levels: &[u8], index: usize) -> usize {
+    levels[index + 1..].iter().take_while(|level| **level > levels[index]).count()
+}
+
+/// Texto en UTF-16BE con marca de orden de bytes, como lo esperan los
+/// títulos del índice de un PDF.
+fn utf16_text(text: &str) -> Vec<u8> {
+    let mut bytes = vec![0xFE, 0xFF];
+    for unit in text.encode_utf16() {
+        bytes.extend_from_slice(&unit.to_be_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(source: &str) -> Vec<Span> {
+        match &Document::parse(source).blocks[0].kind {
+            BlockKind::Paragraph { spans } => spans.clone(),
+            kind => panic!("no es un párrafo: {kind:?}"),
+        }
+    }
+
+    fn line_text(line: &TextLine) -> String {
+        line.fragments.iter().map(|fragment| fragment.text.as_str()).collect()
+    }
+
+    fn outline_titles(pdf: &lopdf::Document) -> Vec<(Vec<u8>, Option<i64>)> {
+        let mut titles = Vec::new();
+        let mut stack = vec![pdf.catalog().unwrap().get(b"Outlines").unwrap().as_reference().unwrap()];
+        let outlines = pdf.get_dictionary(stack[0]).unwrap();
+        stack[0] = outlines.get(b"First").unwrap().as_reference().unwrap();
+        // Recorrido en profundidad: primero los hijos, después el siguiente hermano
+        while let Some(id) = stack.pop() {
+            let item = pdf.get_dictionary(id).unwrap();
+            let title = item.get(b"Title").unwrap().as_str().unwrap().to_vec();
+            titles.push((title, item.get(b"Count").ok().map(|count| count.as_i64().unwrap())));
+            if let Ok(next) = item.get(b"Next") {
+                stack.push(next.as_reference().unwrap());
+            }
+            if let Ok(first) = item.get(b"First") {
+                stack.push(first.as_reference().unwrap());
+            }
+        }
+        titles
+    }
+
+    #[test]
+    fn columns_keep_their_natural_width_when_it_fits() {
+        assert_eq!(column_widths(&[30.0, 50.0], &[10.0, 20.0], 100.0), vec![30.0, 50.0]);
+    }
+
+    #[test]
+    fn columns_share_the_space_left_over_their_minimum() {
+        // Sobran 30 puntos sobre los mínimos; se reparten según lo que le falta a cada una
+        assert_eq!(column_widths(&[70.0, 50.0], &[10.0, 20.0], 60.0), vec![30.0, 30.0]);
+    }
+
+    #[test]
+    fn columns_shrink_proportionally_below_their_minimum() {
+        assert_eq!(column_widths(&[70.0, 50.0], &[40.0, 20.0], 30.0), vec![20.0, 10.0]);
+    }
+
+    #[test]
+    fn text_wraps_between_words_without_trailing_spaces() {
+        let (regular, mono) = font_bytes().unwrap();
+        let writer = PdfWriter::new("prueba", &PdfOptions::default(), &regular, &mono).unwrap();
+        let words = "uno dos tres cuatro cinco seis siete ocho nueve diez";
+        let lines = writer.layout(&spans(words), &TextStyle::body(), 80.0);
+
+        assert!(lines.len() > 1);
+        for line in &lines {
+            assert!(line.width <= 80.0, "{}", line_text(line));
+            assert_eq!(line_text(line), line_text(line).trim());
+        }
+        let joined: Vec<String> = lines.iter().map(line_text).collect();
+        assert_eq!(joined.join(" "), words);
+    }
+
+    #[test]
+    fn long_words_and_hard_breaks_start_new_lines() {
+        let (regular, mono) = font_bytes().unwrap();
+        let writer = PdfWriter::new("prueba", &PdfOptions::default(), &regular, &mono).unwrap();
+        let word = "a".repeat(60);
+        let lines = writer.layout(&spans(&word), &TextStyle::body(), 50.0);
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| line.width <= 50.0));
+        assert_eq!(lines.iter().map(line_text).collect::<String>(), word);
+
+        let lines = writer.layout(&spans("uno\\\ndos"), &TextStyle::body(), 500.0);
+        assert_eq!(lines.iter().map(line_text).collect::<Vec<_>>(), vec!["uno", "dos"]);
+    }
+
+    #[test]
+    fn outline_entries_hang_from_the_previous_higher_heading() {
+        let levels = [1, 2, 3, 2, 1, 3];
+        assert_eq!(outline_parents(&levels), vec![None, Some(0), Some(1), Some(0), None, Some(4)]);
+        let descendants: Vec<usize> = (0..levels.len()).map(|index| outline_descendants(&levels, index)).collect();
+        assert_eq!(descendants, vec![3, 1, 0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn titles_are_utf16_with_byte_order_mark() {
+        assert_eq!(utf16_text("Añ"), vec![0xFE, 0xFF, 0x00, 0x41, 0x00, 0xF1]);
+        assert_eq!(utf16_text("😀"), vec![0xFE, 0xFF, 0xD8, 0x3D, 0xDE, 0x00]);
+    }
+
+    #[test]
+    fn headings_form_the_outline() {
+        let document = Document::parse("# Uno\n\n## Dos\n\n### Tres\n\n# Cuatro\n");
+        let bytes = export_pdf(&document, "prueba", &PdfOptions::default()).unwrap();
+        let pdf = lopdf::Document::load_mem(&bytes).unwrap();
+        assert_eq!(
+            outline_titles(&pdf),
+            vec![
+                (utf16_text("Uno"), Some(2)),
+                (utf16_text("Dos"), Some(1)),
+                (utf16_text("Tres"), None),
+                (utf16_text("Cuatro"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn headings_without_a_position_are_left_out() {
+        let document = Document::parse("# Uno\n\n## Dos\n\n# Tres\n");
+        let (regular, mono) = font_bytes().unwrap();
+        let writer = PdfWriter::new("prueba", &PdfOptions::default(), &regular, &mono).unwrap();
+        let bytes = writer.pdf.save_to_bytes().unwrap();
+        // Solo se conoce la posición del segundo encabezado
+        let positions = HashMap::from([(document.headings[1].block, (0, 100.0))]);
+        let output = add_navigation(&bytes, &document, &positions, &[], 800.0).unwrap();
+        let pdf = lopdf::Document::load_mem(&output).unwrap();
+        assert_eq!(outline_titles(&pdf), vec![(utf16_text("Dos"), None)]);
+    }
+}
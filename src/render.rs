@@ -0,0 +1,475 @@
+use crate::changes::Change;
+use crate::document::{Block, BlockKind, Document, ListMarker, Span, SpanStyle};
+use crate::search::DocumentMatch;
+use eframe::egui;
+use pulldown_cmark::HeadingLevel;
+use std::ops::Range;
+use std::sync::Arc;
+
+#[derive(Default)]
+pub struct RenderOptions<'a> {
+    /// Bloque al que hay que desplazar el `ScrollArea` en este frame.
+    pub scroll_to_block: Option<usize>,
+    /// Coincidencias de búsqueda a resaltar, ordenadas por bloque.
+    pub matches: &'a [DocumentMatch],
+    pub current_match: Option<usize>,
+    pub scroll_to_match: bool,
+}
+
+#[derive(Default)]
+pub struct RenderOutput {
+    /// Rectángulo (en coordenadas de pantalla) de cada bloque del documento.
+    pub block_rects: Vec<egui::Rect>,
+    pub clicked_link: Option<String>,
+}
+
+/// Rango de texto resaltado por la búsqueda.
+pub struct Highlight {
+    pub range: Range<usize>,
+    pub current: bool,
+}
+
+// Coincidencias que caen dentro de un bloque
+struct BlockMatches<'a> {
+    matches: &'a [DocumentMatch],
+    first_index: usize,
+    current: Option<usize>,
+    scroll: bool,
+}
+
+impl BlockMatches<'_> {
+    fn unit(&self, unit: usize) -> Vec<Highlight> {
+        self.matches
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.unit == unit)
+            .map(|(offset, m)| Highlight {
+                range: m.range.clone(),
+                current: self.current == Some(self.first_index + offset),
+            })
+            .collect()
+    }
+}
+
+struct TextLook {
+    size: f32,
+    color: Option<egui::Color32>,
+    strong: bool,
+}
+
+pub fn render_document(ui: &mut egui::Ui, document: &Document, options: &RenderOptions) -> RenderOutput {
+    let mut output = RenderOutput::default();
+    let mut previous_in_list = false;
+    let mut match_start = 0;
+
+    for (index, block) in document.blocks.iter().enumerate() {
+        let match_count = options.matches[match_start..]
+            .iter()
+            .take_while(|m| m.block == index)
+            .count();
+        let matches = BlockMatches {
+            matches: &options.matches[match_start..match_start + match_count],
+            first_index: match_start,
+            current: options.current_match,
+            scroll: options.scroll_to_match,
+        };
+        match_start += match_count;
+
+        // Espacio extra al entrar y salir de una lista
+        let in_list = matches!(block.kind, BlockKind::ListItem { .. });
+        if in_list != previous_in_list {
+            ui.add_space(8.0);
+        }
+        previous_in_list = in_list;
+
+        let rect = ui.scope(|ui| render_block(ui, index, block, &matches, &mut output)).response.rect;
+        output.block_rects.push(rect);
+
+        if options.scroll_to_block == Some(index) {
+            ui.scroll_to_rect(rect, Some(egui::Align::TOP));
+        }
+    }
+
+    output
+}
+
+fn render_block(ui: &mut egui::Ui, index: usize, block: &Block, matches: &BlockMatches, output: &mut RenderOutput) {
+    if block.quote_depth == 0 {
+        render_block_body(ui, index, block, matches, output);
+        return;
+    }
+
+    // Citas: sangría con una barra vertical por cada nivel
+    let quote_indent = 14.0;
+    let response = ui
+        .horizontal(|ui| {
+            ui.add_space(quote_indent * block.quote_depth as f32);
+            ui.vertical(|ui| render_block_body(ui, index, block, matches, output));
+        })
+        .response;
+
+    let bar_color = if ui.visuals().dark_mode {
+        egui::Color32::from_rgb(90, 90, 90)
+    } else {
+        egui::Color32::from_rgb(200, 200, 200)
+    };
+    for depth in 0..block.quote_depth {
+        let x = response.rect.left() + quote_indent * depth as f32 + 3.0;
+        ui.painter().vline(x, response.rect.y_range(), egui::Stroke::new(3.0, bar_color));
+    }
+}
+
+fn render_block_body(ui: &mut egui::Ui, index: usize, block: &Block, matches: &BlockMatches, output: &mut RenderOutput) {
+    match &block.kind {
+        BlockKind::Heading { level, spans } => {
+            ui.add_space(20.0);
+            let look = TextLook {
+                size: heading_size(*level),
+                color: Some(heading_color(*level, ui.visuals().dark_mode)),
+                strong: true,
+            };
+            render_spans(ui, spans, &look, &matches.unit(0), matches.scroll, output);
+
+            if matches!(level, HeadingLevel::H1 | HeadingLevel::H2) {
+                ui.add_space(5.0);
+                ui.separator();
+            }
+            ui.add_space(10.0);
+        }
+        BlockKind::Paragraph { spans } => {
+            ui.add_space(8.0);
+            render_spans(ui, spans, &body_look(), &matches.unit(0), matches.scroll, output);
+            ui.add_space(8.0);
+        }
+        BlockKind::ListItem { marker, spans } => {
+            ui.horizontal(|ui| {
+                ui.add_space(block.indent as f32 * 20.0);
+                match marker {
+                    Some(ListMarker::Bullet) => {
+                        ui.add(egui::Label::new(egui_phosphor::regular::DOT_OUTLINE));
+                    }
+                    Some(ListMarker::Ordered(number)) => {
+                        ui.add(egui::Label::new(egui::RichText::new(format!("{}.", number)).size(14.0)));
+                    }
+                    Some(ListMarker::Task(checked)) => {
+                        let icon = if *checked {
+                            egui_phosphor::regular::CHECK_SQUARE
+                        } else {
+                            egui_phosphor::regular::SQUARE
+                        };
+                        ui.add(egui::Label::new(icon));
+                    }
+                    None => ui.add_space(14.0),
+                }
+                ui.add_space(5.0);
+                ui.vertical(|ui| render_spans(ui, spans, &body_look(), &matches.unit(0), matches.scroll, output));
+            });
+        }
+        BlockKind::CodeBlock { lang, code } => {
+            ui.add_space(10.0);
+            if !lang.is_empty() {
+                ui.add(egui::Label::new(
+                    egui::RichText::new(format!("```{}", lang))
+                        .monospace()
+                        .size(12.0)
+                        .color(egui::Color32::GRAY),
+                ));
+            }
+            let mut code = code.trim_end_matches('\n');
+            let rows = code.lines().count().max(1);
+            let highlights = matches.unit(0);
+            let mut layouter = highlight_layouter(&highlights);
+            egui::Frame::new()
+                .fill(if ui.visuals().dark_mode {
+                    egui::Color32::from_rgb(30, 30, 30)
+                } else {
+                    egui::Color32::from_rgb(248, 248, 248)
+                })
+                .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(200, 200, 200)))
+                .inner_margin(10.0)
+                .show(ui, |ui| {
+                    let mut editor = egui::TextEdit::multiline(&mut code)
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(rows);
+                    if !highlights.is_empty() {
+                        editor = editor.layouter(&mut layouter);
+                    }
+                    let text_output = editor.show(ui);
+
+                    if matches.scroll {
+                        if let Some(current) = highlights.iter().find(|h| h.current) {
+                            scroll_to_text_offset(ui, &text_output, code, current.range.start);
+                        }
+                    }
+                });
+            ui.add_space(10.0);
+        }
+        BlockKind::Table { rows, .. } => {
+            ui.add_space(8.0);
+            egui::Frame::new()
+                .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(200, 200, 200)))
+                .inner_margin(6.0)
+                .show(ui, |ui| {
+                    egui::Grid::new(("md_table", index))
+                        .striped(true)
+                        .spacing([16.0, 6.0])
+                        .show(ui, |ui| {
+                            let mut cell_index = 0;
+                            for (row_index, row) in rows.iter().enumerate() {
+                                let look = TextLook {
+                                    strong: row_index == 0,
+                                    ..body_look()
+                                };
+                                for cell in row {
+                                    render_spans(ui, cell, &look, &matches.unit(cell_index), matches.scroll, output);
+                                    cell_index += 1;
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            ui.add_space(8.0);
+        }
+        BlockKind::Rule => {
+            ui.add_space(8.0);
+            ui.separator();
+            ui.add_space(8.0);
+        }
+    }
+}
+
+fn body_look() -> TextLook {
+    TextLook {
+        size: 14.0,
+        color: None,
+        strong: false,
+    }
+}
+
+fn render_spans(
+    ui: &mut egui::Ui,
+    spans: &[Span],
+    look: &TextLook,
+    highlights: &[Highlight],
+    scroll_to_current: bool,
+    output: &mut RenderOutput,
+) {
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        let mut offset = 0;
+        for span in spans {
+            let span_range = offset..offset + span.text.len();
+            offset = span_range.end;
+
+            // Partir el span donde empiezan o terminan las coincidencias
+            let mut cuts = vec![span_range.start, span_range.end];
+            for highlight in highlights {
+                for point in [highlight.range.start, highlight.range.end] {
+                    if span_range.start < point && point < span_range.end {
+                        cuts.push(point);
+                    }
+                }
+            }
+            cuts.sort_unstable();
+            cuts.dedup();
+
+            for segment in cuts.windows(2) {
+                let highlight = highlights
+                    .iter()
+                    .find(|h| h.range.start <= segment[0] && segment[1] <= h.range.end);
+                let content = &span.text[segment[0] - span_range.start..segment[1] - span_range.start];
+                let mut text = span_rich_text(ui, content, &span.style, look);
+                if let Some(highlight) = highlight {
+                    text = text.background_color(highlight_color(highlight.current));
+                }
+
+                let response = if let Some(link) = &span.style.link {
+                    let response = ui.link(text).on_hover_text(link);
+                    if response.clicked() {
+                        output.clicked_link = Some(link.clone());
+                    }
+                    response
+                } else {
+                    ui.add(egui::Label::new(text).wrap())
+                };
+
+                if scroll_to_current && highlight.is_some_and(|h| h.current) {
+                    ui.scroll_to_rect(response.rect, Some(egui::Align::Center));
+                }
+            }
+        }
+    });
+}
+
+fn highlight_color(current: bool) -> egui::Color32 {
+    if current {
+        egui::Color32::from_rgba_unmultiplied(255, 140, 0, 170)
+    } else {
+        egui::Color32::from_rgba_unmultiplied(255, 210, 0, 90)
+    }
+}
+
+/// `layouter` para un `TextEdit` monoespaciado que resalta las coincidencias de búsqueda.
+pub fn highlight_layouter(highlights: &[Highlight]) -> impl FnMut(&egui::Ui, &str, f32) -> Arc<egui::Galley> + '_ {
+    move |ui, text, wrap_width| {
+        let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        let mut job = highlight_job(ui, text, highlights, font_id);
+        job.wrap.max_width = wrap_width;
+        ui.fonts(|fonts| fonts.layout_job(job))
+    }
+}
+
+/// Texto con las coincidencias de búsqueda resaltadas.
+pub fn highlight_job(ui: &egui::Ui, text: &str, highlights: &[Highlight], font_id: egui::FontId) -> egui::text::LayoutJob {
+    let color = ui.visuals().text_color();
+    let mut job = egui::text::LayoutJob::default();
+    let mut position = 0;
+
+    for highlight in highlights {
+        // Los rangos pueden ser de antes de una edición
+        let range = &highlight.range;
+        if range.start < position || range.end > text.len() || !text.is_char_boundary(range.start) || !text.is_char_boundary(range.end) {
+            continue;
+        }
+        job.append(&text[position..highlight.range.start], 0.0, egui::TextFormat::simple(font_id.clone(), color));
+        job.append(
+            &text[highlight.range.clone()],
+            0.0,
+            egui::TextFormat {
+                background: highlight_color(highlight.current),
+                ..egui::TextFormat::simple(font_id.clone(), color)
+            },
+        );
+        position = highlight.range.end;
+    }
+    job.append(&text[position..], 0.0, egui::TextFormat::simple(font_id, color));
+    job
+}
+
+/// Desplaza el `ScrollArea` para mostrar la posición `byte_offset` de un `TextEdit`.
+pub fn scroll_to_text_offset(ui: &egui::Ui, output: &egui::text_edit::TextEditOutput, text: &str, byte_offset: usize) {
+    let mut byte_offset = byte_offset.min(text.len());
+    while !text.is_char_boundary(byte_offset) {
+        byte_offset -= 1;
+    }
+    let char_index = text[..byte_offset].chars().count();
+    let rect = output
+        .galley
+        .pos_from_ccursor(egui::text::CCursor::new(char_index))
+        .translate(output.galley_pos.to_vec2());
+    ui.scroll_to_rect(rect.expand(20.0), Some(egui::Align::Center));
+}
+
+/// Posición vertical (en pantalla) de varias posiciones de un `TextEdit`.
+/// `byte_offsets` debe estar ordenado.
+pub fn text_offset_tops(output: &egui::text_edit::TextEditOutput, text: &str, byte_offsets: &[usize]) -> Vec<f32> {
+    let mut char_index = 0;
+    let mut previous = 0;
+    byte_offsets
+        .iter()
+        .map(|offset| {
+            // Las posiciones pueden ser de antes de una edición
+            let mut offset = (*offset).clamp(previous, text.len());
+            while !text.is_char_boundary(offset) {
+                offset -= 1;
+            }
+            char_index += text[previous..offset].chars().count();
+            previous = offset;
+            output.galley.pos_from_ccursor(egui::text::CCursor::new(char_index)).top() + output.galley_pos.y
+        })
+        .collect()
+}
+
+/// Dibuja en el margen izquierdo las marcas de los bloques que cambiaron:
+/// una barra azul para los modificados, verde para los nuevos y un triángulo
+/// rojo donde se quitaron bloques. `block_spans` es el alto en pantalla de
+/// cada bloque.
+pub fn paint_change_markers(painter: &egui::Painter, gutter_x: f32, block_spans: &[Range<f32>], changes: &[Change], opacity: f32) {
+    let modified_color = egui::Color32::from_rgb(66, 133, 244).gamma_multiply(opacity);
+    let inserted_color = egui::Color32::from_rgb(52, 168, 83).gamma_multiply(opacity);
+    let deleted_color = egui::Color32::from_rgb(220, 70, 60).gamma_multiply(opacity);
+    let bar = |range: Range<usize>, color: egui::Color32| {
+        for span in block_spans.get(range).unwrap_or_default() {
+            let rect = egui::Rect::from_x_y_ranges(gutter_x..=gutter_x + 3.0, span.start..=span.end);
+            painter.rect_filled(rect, 1.0, color);
+        }
+    };
+
+    for change in changes {
+        bar(change.modified(), modified_color);
+        bar(change.inserted(), inserted_color);
+        if change.deleted == 0 {
+            continue;
+        }
+        // Entre el último bloque del cambio (o el anterior) y el siguiente
+        let y = if change.blocks.end > 0 {
+            block_spans.get(change.blocks.end - 1).map(|span| span.end)
+        } else {
+            block_spans.first().map(|span| span.start)
+        };
+        let Some(y) = y else {
+            continue;
+        };
+        painter.add(egui::Shape::convex_polygon(
+            vec![egui::pos2(gutter_x - 1.0, y - 5.0), egui::pos2(gutter_x + 6.0, y), egui::pos2(gutter_x - 1.0, y + 5.0)],
+            deleted_color,
+            egui::Stroke::NONE,
+        ));
+    }
+}
+
+fn span_rich_text(ui: &egui::Ui, content: &str, style: &SpanStyle, look: &TextLook) -> egui::RichText {
+    let mut text = egui::RichText::new(content).size(look.size);
+    if let Some(color) = look.color {
+        text = text.color(color);
+    }
+    if look.strong || style.strong {
+        text = text.strong();
+    }
+    if style.emphasis {
+        text = text.italics();
+    }
+    if style.strikethrough {
+        text = text.strikethrough();
+    }
+    if style.code {
+        text = text.monospace().background_color(if ui.visuals().dark_mode {
+            egui::Color32::from_rgb(45, 45, 45)
+        } else {
+            egui::Color32::from_rgb(240, 240, 240)
+        });
+    }
+    text
+}
+
+fn heading_size(level: HeadingLevel) -> f32 {
+    match level {
+        HeadingLevel::H1 => 28.0,
+        HeadingLevel::H2 => 24.0,
+        HeadingLevel::H3 => 20.0,
+        HeadingLevel::H4 => 18.0,
+        HeadingLevel::H5 => 16.0,
+        HeadingLevel::H6 => 14.0,
+    }
+}
+
+fn heading_color(level: HeadingLevel, dark_mode: bool) -> egui::Color32 {
+    if dark_mode {
+        match level {
+            HeadingLevel::H1 => egui::Color32::WHITE,
+            HeadingLevel::H2 => egui::Color32::from_rgb(230, 230, 230),
+            HeadingLevel::H3 => egui::Color32::from_rgb(210, 210, 210),
+            _ => egui::Color32::from_rgb(190, 190, 190),
+        }
+    } else {
+        match level {
+            HeadingLevel::H1 => egui::Color32::from_rgb(51, 51, 51),
+            HeadingLevel::H2 => egui::Color32::from_rgb(68, 68, 68),
+            HeadingLevel::H3 => egui::Color32::from_rgb(85, 85, 85),
+            HeadingLevel::H4 => egui::Color32::from_rgb(102, 102, 102),
+            HeadingLevel::H5 => egui::Color32::from_rgb(119, 119, 119),
+            HeadingLevel::H6 => egui::Color32::from_rgb(136, 136, 136),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use crate::tab::canonical_path;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// Cantidad de archivos recordados; se descartan los usados hace más tiempo
+const MAX_REMEMBERED_FILES: usize = 200;
+
+/// Posición de lectura de un archivo. Se ancla al encabezado visible arriba
+/// para que siga siendo válida si el archivo cambia antes de ese punto.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ScrollPosition {
+    /// Ancla del encabezado visible en la parte superior.
+    pub heading: Option<String>,
+    /// Distancia desde ese encabezado (o desde el principio, si no hay).
+    pub offset: f32,
+}
+
+/// Posiciones de lectura por archivo, de la más reciente a la más antigua.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ScrollMemory {
+    entries: Vec<(PathBuf, ScrollPosition)>,
+}
+
+impl ScrollMemory {
+    pub fn get(&self, path: &Path) -> Option<&ScrollPosition> {
+        let path = canonical_path(path);
+        self.entries
+            .iter()
+            .find(|(remembered, _)| *remembered == path)
+            .map(|(_, position)| position)
+    }
+
+    pub fn remember(&mut self, path: &Path, position: ScrollPosition) {
+        let path = canonical_path(path);
+        self.entries.retain(|(remembered, _)| *remembered != path);
+        self.entries.insert(0, (path, position));
+        self.entries.truncate(MAX_REMEMBERED_FILES);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(offset: f32) -> ScrollPosition {
+        ScrollPosition { heading: None, offset }
+    }
+
+    #[test]
+    fn least_recently_used_files_are_forgotten() {
+        let mut memory = ScrollMemory::default();
+        let path = |index: usize| PathBuf::from(format!("/mdview-memory/{index}.md"));
+        for index in 0..=MAX_REMEMBERED_FILES {
+            memory.remember(&path(index), position(index as f32));
+            // El primero se vuelve a leer y pasa a ser el más reciente
+            if index == 10 {
+                memory.remember(&path(0), position(0.0));
+            }
+        }
+        assert_eq!(memory.entries.len(), MAX_REMEMBERED_FILES);
+        assert_eq!(memory.get(&path(0)), Some(&position(0.0)));
+        assert_eq!(memory.get(&path(1)), None);
+        assert_eq!(memory.get(&path(MAX_REMEMBERED_FILES)), Some(&position(MAX_REMEMBERED_FILES as f32)));
+    }
+
+    #[test]
+    fn same_file_by_different_paths() {
+        let dir = std::env::temp_dir().join(format!("mdview-memory-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        let file = dir.join("a.md");
+        std::fs::write(&file, "# A").unwrap();
+
+        let mut memory = ScrollMemory::default();
+        memory.remember(&file, position(10.0));
+        memory.remember(&dir.join("sub/../a.md"), position(20.0));
+        assert_eq!(memory.entries.len(), 1);
+        assert_eq!(memory.get(&file), Some(&position(20.0)));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
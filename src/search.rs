@@ -0,0 +1,226 @@
+use crate::document::Document;
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct SearchOptions {
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub regex: bool,
+}
+
+pub struct Matcher {
+    regex: Regex,
+    whole_word: bool,
+}
+
+impl Matcher {
+    pub fn new(query: &str, options: &SearchOptions) -> Result<Self, regex::Error> {
+        let pattern = if options.regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!options.case_sensitive)
+            .build()?;
+        Ok(Self {
+            regex,
+            whole_word: options.whole_word,
+        })
+    }
+
+    /// Rangos (en bytes) de las coincidencias no vacías. Con palabras
+    /// completas, una coincidencia descartada se vuelve a buscar desde el
+    /// carácter siguiente a su comienzo.
+    pub fn find_iter<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+        let mut start = 0;
+        std::iter::from_fn(move || {
+            while start <= text.len() {
+                let m = self.regex.find_at(text, start)?;
+                if !m.is_empty() && (!self.whole_word || is_whole_word(text, m.range())) {
+                    start = m.end();
+                    return Some(m.range());
+                }
+                start = m.start() + text[m.start()..].chars().next().map_or(1, char::len_utf8);
+            }
+            None
+        })
+    }
+}
+
+// Un borde de la coincidencia que es letra, dígito o `_` no puede seguir una
+// palabra; los demás bordes (como en `c++` o `.env`) no se comprueban
+fn is_whole_word(text: &str, range: Range<usize>) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let matched = &text[range.clone()];
+    let joins_before = matched.chars().next().is_some_and(is_word) && text[..range.start].chars().next_back().is_some_and(is_word);
+    let joins_after = matched.chars().next_back().is_some_and(is_word) && text[range.end..].chars().next().is_some_and(is_word);
+    !joins_before && !joins_after
+}
+
+/// Coincidencia en la vista renderizada: bloque, unidad de texto dentro del
+/// bloque (ver `Block::text_units`) y rango en bytes dentro de esa unidad.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DocumentMatch {
+    pub block: usize,
+    pub unit: usize,
+    pub range: Range<usize>,
+}
+
+/// Busca sobre el modelo del documento y no sobre los widgets, así que
+/// encuentra coincidencias aunque el bloque no se haya dibujado todavía.
+pub fn find_in_document(document: &Document, matcher: &Matcher) -> Vec<DocumentMatch> {
+    let mut matches = Vec::new();
+    for (block, content) in document.blocks.iter().enumerate() {
+        for (unit, text) in content.text_units().iter().enumerate() {
+            matches.extend(matcher.find_iter(text).map(|range| DocumentMatch { block, unit, range }));
+        }
+    }
+    matches
+}
+
+/// Estado de la barra de búsqueda (Ctrl+F).
+#[derive(Default)]
+pub struct FindState {
+    pub open: bool,
+    pub query: String,
+    pub options: SearchOptions,
+    pub invalid_regex: bool,
+    pub rendered_matches: Vec<DocumentMatch>,
+    pub raw_matches: Vec<Range<usize>>,
+    pub current: usize,
+    /// Desplazar la vista hasta la coincidencia actual en el próximo frame.
+    pub scroll_to_current: bool,
+    pub request_focus: bool,
+    computed_for: Option<(String, SearchOptions)>,
+}
+
+impl FindState {
+    pub fn show(&mut self) {
+        self.open = true;
+        self.request_focus = true;
+        self.scroll_to_current = true;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        self.rendered_matches.clear();
+        self.raw_matches.clear();
+        self.computed_for = None;
+    }
+
+    /// Fuerza a recalcular las coincidencias (por ejemplo, al cambiar el documento).
+    pub fn invalidate(&mut self) {
+        self.computed_for = None;
+    }
+
+    pub fn refresh(&mut self, document: &Document, source: &str) {
+        let key = (self.query.clone(), self.options.clone());
+        if !self.open || self.computed_for.as_ref() == Some(&key) {
+            return;
+        }
+
+        self.rendered_matches.clear();
+        self.raw_matches.clear();
+        self.invalid_regex = false;
+
+        if !self.query.is_empty() {
+            match Matcher::new(&self.query, &self.options) {
+                Ok(matcher) => {
+                    self.rendered_matches = find_in_document(document, &matcher);
+                    self.raw_matches = matcher.find_iter(source).collect();
+                }
+                Err(_) => self.invalid_regex = true,
+            }
+        }
+
+        self.current = 0;
+        self.scroll_to_current = true;
+        self.computed_for = Some(key);
+    }
+
+    pub fn match_count(&self, raw: bool) -> usize {
+        if raw {
+            self.raw_matches.len()
+        } else {
+            self.rendered_matches.len()
+        }
+    }
+
+    /// Índice de la coincidencia actual, acotado a las coincidencias de la vista.
+    pub fn current_index(&self, raw: bool) -> Option<usize> {
+        let count = self.match_count(raw);
+        (count > 0).then(|| self.current.min(count - 1))
+    }
+
+    pub fn next(&mut self, raw: bool) {
+        let count = self.match_count(raw);
+        if count > 0 {
+            self.current = (self.current.min(count - 1) + 1) % count;
+            self.scroll_to_current = true;
+        }
+    }
+
+    pub fn previous(&mut self, raw: bool) {
+        let count = self.match_count(raw);
+        if count > 0 {
+            self.current = (self.current.min(count - 1) + count - 1) % count;
+            self.scroll_to_current = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(query: &str, options: SearchOptions, text: &str) -> Vec<Range<usize>> {
+        Matcher::new(query, &options).unwrap().find_iter(text).collect()
+    }
+
+    #[test]
+    fn plain_text_is_case_insensitive_by_default() {
+        assert_eq!(find("rust", SearchOptions::default(), "Rust and rust"), vec![0..4, 9..13]);
+        let options = SearchOptions { case_sensitive: true, ..Default::default() };
+        assert_eq!(find("rust", options, "Rust and rust"), vec![9..13]);
+    }
+
+    #[test]
+    fn plain_text_escapes_regex_syntax() {
+        assert_eq!(find("a.b", SearchOptions::default(), "axb a.b"), vec![4..7]);
+    }
+
+    #[test]
+    fn whole_word() {
+        let options = SearchOptions { whole_word: true, ..Default::default() };
+        assert_eq!(find("cat", options.clone(), "cat concat cats cat."), vec![0..3, 16..19]);
+        let regex = SearchOptions { regex: true, ..options };
+        assert_eq!(find("cat|dog", regex, "dogma dog cat"), vec![6..9, 10..13]);
+    }
+
+    #[test]
+    fn whole_word_with_symbols_at_the_edges() {
+        let options = SearchOptions { whole_word: true, ..Default::default() };
+        assert_eq!(find("c++", options.clone(), "c++ abc++ xc++ c++"), vec![0..3, 15..18]);
+        assert_eq!(find(".env", options.clone(), "a.env .env .envrc"), vec![1..5, 6..10]);
+        assert_eq!(find("año", options.clone(), "años año"), vec![6..10]);
+        let regex = SearchOptions { regex: true, ..options };
+        assert_eq!(find(r"\.env", regex, "cp .env x.env"), vec![3..7, 9..13]);
+    }
+
+    #[test]
+    fn whole_word_finds_overlapping_candidates() {
+        let options = SearchOptions { whole_word: true, ..Default::default() };
+        assert_eq!(find("a-a", options, "xa-a-a"), vec![3..6]);
+    }
+
+    #[test]
+    fn regex() {
+        let options = SearchOptions { regex: true, ..Default::default() };
+        assert_eq!(find(r"\d+", options.clone(), "a1 b22"), vec![1..2, 4..6]);
+        // Las coincidencias vacías se descartan
+        assert_eq!(find("x*", options.clone(), "axxb"), vec![1..3]);
+        assert!(Matcher::new("(", &options).is_err());
+    }
+}
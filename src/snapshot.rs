@@ -0,0 +1,366 @@
+use crate::document::Document;
+use crate::export::ExportTheme;
+use crate::render;
+use eframe::egui;
+use egui::epaint::textures::TexturesDelta;
+use egui::epaint::{ClippedPrimitive, ImageData, Primitive, TextureId, Vertex};
+use egui::{Color32, Pos2, Rect};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+// Márgenes alrededor del documento, los mismos que en la vista renderizada
+const MARGIN: f32 = 20.0;
+const BOTTOM_MARGIN: f32 = 50.0;
+/// Alto disponible al distribuir el documento, en puntos. El contenido nunca
+/// llega a ocuparlo entero: solo evita que egui recorte nada.
+const LAYOUT_HEIGHT: f32 = 1_000_000.0;
+/// Filas de píxeles que se pintan a la vez antes de pasarlas al PNG.
+const TILE_ROWS: usize = 256;
+
+/// Opciones de la exportación a PNG.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PngOptions {
+    /// Ancho de la imagen en puntos, márgenes incluidos.
+    pub width: f32,
+    /// Píxeles por punto: 2 da una imagen nítida en pantallas de alta densidad.
+    pub scale: f32,
+}
+
+impl Default for PngOptions {
+    fn default() -> Self {
+        Self {
+            width: 800.0,
+            scale: 2.0,
+        }
+    }
+}
+
+/// Parte del documento que se exporta.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Area {
+    Document,
+    /// Lo que se ve en la ventana: `height` puntos desde `offset` puntos por
+    /// debajo del comienzo del bloque `block` (negativo si empieza antes).
+    /// El documento se distribuye con el ancho `width` de la ventana, para
+    /// que las líneas se corten igual y las medidas coincidan.
+    Viewport { block: usize, offset: f32, height: f32, width: f32 },
+}
+
+/// Dibuja el documento como en la vista renderizada y lo guarda como PNG.
+/// No necesita ventana ni GPU: egui distribuye y teseliza el contenido, y
+/// los triángulos se pintan aquí, por franjas, directamente en la imagen.
+pub fn export_png(document: &Document, theme: ExportTheme, area: Area, options: &PngOptions) -> io::Result<Vec<u8>> {
+    let scale = options.scale.clamp(0.5, 8.0);
+    let width = match area {
+        Area::Document => options.width,
+        Area::Viewport { width, .. } => width + 2.0 * MARGIN,
+    };
+    let width = width.max(2.0 * MARGIN + 100.0);
+
+    let ctx = egui::Context::default();
+    let mut fonts = egui::FontDefinitions::default();
+    egui_phosphor::add_to_fonts(&mut fonts, egui_phosphor::Variant::Regular);
+    ctx.set_fonts(fonts);
+    ctx.set_visuals(match theme {
+        ExportTheme::Light => egui::Visuals::light(),
+        ExportTheme::Dark => egui::Visuals::dark(),
+    });
+    let background = ctx.style().visuals.panel_fill;
+
+    let mut raw_input = egui::RawInput {
+        screen_rect: Some(Rect::from_min_size(Pos2::ZERO, egui::vec2(width, LAYOUT_HEIGHT))),
+        ..Default::default()
+    };
+    raw_input.viewports.entry(egui::ViewportId::ROOT).or_default().native_pixels_per_point = Some(scale);
+
+    // La primera pasada mide lo que egui dibuja de forma diferida (por
+    // ejemplo las columnas de las tablas); la segunda es la que se pinta
+    let mut textures = Textures::default();
+    let mut block_rects = Vec::new();
+    let mut bottom = 0.0;
+    let mut shapes = Vec::new();
+    for _ in 0..2 {
+        let output = ctx.run(raw_input.clone(), |ctx| {
+            egui::CentralPanel::default().frame(egui::Frame::NONE).show(ctx, |ui| {
+                ui.add_space(MARGIN);
+                let content = ui.horizontal(|ui| {
+                    ui.add_space(MARGIN);
+                    ui.vertical(|ui| {
+                        ui.set_max_width(width - 2.0 * MARGIN);
+                        block_rects = render::render_document(ui, document, &render::RenderOptions::default()).block_rects;
+                        ui.add_space(BOTTOM_MARGIN);
+                    });
+                });
+                // El panel ocupa todo el alto disponible; la imagen, solo el contenido
+                bottom = content.response.rect.bottom();
+            });
+        });
+        textures.apply(&output.textures_delta);
+        shapes = output.shapes;
+    }
+    let primitives = ctx.tessellate(shapes, scale);
+
+    let (top, bottom) = match area {
+        Area::Document => (0.0, bottom),
+        Area::Viewport { block, offset, height, .. } => {
+            let top = block_rects.get(block).map_or(0.0, |rect| rect.top() + offset).clamp(0.0, bottom);
+            (top, (top + height).min(bottom))
+        }
+    };
+    let image_width = (width * scale).round() as usize;
+    let image_height = ((bottom - top) * scale).round().max(1.0) as usize;
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, image_width as u32, image_height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(io::Error::other)?;
+    let mut stream = writer.stream_writer().map_err(io::Error::other)?;
+    paint_tiles(&primitives, &textures, scale, [image_width, image_height], top * scale, to_rgba(background), |rgb| stream.write_all(rgb))?;
+    stream.finish().map_err(io::Error::other)?;
+    writer.finish().map_err(io::Error::other)?;
+    Ok(bytes)
+}
+
+/// Pinta la imagen de `size` píxeles desde la fila `top` del contenido. Las
+/// franjas se pintan una tras otra y cada una se pasa en RGB a `write`, así
+/// que juntas forman una sola imagen.
+fn paint_tiles(
+    primitives: &[ClippedPrimitive],
+    textures: &Textures,
+    scale: f32,
+    [width, height]: [usize; 2],
+    top: f32,
+    background: [f32; 4],
+    mut write: impl FnMut(&[u8]) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut tile = Tile {
+        pixels: Vec::new(),
+        width,
+        rows: 0,
+        top: 0.0,
+    };
+    let mut row = 0;
+    while row < height {
+        tile.rows = TILE_ROWS.min(height - row);
+        tile.top = top + row as f32;
+        tile.pixels.clear();
+        tile.pixels.resize(tile.width * tile.rows, background);
+        for primitive in primitives {
+            tile.paint(primitive, textures, scale);
+        }
+        let rgb: Vec<u8> = tile.pixels.iter()
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+            .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect();
+        write(&rgb)?;
+        row += tile.rows;
+    }
+    Ok(())
+}
+
+/// Texturas que egui pide subir a la GPU; aquí solo hace falta la de las
+/// fuentes.
+#[derive(Default)]
+struct Textures {
+    images: HashMap<TextureId, ([usize; 2], Vec<Color32>)>,
+}
+
+impl Textures {
+    fn apply(&mut self, delta: &TexturesDelta) {
+        for (id, image_delta) in &delta.set {
+            let (size, pixels) = match &image_delta.image {
+                ImageData::Color(image) => (image.size, image.pixels.clone()),
+                ImageData::Font(image) => (image.size, image.srgba_pixels(None).collect()),
+            };
+            match image_delta.pos {
+                None => {
+                    self.images.insert(*id, (size, pixels));
+                }
+                // Actualización de una parte de una textura existente
+                Some([x, y]) => {
+                    if let Some((texture_size, texture)) = self.images.get_mut(id) {
+                        for row in 0..size[1] {
+                            let start = (y + row) * texture_size[0] + x;
+                            texture[start..start + size[0]].copy_from_slice(&pixels[row * size[0]..(row + 1) * size[0]]);
+                        }
+                    }
+                }
+            }
+        }
+        for id in &delta.free {
+            self.images.remove(id);
+        }
+    }
+
+    fn sample(&self, id: TextureId, uv: Pos2) -> [f32; 4] {
+        let Some(([width, height], pixels)) = self.images.get(&id) else {
+            return [1.0; 4];
+        };
+        let x = ((uv.x * *width as f32) as usize).min(width - 1);
+        let y = ((uv.y * *height as f32) as usize).min(height - 1);
+        to_rgba(pixels[y * width + x])
+    }
+}
+
+/// Franja horizontal de la imagen, en colores con alfa premultiplicado.
+struct Tile {
+    pixels: Vec<[f32; 4]>,
+    width: usize,
+    rows: usize,
+    /// Posición vertical de la primera fila, en píxeles desde el comienzo
+    /// del contenido.
+    top: f32,
+}
+
+impl Tile {
+    fn paint(&mut self, primitive: &ClippedPrimitive, textures: &Textures, scale: f32) {
+        let Primitive::Mesh(mesh) = &primitive.primitive else {
+            return;
+        };
+        let clip = Rect::from_min_max(
+            Pos2::new(primitive.clip_rect.min.x * scale, primitive.clip_rect.min.y * scale - self.top),
+            Pos2::new(primitive.clip_rect.max.x * scale, primitive.clip_rect.max.y * scale - self.top),
+        )
+        .intersect(Rect::from_min_size(Pos2::ZERO, egui::vec2(self.width as f32, self.rows as f32)));
+        if !clip.is_positive() {
+            return;
+        }
+        for triangle in mesh.indices.chunks_exact(3) {
+            let vertices = [0, 1, 2].map(|corner| mesh.vertices[triangle[corner] as usize]);
+            self.triangle(&vertices, clip, mesh.texture_id, textures, scale);
+        }
+    }
+
+    fn triangle(&mut self, vertices: &[Vertex; 3], clip: Rect, texture: TextureId, textures: &Textures, scale: f32) {
+        let mut vertices = *vertices;
+        let mut points = vertices.map(|vertex| Pos2::new(vertex.pos.x * scale, vertex.pos.y * scale - self.top));
+        let mut area = edge(points[0], points[1], points[2]);
+        if area.abs() < f32::EPSILON {
+            return;
+        }
+        // Todos los triángulos con la misma orientación
+        if area < 0.0 {
+            vertices.swap(1, 2);
+            points.swap(1, 2);
+            area = -area;
+        }
+        let edges = [(points[1], points[2]), (points[2], points[0]), (points[0], points[1])];
+        let bounds = Rect::from_points(&points).intersect(clip);
+        if !bounds.is_positive() {
+            return;
+        }
+
+        let colors = vertices.map(|vertex| to_rgba(vertex.color));
+        for y in bounds.min.y.floor() as usize..bounds.max.y.ceil() as usize {
+            for x in bounds.min.x.floor() as usize..bounds.max.x.ceil() as usize {
+                // Coordenadas baricéntricas del centro del píxel
+                let center = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+                if !clip.contains(center) {
+                    continue;
+                }
+                let weights = edges.map(|(a, b)| edge(a, b, center) / area);
+                // Un píxel justo sobre un borde compartido lo pinta uno solo
+                // de los dos triángulos: en el otro el borde va al revés
+                let outside = weights.iter().zip(edges)
+                    .any(|(weight, (a, b))| *weight < 0.0 || *weight == 0.0 && (b.y > a.y || b.y == a.y && b.x < a.x));
+                if outside {
+                    continue;
+                }
+                let uv = vertices.iter().zip(weights)
+                    .fold(egui::Vec2::ZERO, |uv, (vertex, weight)| uv + vertex.uv.to_vec2() * weight);
+                let texel = textures.sample(texture, uv.to_pos2());
+                let mut source = [0.0; 4];
+                for (channel, value) in source.iter_mut().enumerate() {
+                    let color: f32 = colors.iter().zip(weights).map(|(color, weight)| color[channel] * weight).sum();
+                    *value = color * texel[channel];
+                }
+                // Mezcla con alfa premultiplicado, como la de egui en la GPU
+                let pixel = &mut self.pixels[y * self.width + x];
+                for channel in 0..4 {
+                    pixel[channel] = source[channel] + pixel[channel] * (1.0 - source[3]);
+                }
+            }
+        }
+    }
+}
+
+fn edge(a: Pos2, b: Pos2, point: Pos2) -> f32 {
+    (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x)
+}
+
+fn to_rgba(color: Color32) -> [f32; 4] {
+    color.to_array().map(|channel| channel as f32 / 255.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::epaint::Mesh;
+
+    fn image_size(bytes: &[u8]) -> (u32, u32) {
+        let reader = png::Decoder::new(io::Cursor::new(bytes)).read_info().unwrap();
+        (reader.info().width, reader.info().height)
+    }
+
+    // Rectángulo de un color, en puntos
+    fn rect(rect: Rect, color: Color32) -> ClippedPrimitive {
+        let mut mesh = Mesh::default();
+        mesh.add_colored_rect(rect, color);
+        ClippedPrimitive {
+            clip_rect: Rect::EVERYTHING,
+            primitive: Primitive::Mesh(mesh),
+        }
+    }
+
+    #[test]
+    fn tiles_join_into_one_image() {
+        let primitives = [rect(Rect::from_min_max(Pos2::new(1.0, 100.0), Pos2::new(3.0, 200.0)), Color32::RED)];
+        let (width, height) = (4, TILE_ROWS + 100);
+        let mut rgb = Vec::new();
+        let mut tiles = 0;
+        paint_tiles(&primitives, &Textures::default(), 2.0, [width, height], 50.0, [0.0, 0.0, 0.0, 1.0], |tile| {
+            tiles += 1;
+            rgb.extend_from_slice(tile);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(tiles, 2);
+        assert_eq!(rgb.len(), width * height * 3);
+        // Con escala 2 y la imagen empezando en la fila 50, el rectángulo
+        // ocupa las columnas 2 y 3 y las filas 150 a 349, a los dos lados del corte
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = &rgb[(y * width + x) * 3..][..3];
+                let inside = (2..6).contains(&x) && (150..350).contains(&y);
+                assert_eq!(pixel, if inside { [255, 0, 0] } else { [0, 0, 0] }, "({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn image_size_follows_width_and_scale() {
+        let document = Document::parse("# Título\n\nTexto.\n");
+        let options = PngOptions { width: 400.0, scale: 2.0 };
+        let (width, height) = image_size(&export_png(&document, ExportTheme::Light, Area::Document, &options).unwrap());
+        assert_eq!(width, 800);
+
+        let options = PngOptions { width: 400.0, scale: 1.0 };
+        assert_eq!(image_size(&export_png(&document, ExportTheme::Light, Area::Document, &options).unwrap()), (400, height / 2));
+
+        // Un ancho menor que los márgenes no deja la imagen vacía
+        let options = PngOptions { width: 10.0, scale: 1.0 };
+        assert_eq!(image_size(&export_png(&document, ExportTheme::Light, Area::Document, &options).unwrap()).0, 140);
+    }
+
+    #[test]
+    fn viewport_uses_the_window_width_and_height() {
+        let document = Document::parse(&"Un párrafo.\n\n".repeat(50));
+        let area = Area::Viewport { block: 3, offset: 0.0, height: 120.0, width: 300.0 };
+        let options = PngOptions { width: 800.0, scale: 2.0 };
+        assert_eq!(image_size(&export_png(&document, ExportTheme::Dark, area, &options).unwrap()), (680, 240));
+    }
+}
@@ -0,0 +1,137 @@
+/// Panel de la vista dividida.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Hash)]
+pub enum Pane {
+    Source,
+    #[default]
+    Preview,
+}
+
+/// Sincronización del desplazamiento entre los dos paneles de la vista
+/// dividida. El panel que el usuario está moviendo guía y el otro lo sigue.
+#[derive(Default)]
+pub struct SplitSync {
+    pub leader: Pane,
+    /// Desplazamientos a aplicar a cada panel en el próximo frame.
+    pub source_restore: Option<f32>,
+    pub preview_restore: Option<f32>,
+    /// Desplazamiento del código fuente en el último frame.
+    pub source_offset: f32,
+    // Desplazamiento que le dimos al código fuente, para no confundirlo con
+    // uno hecho por el usuario
+    expected_source_offset: Option<f32>,
+}
+
+impl SplitSync {
+    /// Indica si el código fuente se movió solo (por el cursor de texto, por
+    /// ejemplo) y no porque lo sincronizamos.
+    pub fn source_moved(&mut self, offset: f32) -> bool {
+        let moved = (offset - self.source_offset).abs() > 0.5;
+        let expected = self.expected_source_offset.take().is_some_and(|expected| (offset - expected).abs() <= 1.0);
+        self.source_offset = offset;
+        moved && !expected
+    }
+
+    /// Lleva el panel que sigue a la posición que corresponde a la del que guía.
+    /// Devuelve `true` si hubo que moverlo.
+    pub fn follow(&mut self, source: &PaneLayout, preview: &PaneLayout) -> bool {
+        let (from, to) = match self.leader {
+            Pane::Source => (source, preview),
+            Pane::Preview => (preview, source),
+        };
+        let target = map_offset(from, to);
+        if (target - to.offset).abs() <= 1.0 {
+            return false;
+        }
+        match self.leader {
+            Pane::Source => self.preview_restore = Some(target),
+            Pane::Preview => {
+                self.source_restore = Some(target);
+                self.expected_source_offset = Some(target);
+            }
+        }
+        true
+    }
+}
+
+/// Dónde quedó cada bloque en un panel, en coordenadas del contenido.
+pub struct PaneLayout {
+    pub offset: f32,
+    pub max_offset: f32,
+    pub content_height: f32,
+    pub block_tops: Vec<f32>,
+}
+
+/// Desplazamiento de `to` que muestra arriba lo mismo que `from`: se busca el
+/// bloque que está en el borde superior de `from` y se interpola dentro de él.
+fn map_offset(from: &PaneLayout, to: &PaneLayout) -> f32 {
+    // Pares de posiciones equivalentes, del principio al final del contenido
+    let mut anchors: Vec<(f32, f32)> = vec![(0.0, 0.0)];
+    for (from_top, to_top) in from.block_tops.iter().zip(&to.block_tops) {
+        // Las posiciones deben crecer en los dos paneles para poder interpolar
+        if anchors.last().is_some_and(|(last_from, last_to)| from_top > last_from && to_top >= last_to) {
+            anchors.push((*from_top, *to_top));
+        }
+    }
+    anchors.push((from.content_height.max(anchors.last().map_or(0.0, |a| a.0)), to.content_height));
+
+    // Al final de un panel, el otro también va al final
+    if from.max_offset > 0.0 && from.offset >= from.max_offset - 1.0 {
+        return to.max_offset;
+    }
+
+    let index = anchors.iter().rposition(|(from_top, _)| *from_top <= from.offset).unwrap_or(0);
+    let Some(next) = anchors.get(index + 1) else {
+        return to.max_offset;
+    };
+    let (from_start, to_start) = anchors[index];
+    let (from_end, to_end) = *next;
+    let fraction = if from_end > from_start {
+        (from.offset - from_start) / (from_end - from_start)
+    } else {
+        0.0
+    };
+    (to_start + fraction * (to_end - to_start)).clamp(0.0, to.max_offset.max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(offset: f32, block_tops: &[f32], content_height: f32) -> PaneLayout {
+        PaneLayout {
+            offset,
+            max_offset: content_height - 100.0,
+            content_height,
+            block_tops: block_tops.to_vec(),
+        }
+    }
+
+    #[test]
+    fn block_tops_map_to_block_tops() {
+        let to = layout(0.0, &[0.0, 300.0, 900.0], 1500.0);
+        assert_eq!(map_offset(&layout(0.0, &[0.0, 100.0, 200.0], 1000.0), &to), 0.0);
+        assert_eq!(map_offset(&layout(100.0, &[0.0, 100.0, 200.0], 1000.0), &to), 300.0);
+        assert_eq!(map_offset(&layout(200.0, &[0.0, 100.0, 200.0], 1000.0), &to), 900.0);
+    }
+
+    #[test]
+    fn interpolates_inside_a_block() {
+        let to = layout(0.0, &[0.0, 300.0, 900.0], 1500.0);
+        assert_eq!(map_offset(&layout(150.0, &[0.0, 100.0, 200.0], 1000.0), &to), 600.0);
+        // Después del último bloque, hasta el final del contenido
+        assert_eq!(map_offset(&layout(600.0, &[0.0, 100.0, 200.0], 1000.0), &to), 1200.0);
+    }
+
+    #[test]
+    fn end_maps_to_end() {
+        let to = layout(0.0, &[0.0, 300.0, 900.0], 1500.0);
+        assert_eq!(map_offset(&layout(900.0, &[0.0, 100.0, 200.0], 1000.0), &to), 1400.0);
+    }
+
+    #[test]
+    fn skips_blocks_out_of_order() {
+        // El tercer bloque queda por encima del segundo y no se usa
+        let to = layout(0.0, &[0.0, 500.0, 300.0], 1500.0);
+        assert_eq!(map_offset(&layout(550.0, &[0.0, 100.0, 200.0], 1000.0), &to), 1000.0);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::encoding;
+use eframe::egui;
+use encoding_rs::Decoder;
+use std::io::{self, Read};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+// Bytes con los que se decide la codificación de la entrada
+const SNIFF_LENGTH: usize = 4096;
+
+/// Lee la entrada estándar en un hilo aparte para no bloquear la ventana
+/// mientras el programa que escribe en ella sigue trabajando.
+pub struct StdinReader {
+    /// Pestaña que muestra lo leído.
+    pub tab: u64,
+    chunks: Receiver<Vec<u8>>,
+    /// Bytes recibidos mientras no se sabe la codificación.
+    pending: Vec<u8>,
+    /// Decodificador de la codificación elegida. Guarda entre bloques los
+    /// caracteres que llegaron cortados.
+    decoder: Option<Decoder>,
+    text: String,
+    /// Con `--follow` el documento se actualiza a medida que llega el texto;
+    /// si no, se muestra todo junto al terminar la entrada.
+    follow: bool,
+}
+
+impl StdinReader {
+    pub fn spawn(ctx: &egui::Context, tab: u64, follow: bool) -> Self {
+        let (sender, chunks) = mpsc::channel();
+        let repaint_ctx = ctx.clone();
+        thread::spawn(move || {
+            let mut stdin = io::stdin().lock();
+            let mut buffer = [0; 8192];
+            loop {
+                match stdin.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(read) => {
+                        if sender.send(buffer[..read].to_vec()).is_err() {
+                            break;
+                        }
+                        repaint_ctx.request_repaint();
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        eprintln!("Error al leer la entrada estándar: {}", e);
+                        break;
+                    }
+                }
+            }
+            // Al soltar `sender` el receptor sabe que la entrada terminó
+            drop(sender);
+            repaint_ctx.request_repaint();
+        });
+        Self {
+            tab,
+            chunks,
+            pending: Vec::new(),
+            decoder: None,
+            text: String::new(),
+            follow,
+        }
+    }
+
+    /// Recoge lo que llegó desde el último frame. Devuelve el texto completo
+    /// si hay que actualizar el documento y si la entrada ya terminó.
+    pub fn poll(&mut self) -> (Option<String>, bool) {
+        let mut received = false;
+        let finished = loop {
+            match self.chunks.try_recv() {
+                Ok(chunk) => {
+                    self.push(&chunk, false);
+                    received = true;
+                }
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        if finished {
+            self.push(&[], true);
+        }
+        let update = if self.follow { received || finished } else { finished };
+        let text = update.then(|| match &self.decoder {
+            Some(_) => self.text.clone(),
+            // Todavía no se eligió la codificación: se muestra lo que hay con la más probable
+            None => encoding::decode_with(&self.pending, encoding::detect(&self.pending, false)).text,
+        });
+        (text, finished)
+    }
+
+    // Decodifica un bloque. La codificación se elige una sola vez, con los
+    // primeros bytes o con todo el texto si es más corto.
+    fn push(&mut self, bytes: &[u8], last: bool) {
+        if self.decoder.is_none() {
+            self.pending.extend_from_slice(bytes);
+            if self.pending.len() < SNIFF_LENGTH && !last {
+                return;
+            }
+            self.decoder = Some(encoding::detect(&self.pending, last).new_decoder());
+        }
+        // Al elegir la codificación, `pending` ya incluye el bloque
+        let pending = std::mem::take(&mut self.pending);
+        let bytes = if pending.is_empty() { bytes } else { &pending };
+        let Some(decoder) = &mut self.decoder else {
+            return;
+        };
+        if let Some(needed) = decoder.max_utf8_buffer_length(bytes.len()) {
+            self.text.reserve(needed);
+        }
+        let (_result, _read, _had_errors) = decoder.decode_to_string(bytes, &mut self.text, last);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reader(chunks: &[&[u8]]) -> StdinReader {
+        let (sender, receiver) = mpsc::channel();
+        for chunk in chunks {
+            sender.send(chunk.to_vec()).unwrap();
+        }
+        StdinReader {
+            tab: 0,
+            chunks: receiver,
+            pending: Vec::new(),
+            decoder: None,
+            text: String::new(),
+            follow: true,
+        }
+    }
+
+    #[test]
+    fn characters_split_between_chunks() {
+        let text = "año ".repeat(2000);
+        let (first, second) = text.as_bytes().split_at(4097);
+        assert!(!text.is_char_boundary(4097));
+        let (update, finished) = reader(&[first, second]).poll();
+        assert!(finished);
+        assert_eq!(update.as_deref(), Some(text.as_str()));
+    }
+
+    #[test]
+    fn short_legacy_input() {
+        let (update, finished) = reader(&[b"Une cr\xe8me br\xfbl\xe9e au caf\xe9"]).poll();
+        assert!(finished);
+        assert_eq!(update.as_deref(), Some("Une crème brûlée au café"));
+    }
+}
@@ -0,0 +1,592 @@
+use crate::changes::DocumentChanges;
+use crate::document::Document;
+use crate::encoding::{self, TextEncoding};
+use crate::history::{HistoryEntry, NavigationHistory};
+use crate::scroll_memory::ScrollPosition;
+use crate::split::{Pane, SplitSync};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// Pausa al escribir antes de volver a analizar el documento
+const EDIT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Cómo se muestra un documento: renderizado, como código fuente o los dos
+/// lado a lado.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub enum ViewMode {
+    #[default]
+    Rendered,
+    Raw,
+    Split,
+}
+
+impl ViewMode {
+    /// La búsqueda recorre las coincidencias del código fuente solo cuando es
+    /// lo único que se ve; en la vista dividida guía la vista renderizada.
+    pub fn finds_in_source(self) -> bool {
+        self == ViewMode::Raw
+    }
+}
+
+/// Un documento abierto en una pestaña, con su propio estado de vista.
+pub struct DocumentTab {
+    pub id: u64,
+    pub markdown_content: String,
+    pub current_file: Option<PathBuf>,
+    /// El documento se lee de la entrada estándar.
+    pub from_stdin: bool,
+    pub current_encoding: Option<TextEncoding>,
+    pub document: Document,
+    pub view_mode: ViewMode,
+    /// Desplazamiento vertical del último frame (del panel renderizado en la vista dividida).
+    pub scroll_offset: f32,
+    /// Borde superior en pantalla del panel renderizado en el último frame.
+    pub viewport_top: f32,
+    /// Alto visible y alto total del contenido en el último frame.
+    pub viewport_height: f32,
+    pub content_height: f32,
+    /// Ancho con el que se distribuyó la vista renderizada en el último frame.
+    pub preview_width: f32,
+    /// Desplazamiento a restaurar en el próximo frame.
+    pub restore_scroll: Option<f32>,
+    /// Posición recordada a restaurar cuando se conozca la ubicación de los encabezados.
+    pub restore_position: Option<ScrollPosition>,
+    // Posición de cada bloque en el último frame renderizado
+    pub block_rects: Vec<egui::Rect>,
+    pub scroll_to_block: Option<usize>,
+    /// Posición (en bytes) a mostrar en la vista raw en el próximo frame.
+    pub scroll_to_source: Option<usize>,
+    /// Rango (en bytes) a seleccionar en la vista raw en el próximo frame.
+    pub select_source: Option<Range<usize>>,
+    /// Bloques donde empezó y por donde va la selección con el ratón en la
+    /// vista renderizada. Solo vale mientras haya texto seleccionado.
+    pub preview_selection: Option<(usize, usize)>,
+    /// Bloque resaltado brevemente en la vista renderizada, y desde cuándo.
+    pub flash_block: Option<(usize, Instant)>,
+    pub active_heading: Option<usize>,
+    /// Posición vertical de cada encabezado dentro del contenido desplazable.
+    pub heading_offsets: Vec<f32>,
+    pub outline_scrolled_to: Option<usize>,
+    pub history: NavigationHistory,
+    /// El archivo se borró o se movió mientras estaba abierto.
+    pub file_missing: bool,
+    /// Bloques que cambiaron en la última recarga.
+    pub changes: Option<DocumentChanges>,
+    /// El código fuente se puede editar en la vista raw.
+    pub editing: bool,
+    /// Hay cambios sin guardar.
+    pub dirty: bool,
+    // Momento de la última edición todavía no reflejada en `document`
+    edited_at: Option<Instant>,
+    /// Sincronización de los paneles de la vista dividida.
+    pub split: SplitSync,
+}
+
+impl DocumentTab {
+    pub fn new(id: u64, view_mode: ViewMode) -> Self {
+        Self {
+            id,
+            markdown_content: String::new(),
+            current_file: None,
+            from_stdin: false,
+            current_encoding: None,
+            document: Document::default(),
+            view_mode,
+            scroll_offset: 0.0,
+            viewport_top: 0.0,
+            viewport_height: 0.0,
+            content_height: 0.0,
+            preview_width: 0.0,
+            restore_scroll: None,
+            restore_position: None,
+            block_rects: Vec::new(),
+            scroll_to_block: None,
+            scroll_to_source: None,
+            select_source: None,
+            preview_selection: None,
+            flash_block: None,
+            active_heading: None,
+            heading_offsets: Vec::new(),
+            outline_scrolled_to: None,
+            history: NavigationHistory::default(),
+            file_missing: false,
+            changes: None,
+            editing: false,
+            dirty: false,
+            edited_at: None,
+            split: SplitSync::default(),
+        }
+    }
+
+    pub fn open(id: u64, path: PathBuf, view_mode: ViewMode) -> io::Result<Self> {
+        let mut tab = Self::new(id, view_mode);
+        tab.load_file(path)?;
+        Ok(tab)
+    }
+
+    /// Reemplaza el documento de la pestaña por otro archivo, desde el principio.
+    pub fn load_file(&mut self, path: PathBuf) -> io::Result<()> {
+        let decoded = encoding::decode(&fs::read(&path)?);
+        let path = canonical_path(&path);
+        self.current_encoding = Some(decoded.encoding);
+        self.current_file = Some(path);
+        self.file_missing = false;
+        self.set_content(decoded.text);
+        self.restore_scroll = Some(0.0);
+        Ok(())
+    }
+
+    /// Vuelve a leer el archivo desde el disco. La posición se ancla al
+    /// encabezado visible, para no saltar si cambió algo más arriba.
+    pub fn reload(&mut self) -> io::Result<()> {
+        let Some(path) = &self.current_file else {
+            return Ok(());
+        };
+        let decoded = encoding::decode(&fs::read(path)?);
+        // Un archivo que se guardó sin cambios (o que guardamos nosotros) no se vuelve a analizar
+        if decoded.text == self.markdown_content && self.current_encoding == Some(decoded.encoding) {
+            self.file_missing = false;
+            return Ok(());
+        }
+        let position = self.current_position();
+        let previous_source = std::mem::take(&mut self.markdown_content);
+        let previous_document = std::mem::take(&mut self.document);
+        self.current_encoding = Some(decoded.encoding);
+        self.file_missing = false;
+        self.set_content(decoded.text);
+        self.restore_position = position;
+        self.changes = DocumentChanges::compute(&previous_source, &previous_document, &self.markdown_content, &self.document);
+        Ok(())
+    }
+
+    pub fn reopen_with_encoding(&mut self, encoding: &'static encoding_rs::Encoding) -> io::Result<()> {
+        let Some(path) = &self.current_file else {
+            return Ok(());
+        };
+        let decoded = encoding::decode_with(&fs::read(path)?, encoding);
+        self.current_encoding = Some(decoded.encoding);
+        self.set_content(decoded.text);
+        Ok(())
+    }
+
+    pub fn set_content(&mut self, content: String) {
+        self.markdown_content = content;
+        self.dirty = false;
+        self.update_document();
+    }
+
+    /// Registra una edición del código fuente. El documento se vuelve a
+    /// analizar en `apply_edits`, cuando se deja de escribir.
+    pub fn mark_edited(&mut self) {
+        self.dirty = true;
+        self.changes = None;
+        self.edited_at = Some(Instant::now());
+    }
+
+    /// Vuelve a analizar el documento si ya pasó la pausa desde la última
+    /// edición; si no, pide un repintado para cuando pase. Devuelve `true`
+    /// si el documento cambió.
+    pub fn apply_edits(&mut self, ctx: &egui::Context) -> bool {
+        let Some(edited_at) = self.edited_at else {
+            return false;
+        };
+        let elapsed = edited_at.elapsed();
+        if elapsed < EDIT_DEBOUNCE {
+            ctx.request_repaint_after(EDIT_DEBOUNCE - elapsed);
+            return false;
+        }
+        self.flush_edits();
+        true
+    }
+
+    fn flush_edits(&mut self) {
+        if self.edited_at.take().is_some() {
+            self.update_document();
+        }
+    }
+
+    /// Guarda el código fuente en `path`, con la codificación con la que se leyó.
+    pub fn save_to(&mut self, path: PathBuf) -> io::Result<()> {
+        self.flush_edits();
+        let encoding = self.current_encoding.unwrap_or_default();
+        fs::write(&path, encoding::encode(&self.markdown_content, encoding))?;
+        self.current_encoding = Some(encoding);
+        self.current_file = Some(canonical_path(&path));
+        self.file_missing = false;
+        self.dirty = false;
+        Ok(())
+    }
+
+    pub fn update_document(&mut self) {
+        self.document = Document::parse(&self.markdown_content);
+        self.block_rects.clear();
+        self.scroll_to_block = None;
+        self.scroll_to_source = None;
+        self.select_source = None;
+        self.preview_selection = None;
+        self.flash_block = None;
+        self.active_heading = None;
+        self.changes = None;
+    }
+
+    /// Carpeta desde la que se resuelven las rutas relativas del documento.
+    pub fn base_dir(&self) -> PathBuf {
+        self.current_file.as_ref()
+            .and_then(|path| path.parent())
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
+    }
+
+    /// Nombre del documento sin extensión, para los archivos exportados.
+    pub fn export_name(&self) -> String {
+        self.current_file.as_ref()
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| if self.from_stdin { "stdin" } else { "document" }.to_string())
+    }
+
+    /// Desplaza ambas vistas hasta una posición del texto fuente: la vista
+    /// renderizada va al bloque que la contiene.
+    pub fn reveal_offset(&mut self, offset: usize) {
+        self.scroll_to_source = Some(offset);
+        self.scroll_to_block = self.block_at_offset(offset);
+    }
+
+    /// Selecciona en el código fuente las líneas completas de un bloque y las
+    /// muestra.
+    pub fn show_block_source(&mut self, block: usize) {
+        let Some(block) = self.document.blocks.get(block) else {
+            return;
+        };
+        let source = &self.markdown_content;
+        let start = source.get(..block.range.start)
+            .and_then(|before| before.rfind('\n'))
+            .map_or(0, |newline| newline + 1);
+        let end = block.range.start + source.get(block.range.clone()).unwrap_or_default().trim_end().len();
+        self.select_source = Some(start..end);
+        self.scroll_to_source = Some(start);
+        self.split.leader = Pane::Source;
+    }
+
+    /// Muestra un bloque en la vista renderizada y lo resalta un momento.
+    pub fn reveal_in_preview(&mut self, block: usize) {
+        self.scroll_to_block = Some(block);
+        self.flash_block = Some((block, Instant::now()));
+        self.split.leader = Pane::Preview;
+    }
+
+    /// Bloque que contiene una posición del código fuente.
+    pub fn block_at_offset(&self, offset: usize) -> Option<usize> {
+        self.document.blocks.iter().rposition(|block| block.range.start <= offset)
+    }
+
+    /// Rango del texto fuente de la sección que contiene un bloque: desde su
+    /// encabezado hasta el siguiente del mismo nivel o superior. Antes del
+    /// primer encabezado, la sección es el principio del documento.
+    pub fn section_range(&self, block: usize) -> Range<usize> {
+        let block_start = |index: usize| self.document.blocks.get(index).map_or(self.markdown_content.len(), |block| block.range.start);
+        let headings = &self.document.headings;
+        match headings.iter().rposition(|heading| heading.block <= block) {
+            Some(index) => {
+                let heading = &headings[index];
+                let end = headings[index + 1..].iter()
+                    .find(|next| next.level <= heading.level)
+                    .map_or(self.markdown_content.len(), |next| block_start(next.block));
+                block_start(heading.block)..end
+            }
+            None => 0..headings.first().map_or(self.markdown_content.len(), |first| block_start(first.block)),
+        }
+    }
+
+    /// Texto seleccionado en el código fuente o, si no hay, en la vista
+    /// renderizada.
+    pub fn selection(&self, ctx: &egui::Context) -> Option<Range<usize>> {
+        self.source_selection(ctx).or_else(|| self.preview_selection_range(ctx))
+    }
+
+    /// Texto seleccionado en el código fuente, si lo hay y se ve.
+    pub fn source_selection(&self, ctx: &egui::Context) -> Option<Range<usize>> {
+        if self.view_mode == ViewMode::Rendered {
+            return None;
+        }
+        let state = egui::text_edit::TextEditState::load(ctx, self.raw_view_id())?;
+        let [start, end] = state.cursor.char_range()?.sorted();
+        if start.index == end.index {
+            return None;
+        }
+        // El editor cuenta caracteres; el rango se da en bytes
+        let byte_offset = |chars: usize| {
+            self.markdown_content.char_indices().nth(chars).map_or(self.markdown_content.len(), |(offset, _)| offset)
+        };
+        Some(byte_offset(start.index)..byte_offset(end.index))
+    }
+
+    /// Código fuente de los bloques que abarca la selección de la vista
+    /// renderizada. Los bloques son etiquetas separadas, así que la selección
+    /// se copia por bloques completos.
+    pub fn preview_selection_range(&self, ctx: &egui::Context) -> Option<Range<usize>> {
+        if self.view_mode == ViewMode::Raw || !egui::text_selection::LabelSelectionState::load(ctx).has_selection() {
+            return None;
+        }
+        let (anchor, end) = self.preview_selection?;
+        let first = self.document.blocks.get(anchor.min(end))?;
+        let last = self.document.blocks.get(anchor.max(end))?;
+        Some(first.range.start..last.range.end)
+    }
+
+    /// Sigue la selección con el ratón en la vista renderizada: el bloque
+    /// donde se empieza a arrastrar y el que queda bajo el puntero.
+    pub fn track_preview_selection(&mut self, ctx: &egui::Context, area: egui::Rect) {
+        let (pressed, down, position) = ctx.input(|i| (i.pointer.primary_pressed(), i.pointer.primary_down(), i.pointer.interact_pos()));
+        let Some(position) = position else {
+            return;
+        };
+        // El último bloque que empieza por encima del puntero
+        let block = self.block_rects.iter().rposition(|rect| rect.top() <= position.y).unwrap_or(0);
+        if pressed {
+            self.preview_selection = area.contains(position).then_some((block, block));
+        } else if down {
+            if let Some((_, end)) = &mut self.preview_selection {
+                *end = block;
+            }
+        }
+    }
+
+    /// Posición actual anclada al encabezado activo. `None` si la pestaña
+    /// todavía no se dibujó y no hay una posición que conservar.
+    pub fn current_position(&self) -> Option<ScrollPosition> {
+        if let Some(position) = &self.restore_position {
+            return Some(position.clone());
+        }
+        if self.content_height == 0.0 {
+            return None;
+        }
+        let position = match self.active_heading {
+            Some(index) => ScrollPosition {
+                heading: Some(self.document.headings[index].anchor.clone()),
+                offset: self.scroll_offset - self.heading_offsets[index],
+            },
+            None => ScrollPosition {
+                heading: None,
+                offset: self.scroll_offset,
+            },
+        };
+        Some(position)
+    }
+
+    /// Desplazamiento que corresponde a una posición recordada. Si el
+    /// encabezado ya no existe se usa la distancia desde el principio.
+    pub fn resolve_position(&self, position: &ScrollPosition) -> f32 {
+        let heading = position.heading.as_ref().and_then(|anchor| {
+            self.document.headings.iter().position(|heading| heading.anchor == *anchor)
+        });
+        let offset = match heading.and_then(|index| self.heading_offsets.get(index)) {
+            Some(top) => top + position.offset,
+            None => position.offset,
+        };
+        offset.clamp(0.0, self.max_scroll())
+    }
+
+    /// Desplaza la vista al cambio siguiente o anterior de la última recarga.
+    pub fn step_change(&mut self, forward: bool) {
+        let block_count = self.document.blocks.len();
+        let Some(block) = self.changes.as_mut().and_then(|changes| changes.step(forward, block_count)) else {
+            return;
+        };
+        self.reveal_offset(self.document.blocks[block].range.start);
+    }
+
+    /// Primer bloque visible en el panel renderizado y cuánto de él quedó
+    /// por encima del borde superior (negativo si todavía no llegó).
+    pub fn first_visible_block(&self) -> Option<(usize, f32)> {
+        let block = self.block_rects.iter().position(|rect| rect.bottom() > self.viewport_top)?;
+        Some((block, self.viewport_top - self.block_rects[block].top()))
+    }
+
+    pub fn max_scroll(&self) -> f32 {
+        (self.content_height - self.viewport_height).max(0.0)
+    }
+
+    pub fn scroll_by(&mut self, delta: f32) {
+        let current = self.restore_scroll.unwrap_or(self.scroll_offset);
+        self.restore_scroll = Some((current + delta).clamp(0.0, self.max_scroll()));
+    }
+
+    /// Id del `TextEdit` de la vista raw, para saber si tiene el foco.
+    pub fn raw_view_id(&self) -> egui::Id {
+        egui::Id::new(("raw_view", self.id))
+    }
+
+    pub fn history_entry(&self) -> HistoryEntry {
+        HistoryEntry {
+            path: self.current_file.clone(),
+            scroll_offset: self.scroll_offset,
+        }
+    }
+
+    /// Navega a otro archivo dentro de la misma pestaña, registrándolo en el historial.
+    pub fn navigate_to(&mut self, path: PathBuf) -> io::Result<()> {
+        let entry = self.history_entry();
+        self.load_file(path)?;
+        self.history.push(entry);
+        Ok(())
+    }
+
+    /// Desplaza la vista al encabezado con el ancla dada, registrándolo en el historial.
+    pub fn jump_to_anchor(&mut self, anchor: &str) {
+        if let Some(heading) = self.document.headings.iter().find(|heading| heading.anchor == anchor) {
+            self.jump_to_block(heading.block);
+        }
+    }
+
+    pub fn jump_to_block(&mut self, block: usize) {
+        self.history.push(self.history_entry());
+        self.scroll_to_block = Some(block);
+    }
+
+    /// Indica si ir atrás (o adelante) en el historial cambia de archivo.
+    pub fn history_leaves_file(&self, forward: bool) -> bool {
+        self.history.peek(forward).is_some_and(|entry| entry.path != self.current_file)
+    }
+
+    pub fn go_back(&mut self) -> io::Result<()> {
+        self.step_history(false)
+    }
+
+    pub fn go_forward(&mut self) -> io::Result<()> {
+        self.step_history(true)
+    }
+
+    // El historial solo se mueve si se pudo abrir la entrada de destino
+    fn step_history(&mut self, forward: bool) -> io::Result<()> {
+        let Some(entry) = self.history.peek(forward).cloned() else {
+            return Ok(());
+        };
+        let current = self.history_entry();
+        self.restore_entry(entry)?;
+        if forward {
+            self.history.go_forward(current);
+        } else {
+            self.history.go_back(current);
+        }
+        Ok(())
+    }
+
+    fn restore_entry(&mut self, entry: HistoryEntry) -> io::Result<()> {
+        if entry.path != self.current_file {
+            if let Some(path) = entry.path {
+                self.load_file(path)?;
+            }
+        }
+        self.restore_scroll = Some(entry.scroll_offset);
+        Ok(())
+    }
+
+    /// Indica si la pestaña muestra `path`, aunque se haya escrito de otra
+    /// forma (relativa, con `..` o por un enlace).
+    pub fn is_file(&self, path: &Path) -> bool {
+        self.current_file.as_deref() == Some(canonical_path(path).as_path())
+    }
+
+    pub fn title(&self) -> String {
+        if self.from_stdin && self.current_file.is_none() {
+            return "stdin".to_string();
+        }
+        self.current_file
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// Ruta canónica con la que se identifica un archivo en pestañas, vigilancia
+/// de cambios, posiciones recordadas y libros. Un archivo que ya no existe
+/// conserva la ruta absoluta.
+pub(crate) fn canonical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path)
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_history_step_keeps_position() {
+        let dir = std::env::temp_dir().join(format!("mdview-history-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (first, second) = (dir.join("first.md"), dir.join("second.md"));
+        fs::write(&first, "# First").unwrap();
+        fs::write(&second, "# Second").unwrap();
+
+        let mut tab = DocumentTab::open(0, first.clone(), ViewMode::Rendered).unwrap();
+        tab.navigate_to(second.clone()).unwrap();
+        fs::remove_file(&first).unwrap();
+        assert!(tab.go_back().is_err());
+        assert!(tab.is_file(&second));
+        assert!(tab.history.can_go_back());
+        assert!(!tab.history.can_go_forward());
+
+        fs::write(&first, "# First").unwrap();
+        tab.go_back().unwrap();
+        assert!(tab.is_file(&first));
+        assert!(!tab.history.can_go_back());
+        assert!(tab.history.can_go_forward());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn same_file_by_different_paths() {
+        let dir = std::env::temp_dir().join(format!("mdview-paths-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        let file = dir.join("a.md");
+        fs::write(&file, "# A").unwrap();
+
+        let tab = DocumentTab::open(0, dir.join("sub/../a.md"), ViewMode::Rendered).unwrap();
+        assert!(tab.is_file(&file));
+        assert!(tab.is_file(&dir.join("./a.md")));
+        assert!(!tab.is_file(&dir.join("b.md")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // Pestaña con el documento ya distribuido: cada encabezado en la altura dada
+    fn laid_out(source: &str, heading_offsets: Vec<f32>) -> DocumentTab {
+        let mut tab = DocumentTab::new(0, ViewMode::Rendered);
+        tab.document = Document::parse(source);
+        tab.heading_offsets = heading_offsets;
+        tab.viewport_height = 500.0;
+        tab.content_height = 5000.0;
+        tab
+    }
+
+    #[test]
+    fn position_follows_a_moved_heading() {
+        let mut tab = laid_out("# Uno\n\n# Dos\n", vec![0.0, 200.0]);
+        tab.scroll_offset = 230.0;
+        tab.active_heading = Some(1);
+        let position = tab.current_position().unwrap();
+        assert_eq!(position, ScrollPosition { heading: Some("dos".to_string()), offset: 30.0 });
+
+        // Se agregó texto antes del encabezado: la posición se mueve con él
+        let tab = laid_out("# Uno\n\nMás texto.\n\n# Dos\n", vec![0.0, 500.0]);
+        assert_eq!(tab.resolve_position(&position), 530.0);
+    }
+
+    #[test]
+    fn position_falls_back_to_the_offset_without_its_heading() {
+        let position = ScrollPosition { heading: Some("dos".to_string()), offset: 30.0 };
+        let tab = laid_out("# Uno\n\n# Tres\n", vec![0.0, 500.0]);
+        assert_eq!(tab.resolve_position(&position), 30.0);
+
+        // Nunca más allá del final del documento
+        let position = ScrollPosition { heading: None, offset: 9000.0 };
+        assert_eq!(tab.resolve_position(&position), tab.max_scroll());
+    }
+}
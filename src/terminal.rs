@@ -0,0 +1,341 @@
+use crate::convert::Input;
+use crate::document::Document;
+use crate::pager::{PagerCommand, PagerKeys};
+use crate::text::{self, Line, Role};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, ContentStyle, PrintStyledContent, StyledContent};
+use crossterm::{cursor, queue, terminal};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::Path;
+
+/// Ancho cuando la salida no es una terminal.
+const DEFAULT_WIDTH: usize = 80;
+
+/// `mdview cat`: escribe el documento en la terminal con colores y estilos,
+/// ajustado a su ancho. Si la salida no es una terminal, escribe texto plano.
+pub fn cat(input: &Path, width: Option<usize>) -> io::Result<()> {
+    let input = Input::read(input)?;
+    let document = Document::parse(&input.source);
+    let ansi = io::stdout().is_terminal();
+    let width = width.unwrap_or_else(|| if ansi { terminal_width() } else { DEFAULT_WIDTH });
+
+    let mut output = BufWriter::new(io::stdout().lock());
+    let result = text::layout(&document, width).iter().try_for_each(|line| {
+        if ansi {
+            write_line(&mut output, line, &[])?;
+        } else {
+            write!(output, "{}", line.text())?;
+        }
+        writeln!(output)
+    });
+    match result.and_then(|_| output.flush()) {
+        // Quien leía la salida (por ejemplo `head`) ya no la quiere
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+/// `mdview --tui`: muestra el documento en un paginador con desplazamiento y
+/// búsqueda. Si la salida no es una terminal, hace lo mismo que `cat`.
+pub fn page(input: &Path) -> io::Result<()> {
+    if !io::stdout().is_terminal() {
+        return cat(input, None);
+    }
+    let input = Input::read(input)?;
+    let (width, height) = terminal::size()?;
+    let mut pager = Pager {
+        document: Document::parse(&input.source),
+        title: input.title,
+        lines: Vec::new(),
+        width: width as usize,
+        height: height as usize,
+        top: 0,
+        query: None,
+        prompt: None,
+        message: None,
+        keys: PagerKeys::default(),
+    };
+    pager.lines = text::layout(&pager.document, pager.width);
+
+    let _screen = AlternateScreen::enter()?;
+    pager.run()
+}
+
+/// Pantalla alternativa en modo crudo, que se restaura al salir aunque haya
+/// un error.
+struct AlternateScreen;
+
+impl AlternateScreen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        queue!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for AlternateScreen {
+    fn drop(&mut self) {
+        let _ = queue!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = io::stdout().flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+struct Pager {
+    document: Document,
+    title: String,
+    lines: Vec<Line>,
+    width: usize,
+    height: usize,
+    /// Primera línea visible.
+    top: usize,
+    /// Última búsqueda, para `n` y `N`.
+    query: Option<String>,
+    /// Texto de la búsqueda que se está escribiendo.
+    prompt: Option<String>,
+    /// Aviso en la línea de estado, hasta la próxima tecla.
+    message: Option<String>,
+    keys: PagerKeys,
+}
+
+impl Pager {
+    fn run(&mut self) -> io::Result<()> {
+        loop {
+            self.draw()?;
+            match event::read()? {
+                Event::Resize(width, height) => self.resize(width as usize, height as usize),
+                Event::Key(key) if key.kind != KeyEventKind::Release => {
+                    self.message = None;
+                    if self.prompt.is_some() {
+                        self.prompt_key(key);
+                    } else if !self.key(key) {
+                        return Ok(());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Líneas de texto visibles; la última fila es la de estado.
+    fn page_height(&self) -> usize {
+        self.height.saturating_sub(1).max(1)
+    }
+
+    fn max_top(&self) -> usize {
+        self.lines.len().saturating_sub(self.page_height())
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        // Mantiene a la vista la misma parte del documento
+        let progress = self.top as f32 / self.lines.len().max(1) as f32;
+        self.width = width;
+        self.height = height;
+        self.lines = text::layout(&self.document, width);
+        self.top = ((progress * self.lines.len() as f32) as usize).min(self.max_top());
+    }
+
+    /// Atiende una tecla. Devuelve `false` para salir.
+    fn key(&mut self, key: KeyEvent) -> bool {
+        let command = match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Down | KeyCode::Enter => PagerCommand::LineDown,
+            KeyCode::Up => PagerCommand::LineUp,
+            KeyCode::PageDown => PagerCommand::PageDown,
+            KeyCode::PageUp => PagerCommand::PageUp,
+            KeyCode::Home => PagerCommand::Top,
+            KeyCode::End => PagerCommand::Bottom,
+            KeyCode::Char(c) => {
+                let mut commands = Vec::new();
+                self.keys.feed(c, &mut commands);
+                for command in commands {
+                    self.command(command);
+                }
+                return true;
+            }
+            _ => return true,
+        };
+        self.command(command);
+        true
+    }
+
+    fn command(&mut self, command: PagerCommand) {
+        let page = self.page_height();
+        self.top = match command {
+            PagerCommand::LineDown => self.top + 1,
+            PagerCommand::LineUp => self.top.saturating_sub(1),
+            PagerCommand::HalfPageDown => self.top + page / 2,
+            PagerCommand::HalfPageUp => self.top.saturating_sub(page / 2),
+            PagerCommand::PageDown => self.top + page,
+            PagerCommand::PageUp => self.top.saturating_sub(page),
+            PagerCommand::Top => 0,
+            PagerCommand::Bottom => self.max_top(),
+            PagerCommand::NextHeading => {
+                (self.top + 1..self.lines.len()).find(|index| self.lines[*index].heading).unwrap_or(self.top)
+            }
+            PagerCommand::PreviousHeading => {
+                (0..self.top).rev().find(|index| self.lines[*index].heading).unwrap_or(self.top)
+            }
+            PagerCommand::Search => {
+                self.prompt = Some(String::new());
+                self.top
+            }
+            PagerCommand::NextMatch => self.find((self.top + 1..self.lines.len()).collect()),
+            PagerCommand::PreviousMatch => self.find((0..self.top).rev().collect()),
+        }
+        .min(self.max_top());
+    }
+
+    fn prompt_key(&mut self, key: KeyEvent) {
+        let Some(prompt) = &mut self.prompt else {
+            return;
+        };
+        match key.code {
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => prompt.push(c),
+            KeyCode::Backspace => {
+                if prompt.pop().is_none() {
+                    self.prompt = None;
+                }
+            }
+            KeyCode::Enter => {
+                let query = self.prompt.take().unwrap_or_default();
+                if !query.is_empty() {
+                    self.query = Some(query);
+                }
+                // La búsqueda empieza en la primera línea visible
+                self.top = self.find((self.top..self.lines.len()).collect()).min(self.max_top());
+            }
+            _ => self.prompt = None,
+        }
+    }
+
+    /// Primera de `candidates` con una coincidencia de la búsqueda. Si no
+    /// hay ninguna, avisa y deja la posición como estaba.
+    fn find(&mut self, candidates: Vec<usize>) -> usize {
+        let Some(query) = &self.query else {
+            return self.top;
+        };
+        let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+        match candidates.into_iter().find(|index| !matches(&self.lines[*index].text(), &query).is_empty()) {
+            Some(index) => index,
+            None => {
+                self.message = Some(format!("Pattern not found: {}", self.query.as_deref().unwrap_or_default()));
+                self.top
+            }
+        }
+    }
+
+    fn draw(&self) -> io::Result<()> {
+        let mut output = BufWriter::new(io::stdout().lock());
+        let query: Vec<char> = self.query.iter().flat_map(|query| query.chars().flat_map(char::to_lowercase)).collect();
+        for row in 0..self.page_height() {
+            queue!(output, cursor::MoveTo(0, row as u16), terminal::Clear(terminal::ClearType::CurrentLine))?;
+            if let Some(line) = self.lines.get(self.top + row) {
+                let highlights = if query.is_empty() { Vec::new() } else { matches(&line.text(), &query) };
+                write_line(&mut output, line, &highlights)?;
+            }
+        }
+
+        let status = match (&self.prompt, &self.message) {
+            (Some(prompt), _) => format!("/{}", prompt),
+            (None, Some(message)) => message.clone(),
+            (None, None) => {
+                let last = (self.top + self.page_height()).min(self.lines.len());
+                let percent = if self.lines.is_empty() { 100 } else { last * 100 / self.lines.len() };
+                format!("{}  {}-{}/{} ({}%)  q: quit  /: search", self.title, self.top + 1, last, self.lines.len(), percent)
+            }
+        };
+        let status: String = status.chars().take(self.width).collect();
+        let mut style = ContentStyle::new();
+        style.attributes.set(Attribute::Reverse);
+        queue!(
+            output,
+            cursor::MoveTo(0, self.page_height() as u16),
+            terminal::Clear(terminal::ClearType::CurrentLine),
+            PrintStyledContent(StyledContent::new(style, status)),
+        )?;
+        output.flush()
+    }
+}
+
+/// Posiciones (en caracteres) de las coincidencias de `query`, ya en
+/// minúsculas, sin distinguir mayúsculas.
+fn matches(text: &str, query: &[char]) -> Vec<(usize, usize)> {
+    let chars: Vec<char> = text.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect();
+    let mut found = Vec::new();
+    if query.is_empty() || query.len() > chars.len() {
+        return found;
+    }
+    let mut start = 0;
+    while start + query.len() <= chars.len() {
+        if chars[start..start + query.len()] == *query {
+            found.push((start, start + query.len()));
+            start += query.len();
+        } else {
+            start += 1;
+        }
+    }
+    found
+}
+
+/// Escribe una línea con colores ANSI, invirtiendo los colores de las
+/// coincidencias de la búsqueda.
+fn write_line(output: &mut impl Write, line: &Line, highlights: &[(usize, usize)]) -> io::Result<()> {
+    let mut position = 0;
+    for segment in &line.segments {
+        let style = ansi_style(segment.style);
+        let mut run = String::new();
+        let mut run_highlighted = false;
+        for c in segment.text.chars() {
+            let highlighted = highlights.iter().any(|(start, end)| (*start..*end).contains(&position));
+            if highlighted != run_highlighted && !run.is_empty() {
+                queue!(output, PrintStyledContent(StyledContent::new(highlight(style, run_highlighted), std::mem::take(&mut run))))?;
+            }
+            run_highlighted = highlighted;
+            run.push(c);
+            position += 1;
+        }
+        if !run.is_empty() {
+            queue!(output, PrintStyledContent(StyledContent::new(highlight(style, run_highlighted), run)))?;
+        }
+    }
+    Ok(())
+}
+
+fn highlight(mut style: ContentStyle, highlighted: bool) -> ContentStyle {
+    if highlighted {
+        style.attributes.set(Attribute::Reverse);
+    }
+    style
+}
+
+fn ansi_style(style: text::TextStyle) -> ContentStyle {
+    let mut ansi = ContentStyle::new();
+    ansi.foreground_color = match style.role {
+        Role::Text => None,
+        Role::Heading(1) => Some(Color::Magenta),
+        Role::Heading(2) => Some(Color::Blue),
+        Role::Heading(_) => Some(Color::Cyan),
+        Role::Code => Some(Color::Yellow),
+        Role::Link => Some(Color::Blue),
+        Role::Markup => Some(Color::DarkGrey),
+    };
+    if style.role == Role::Link {
+        ansi.attributes.set(Attribute::Underlined);
+    }
+    if style.bold {
+        ansi.attributes.set(Attribute::Bold);
+    }
+    if style.italic {
+        ansi.attributes.set(Attribute::Italic);
+    }
+    if style.strikethrough {
+        ansi.attributes.set(Attribute::CrossedOut);
+    }
+    ansi
+}
+
+fn terminal_width() -> usize {
+    terminal::size().map_or(DEFAULT_WIDTH, |(width, _)| width as usize)
+}
@@ -0,0 +1,440 @@
+use crate::document::{Block, BlockKind, Document, ListMarker, Span};
+use pulldown_cmark::{Alignment, HeadingLevel};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Las líneas horizontales no pasan de este ancho.
+const MAX_RULE_WIDTH: usize = 80;
+
+/// Papel de un fragmento de texto, que la terminal muestra con colores.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Role {
+    #[default]
+    Text,
+    Heading(u8),
+    Code,
+    Link,
+    /// Bordes, viñetas, direcciones de enlaces y demás marcas de formato.
+    Markup,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct TextStyle {
+    pub role: Role,
+    pub bold: bool,
+    pub italic: bool,
+    pub strikethrough: bool,
+}
+
+impl TextStyle {
+    fn role(role: Role) -> Self {
+        Self { role, ..Self::default() }
+    }
+
+    fn with_span(self, span: &Span) -> Self {
+        let role = if span.style.link.is_some() {
+            Role::Link
+        } else if span.style.code {
+            Role::Code
+        } else {
+            self.role
+        };
+        Self {
+            role,
+            bold: self.bold || span.style.strong,
+            italic: self.italic || span.style.emphasis,
+            strikethrough: self.strikethrough || span.style.strikethrough,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Segment {
+    pub text: String,
+    pub style: TextStyle,
+}
+
+/// Línea de texto ya ajustada al ancho, formada por fragmentos con estilo.
+#[derive(Clone, Debug, Default)]
+pub struct Line {
+    pub segments: Vec<Segment>,
+    /// La línea es la primera de un encabezado.
+    pub heading: bool,
+}
+
+impl Line {
+    fn plain(text: impl Into<String>, style: TextStyle) -> Self {
+        let mut line = Self::default();
+        line.push(text, style);
+        line
+    }
+
+    fn push(&mut self, text: impl Into<String>, style: TextStyle) {
+        let text = text.into();
+        if text.is_empty() {
+            return;
+        }
+        match self.segments.last_mut() {
+            Some(last) if last.style == style => last.text.push_str(&text),
+            _ => self.segments.push(Segment { text, style }),
+        }
+    }
+
+    fn append(&mut self, other: Line) {
+        for segment in other.segments {
+            self.push(segment.text, segment.style);
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.segments.iter().map(|segment| segment.text.as_str()).collect()
+    }
+
+    pub fn width(&self) -> usize {
+        self.segments.iter().map(|segment| segment.text.width()).sum()
+    }
+
+    // Sin los espacios del final, que no se ven
+    fn trim_end(mut self) -> Self {
+        while let Some(last) = self.segments.last_mut() {
+            let trimmed = last.text.trim_end().len();
+            last.text.truncate(trimmed);
+            if !last.text.is_empty() {
+                break;
+            }
+            self.segments.pop();
+        }
+        self
+    }
+}
+
+/// Convierte el documento en texto plano de `width` columnas: los párrafos
+/// se ajustan al ancho, las tablas se dibujan con recuadros y los enlaces
+/// muestran su dirección entre paréntesis.
+pub fn plain_text(document: &Document, width: usize) -> String {
+    layout(document, width).iter().map(|line| format!("{}\n", line.text())).collect()
+}
+
+/// Texto plano sin partir los párrafos, para pegarlo en otra aplicación.
+pub fn unwrapped_text(document: &Document) -> String {
+    plain_text(document, usize::MAX / 2)
+}
+
+/// Distribuye el documento en líneas de `width` columnas, con el estilo de
+/// cada fragmento.
+pub fn layout(document: &Document, width: usize) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut previous: Option<&Block> = None;
+    for block in &document.blocks {
+        if let Some(previous) = previous {
+            // Los elementos de una misma lista van sin línea en blanco entre ellos
+            let list = matches!((&previous.kind, &block.kind), (BlockKind::ListItem { .. }, BlockKind::ListItem { .. }));
+            if !list {
+                lines.push(quote_prefix(previous.quote_depth.min(block.quote_depth)).trim_end());
+            }
+        }
+        let prefix = quote_prefix(block.quote_depth);
+        let width = width.saturating_sub(prefix.width()).max(1);
+        for (index, line) in block_lines(block, width).into_iter().enumerate() {
+            let mut prefixed = prefix.clone();
+            prefixed.append(line);
+            prefixed.heading = index == 0 && matches!(block.kind, BlockKind::Heading { .. });
+            lines.push(prefixed.trim_end());
+        }
+        previous = Some(block);
+    }
+    lines
+}
+
+fn quote_prefix(depth: usize) -> Line {
+    Line::plain("> ".repeat(depth), TextStyle::role(Role::Markup))
+}
+
+fn block_lines(block: &Block, width: usize) -> Vec<Line> {
+    let markup = TextStyle::role(Role::Markup);
+    match &block.kind {
+        BlockKind::Heading { level, spans } => {
+            let level_number = *level as u8;
+            let style = TextStyle { bold: true, ..TextStyle::role(Role::Heading(level_number)) };
+            match level {
+                HeadingLevel::H1 | HeadingLevel::H2 => {
+                    let mut lines = wrap(&inline_segments(spans, style), width);
+                    let underline = if *level == HeadingLevel::H1 { "=" } else { "-" };
+                    let length = lines.iter().map(Line::width).max().unwrap_or(0);
+                    lines.push(Line::plain(underline.repeat(length), markup));
+                    lines
+                }
+                _ => {
+                    let marker = Line::plain(format!("{} ", "#".repeat(level_number as usize)), markup);
+                    let text_width = width.saturating_sub(marker.width()).max(1);
+                    hanging(marker, wrap(&inline_segments(spans, style), text_width))
+                }
+            }
+        }
+        BlockKind::Paragraph { spans } => wrap(&inline_segments(spans, TextStyle::default()), width),
+        BlockKind::ListItem { marker, spans } => {
+            let marker = match marker {
+                Some(ListMarker::Bullet) => "• ".to_string(),
+                Some(ListMarker::Ordered(number)) => format!("{}. ", number),
+                Some(ListMarker::Task(true)) => "[x] ".to_string(),
+                Some(ListMarker::Task(false)) => "[ ] ".to_string(),
+                // Párrafo que sigue a otro dentro del mismo elemento
+                None => "  ".to_string(),
+            };
+            let marker = Line::plain(format!("{}{}", " ".repeat(2 * block.indent), marker), markup);
+            let text_width = width.saturating_sub(marker.width()).max(1);
+            hanging(marker, wrap(&inline_segments(spans, TextStyle::default()), text_width))
+        }
+        BlockKind::CodeBlock { code, .. } => {
+            code.trim_end_matches('\n').split('\n')
+                .map(|line| Line::plain(format!("    {}", line), TextStyle::role(Role::Code)))
+                .collect()
+        }
+        BlockKind::Table { alignments, rows } => table(alignments, rows, width),
+        BlockKind::Rule => vec![Line::plain("─".repeat(width.min(MAX_RULE_WIDTH)), markup)],
+    }
+}
+
+/// Fragmentos con su estilo, con la dirección de los enlaces externos a
+/// continuación del texto del enlace.
+fn inline_segments(spans: &[Span], base: TextStyle) -> Line {
+    let mut line = Line::default();
+    for (index, span) in spans.iter().enumerate() {
+        line.push(span.text.as_str(), base.with_span(span));
+        let Some(link) = &span.style.link else {
+            continue;
+        };
+        let link_ends = spans.get(index + 1).is_none_or(|next| next.style.link.as_ref() != Some(link));
+        let external = link.contains("://") || link.starts_with("mailto:");
+        if link_ends && external && !line.text().ends_with(link.trim_start_matches("mailto:")) {
+            line.push(format!(" ({})", link), TextStyle::role(Role::Markup));
+        }
+    }
+    line
+}
+
+/// Primera línea con `marker` delante y las siguientes alineadas debajo del texto.
+fn hanging(marker: Line, lines: Vec<Line>) -> Vec<Line> {
+    let padding = " ".repeat(marker.width());
+    lines.into_iter().enumerate()
+        .map(|(index, line)| {
+            let mut prefixed = if index == 0 { marker.clone() } else { Line::plain(padding.as_str(), TextStyle::default()) };
+            prefixed.append(line);
+            prefixed
+        })
+        .collect()
+}
+
+/// Parte el texto en líneas de hasta `width` columnas, palabra por palabra.
+/// Las palabras más largas que una línea se cortan.
+fn wrap(text: &Line, width: usize) -> Vec<Line> {
+    // Palabras con los estilos de sus partes; `None` marca un salto de línea
+    let mut words: Vec<Option<Line>> = Vec::new();
+    let mut word = Line::default();
+    for segment in &text.segments {
+        for c in segment.text.chars() {
+            if c.is_whitespace() {
+                if !word.segments.is_empty() {
+                    words.push(Some(std::mem::take(&mut word)));
+                }
+                if c == '\n' {
+                    words.push(None);
+                }
+            } else {
+                let mut buffer = [0; 4];
+                word.push(&*c.encode_utf8(&mut buffer), segment.style);
+            }
+        }
+    }
+    if !word.segments.is_empty() {
+        words.push(Some(word));
+    }
+
+    let mut lines = Vec::new();
+    let mut line = Line::default();
+    let mut line_width = 0;
+    for word in words {
+        let Some(word) = word else {
+            lines.push(std::mem::take(&mut line));
+            line_width = 0;
+            continue;
+        };
+        let word_width = word.width();
+        let separator = usize::from(line_width > 0);
+        if line_width + separator + word_width <= width {
+            if separator == 1 {
+                // El espacio entre dos palabras de un mismo enlace o estilo lo conserva
+                let before = line.segments.last().map(|segment| segment.style);
+                let after = word.segments.first().map(|segment| segment.style);
+                let style = if before == after { before.unwrap_or_default() } else { TextStyle::default() };
+                line.push(" ", style);
+            }
+            line.append(word);
+            line_width += separator + word_width;
+            continue;
+        }
+        if line_width > 0 {
+            lines.push(std::mem::take(&mut line));
+            line_width = 0;
+        }
+        for segment in word.segments {
+            for c in segment.text.chars() {
+                let char_width = c.width().unwrap_or(0);
+                if line_width + char_width > width && line_width > 0 {
+                    lines.push(std::mem::take(&mut line));
+                    line_width = 0;
+                }
+                let mut buffer = [0; 4];
+                line.push(&*c.encode_utf8(&mut buffer), segment.style);
+                line_width += char_width;
+            }
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+/// Tabla dibujada con caracteres de recuadro. Si no entra en el ancho, las
+/// celdas parten su texto en varias líneas.
+fn table(alignments: &[Alignment], rows: &[Vec<Vec<Span>>], width: usize) -> Vec<Line> {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return Vec::new();
+    }
+    let cells: Vec<Vec<Line>> = rows.iter().enumerate()
+        .map(|(row_index, row)| {
+            let base = TextStyle { bold: row_index == 0, ..TextStyle::default() };
+            (0..columns).map(|column| row.get(column).map_or_else(Line::default, |cell| inline_segments(cell, base))).collect()
+        })
+        .collect();
+
+    // Ancho natural y mínimo (la palabra más larga) de cada columna
+    let mut natural = vec![1; columns];
+    let mut minimum = vec![1; columns];
+    for row in &cells {
+        for (column, cell) in row.iter().enumerate() {
+            let text = cell.text();
+            natural[column] = natural[column].max(text.width());
+            minimum[column] = minimum[column].max(text.split_whitespace().map(UnicodeWidthStr::width).max().unwrap_or(0));
+        }
+    }
+    // Cada columna ocupa tres columnas más por los bordes y el espacio a cada lado
+    let available = width.saturating_sub(3 * columns + 1).max(columns);
+    let widths = column_widths(&natural, &minimum, available);
+
+    let markup = TextStyle::role(Role::Markup);
+    let border = |left: &str, middle: &str, right: &str| {
+        let segments: Vec<String> = widths.iter().map(|width| "─".repeat(width + 2)).collect();
+        Line::plain(format!("{}{}{}", left, segments.join(middle), right), markup)
+    };
+    let mut lines = vec![border("┌", "┬", "┐")];
+    for (row_index, row) in cells.iter().enumerate() {
+        let wrapped: Vec<Vec<Line>> = row.iter().zip(&widths).map(|(cell, width)| wrap(cell, *width)).collect();
+        let height = wrapped.iter().map(Vec::len).max().unwrap_or(1);
+        for line_index in 0..height {
+            let mut line = Line::plain("│", markup);
+            for (column, cell) in wrapped.iter().enumerate() {
+                let text = cell.get(line_index).cloned().unwrap_or_default();
+                let space = widths[column].saturating_sub(text.width());
+                let (left, right) = match alignments.get(column) {
+                    Some(Alignment::Center) => (space / 2, space - space / 2),
+                    Some(Alignment::Right) => (space, 0),
+                    _ => (0, space),
+                };
+                line.push(" ".repeat(left + 1), TextStyle::default());
+                line.append(text);
+                line.push(" ".repeat(right + 1), TextStyle::default());
+                line.push("│", markup);
+            }
+            lines.push(line);
+        }
+        if row_index == 0 && cells.len() > 1 {
+            lines.push(border("├", "┼", "┤"));
+        }
+    }
+    lines.push(border("└", "┴", "┘"));
+    lines
+}
+
+/// Reparte el ancho entre las columnas: cada una recibe su ancho natural si
+/// entra todo; si no, su mínimo más una parte de lo que sobra.
+fn column_widths(natural: &[usize], minimum: &[usize], width: usize) -> Vec<usize> {
+    if natural.iter().sum::<usize>() <= width {
+        return natural.to_vec();
+    }
+    let minimum: Vec<usize> = minimum.iter().zip(natural).map(|(minimum, natural)| (*minimum).min(*natural)).collect();
+    let minimum_total: usize = minimum.iter().sum();
+    if minimum_total >= width {
+        return minimum.iter().map(|minimum| (minimum * width / minimum_total).max(1)).collect();
+    }
+    let extra = width - minimum_total;
+    let wanted: usize = natural.iter().zip(&minimum).map(|(natural, minimum)| natural - minimum).sum();
+    natural.iter().zip(&minimum)
+        .map(|(natural, minimum)| minimum + extra * (natural - minimum) / wanted.max(1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wrapped(text: &str, width: usize) -> Vec<String> {
+        wrap(&Line::plain(text, TextStyle::default()), width).iter().map(Line::text).collect()
+    }
+
+    #[test]
+    fn wrap_by_words() {
+        assert_eq!(wrapped("the quick brown fox jumps", 10), ["the quick", "brown fox", "jumps"]);
+        assert_eq!(wrapped("  spaced   out  ", 20), ["spaced out"]);
+        assert_eq!(wrapped("first\nsecond", 20), ["first", "second"]);
+    }
+
+    #[test]
+    fn wrap_cuts_long_words() {
+        assert_eq!(wrapped("a abcdefghij", 4), ["a", "abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn wrap_counts_wide_characters() {
+        assert_eq!(wrapped("日本語テキスト", 5), ["日本", "語テ", "キス", "ト"]);
+    }
+
+    #[test]
+    fn wrap_keeps_styles() {
+        let mut line = Line::plain("plain ", TextStyle::default());
+        line.push("bold words", TextStyle { bold: true, ..TextStyle::default() });
+        let lines = wrap(&line, 11);
+        assert_eq!(lines.iter().map(Line::text).collect::<Vec<_>>(), ["plain bold", "words"]);
+        assert!(!lines[0].segments[0].style.bold);
+        assert!(lines[0].segments[1].style.bold);
+        assert!(lines[1].segments[0].style.bold);
+    }
+
+    #[test]
+    fn table_with_alignments() {
+        let document = Document::parse("| Name | Qty |\n|:-----|----:|\n| apple | 3 |\n| kiwi | 12 |\n");
+        assert_eq!(
+            plain_text(&document, 80),
+            "┌───────┬─────┐\n\
+             │ Name  │ Qty │\n\
+             ├───────┼─────┤\n\
+             │ apple │   3 │\n\
+             │ kiwi  │  12 │\n\
+             └───────┴─────┘\n"
+        );
+    }
+
+    #[test]
+    fn narrow_table_wraps_cells() {
+        let document = Document::parse("| Word | Description |\n|---|---|\n| cat | a small domestic animal |\n");
+        assert_eq!(
+            plain_text(&document, 24),
+            "┌──────┬───────────────┐\n\
+             │ Word │ Description   │\n\
+             ├──────┼───────────────┤\n\
+             │ cat  │ a small       │\n\
+             │      │ domestic      │\n\
+             │      │ animal        │\n\
+             └──────┴───────────────┘\n"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use crate::workspace_search::SearchIndex;
+use eframe::egui;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+/// Nodo del árbol de archivos: una carpeta (con hijos) o un archivo Markdown.
+pub struct FileNode {
+    pub name: String,
+    pub path: PathBuf,
+    pub children: Vec<FileNode>,
+    pub is_dir: bool,
+}
+
+/// Carpeta abierta como espacio de trabajo, con su árbol de archivos Markdown.
+pub struct Workspace {
+    pub root: PathBuf,
+    pub tree: Vec<FileNode>,
+    /// Todos los archivos Markdown del espacio de trabajo, ordenados.
+    pub files: Vec<PathBuf>,
+    pub index: SearchIndex,
+    _watcher: Option<RecommendedWatcher>,
+    events: Receiver<notify::Event>,
+}
+
+impl Workspace {
+    /// Abre la carpeta y empieza a vigilarla. Cada cambio pide un repintado
+    /// para que `poll_changes` lo procese en el siguiente frame.
+    pub fn open(root: PathBuf, ctx: &egui::Context) -> io::Result<Self> {
+        // Canónica, como las rutas de las pestañas, para reconocer el archivo activo en el árbol
+        let root = std::fs::canonicalize(&root)?;
+        if !root.is_dir() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "not a directory"));
+        }
+
+        let (sender, events) = mpsc::channel();
+        let repaint_ctx = ctx.clone();
+        let watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+            if let Ok(event) = result {
+                if sender.send(event).is_ok() {
+                    repaint_ctx.request_repaint();
+                }
+            }
+        })
+        .and_then(|mut watcher| {
+            watcher.watch(&root, RecursiveMode::Recursive)?;
+            Ok(watcher)
+        });
+        let watcher = match watcher {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                // Sin vigilancia el árbol sigue funcionando, solo que no se actualiza solo
+                eprintln!("No se pudo vigilar la carpeta: {}", e);
+                None
+            }
+        };
+
+        let mut workspace = Self {
+            root,
+            tree: Vec::new(),
+            files: Vec::new(),
+            index: SearchIndex::new(ctx),
+            _watcher: watcher,
+            events,
+        };
+        workspace.rescan();
+        Ok(workspace)
+    }
+
+    pub fn name(&self) -> String {
+        self.root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.root.display().to_string())
+    }
+
+    pub fn rescan(&mut self) {
+        self.files = scan_markdown_files(&self.root);
+        self.tree = build_tree(&self.root, &self.files);
+        self.index.sync(&self.files);
+    }
+
+    /// Procesa los eventos pendientes del vigilante. Devuelve `true` si se
+    /// agregaron, borraron o renombraron archivos y el árbol se volvió a leer.
+    /// Los archivos modificados se vuelven a indexar.
+    pub fn poll_changes(&mut self) -> bool {
+        let mut structure_changed = false;
+        let mut modified: Vec<PathBuf> = Vec::new();
+        while let Ok(event) = self.events.try_recv() {
+            match event.kind {
+                EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(notify::event::ModifyKind::Name(_)) => {
+                    structure_changed = true;
+                }
+                EventKind::Modify(_) => {
+                    for path in event.paths {
+                        if !modified.contains(&path) {
+                            modified.push(path);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if structure_changed {
+            self.rescan();
+        }
+        for path in modified {
+            if self.files.contains(&path) {
+                self.index.update(path);
+            }
+        }
+        structure_changed
+    }
+
+    /// README de la raíz del espacio de trabajo, si existe.
+    pub fn readme(&self) -> Option<PathBuf> {
+        self.files
+            .iter()
+            .filter(|path| path.parent() == Some(self.root.as_path()))
+            .find(|path| {
+                path.file_stem()
+                    .is_some_and(|stem| stem.to_string_lossy().eq_ignore_ascii_case("readme"))
+            })
+            .cloned()
+    }
+}
+
+/// Extensiones que se abren en el visor, sin distinguir mayúsculas. La misma
+/// comprobación decide el árbol, los enlaces y los archivos soltados.
+pub fn is_markdown_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        let ext = ext.to_string_lossy().to_lowercase();
+        matches!(ext.as_str(), "md" | "markdown" | "txt")
+    })
+}
+
+/// Lista los archivos Markdown bajo `root` respetando `.gitignore`, `.ignore`
+/// y los archivos ocultos, aunque la carpeta no sea un repositorio git.
+pub fn scan_markdown_files(root: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = ignore::WalkBuilder::new(root)
+        .require_git(false)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|path| is_markdown_path(path))
+        .collect();
+    files.sort_by_key(|path| path.to_string_lossy().to_lowercase());
+    files
+}
+
+fn build_tree(root: &Path, files: &[PathBuf]) -> Vec<FileNode> {
+    let mut tree: Vec<FileNode> = Vec::new();
+
+    for file in files {
+        let Ok(relative) = file.strip_prefix(root) else {
+            continue;
+        };
+        let components: Vec<_> = relative.components().collect();
+        let mut level = &mut tree;
+        let mut current = root.to_path_buf();
+
+        for (index, component) in components.iter().enumerate() {
+            current.push(component);
+            let is_dir = index + 1 < components.len();
+            let position = match level.iter().position(|node| node.path == current) {
+                Some(position) => position,
+                None => {
+                    level.push(FileNode {
+                        name: component.as_os_str().to_string_lossy().into_owned(),
+                        path: current.clone(),
+                        children: Vec::new(),
+                        is_dir,
+                    });
+                    level.len() - 1
+                }
+            };
+            level = &mut level[position].children;
+        }
+    }
+
+    sort_tree(&mut tree);
+    tree
+}
+
+// Carpetas primero, después archivos, ambos por nombre
+fn sort_tree(nodes: &mut [FileNode]) {
+    nodes.sort_by_key(|node| (!node.is_dir, node.name.to_lowercase()));
+    for node in nodes {
+        sort_tree(&mut node.children);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_extensions_ignore_case() {
+        for name in ["README.md", "README.MD", "notes.Markdown", "todo.txt"] {
+            assert!(is_markdown_path(Path::new(name)), "{name}");
+        }
+        for name in ["image.png", "md", "archive.md.zip"] {
+            assert!(!is_markdown_path(Path::new(name)), "{name}");
+        }
+    }
+}
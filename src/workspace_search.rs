@@ -0,0 +1,314 @@
+use crate::encoding;
+use crate::search::{Matcher, SearchOptions};
+use eframe::egui;
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// Límites para que una búsqueda muy común no genere miles de filas
+const MAX_RESULTS: usize = 2000;
+const MAX_SNIPPET_CHARS: usize = 160;
+const SNIPPET_CONTEXT_CHARS: usize = 40;
+
+enum IndexCommand {
+    /// Indexa los archivos nuevos y olvida los que ya no están en la lista.
+    Sync(Vec<PathBuf>),
+    /// Vuelve a leer un archivo modificado.
+    Update(PathBuf),
+}
+
+/// Índice de texto completo de un espacio de trabajo. Los archivos se leen en
+/// un hilo aparte; la búsqueda se hace sobre el contenido ya decodificado.
+pub struct SearchIndex {
+    contents: Arc<Mutex<HashMap<PathBuf, String>>>,
+    commands: Sender<IndexCommand>,
+    pending: Arc<AtomicUsize>,
+    /// Aumenta cada vez que cambia el contenido indexado.
+    generation: Arc<AtomicU64>,
+}
+
+impl SearchIndex {
+    pub fn new(ctx: &egui::Context) -> Self {
+        let contents = Arc::new(Mutex::new(HashMap::new()));
+        let pending = Arc::new(AtomicUsize::new(0));
+        let generation = Arc::new(AtomicU64::new(0));
+        let (commands, receiver) = mpsc::channel();
+
+        let worker_contents = contents.clone();
+        let worker_pending = pending.clone();
+        let worker_generation = generation.clone();
+        let repaint_ctx = ctx.clone();
+        // El hilo termina solo cuando se descarta el índice (se cierra el canal)
+        thread::spawn(move || {
+            for command in receiver {
+                match command {
+                    IndexCommand::Sync(files) => {
+                        let known: Vec<PathBuf> = worker_contents.lock().unwrap().keys().cloned().collect();
+                        worker_contents.lock().unwrap().retain(|path, _| files.contains(path));
+                        for path in files.into_iter().filter(|path| !known.contains(path)) {
+                            index_file(&worker_contents, path);
+                        }
+                    }
+                    IndexCommand::Update(path) => index_file(&worker_contents, path),
+                }
+                worker_pending.fetch_sub(1, Ordering::SeqCst);
+                worker_generation.fetch_add(1, Ordering::SeqCst);
+                repaint_ctx.request_repaint();
+            }
+        });
+
+        Self {
+            contents,
+            commands,
+            pending,
+            generation,
+        }
+    }
+
+    pub fn sync(&self, files: &[PathBuf]) {
+        self.send(IndexCommand::Sync(files.to_vec()));
+    }
+
+    pub fn update(&self, path: PathBuf) {
+        self.send(IndexCommand::Update(path));
+    }
+
+    fn send(&self, command: IndexCommand) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        if self.commands.send(command).is_err() {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    pub fn is_indexing(&self) -> bool {
+        self.pending.load(Ordering::SeqCst) > 0
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Busca en todos los archivos indexados. Los resultados se agrupan por
+    /// archivo, en el orden de `files`.
+    pub fn search(&self, matcher: &Matcher, files: &[PathBuf]) -> Vec<FileResults> {
+        let contents = self.contents.lock().unwrap();
+        let mut results = Vec::new();
+        let mut total = 0;
+
+        for path in files {
+            if total >= MAX_RESULTS {
+                break;
+            }
+            let Some(text) = contents.get(path) else {
+                continue;
+            };
+            let lines = search_lines(text, matcher, MAX_RESULTS - total);
+            if !lines.is_empty() {
+                total += lines.len();
+                results.push(FileResults { path: path.clone(), lines });
+            }
+        }
+        results
+    }
+}
+
+fn index_file(contents: &Mutex<HashMap<PathBuf, String>>, path: PathBuf) {
+    match fs::read(&path) {
+        Ok(bytes) => {
+            let text = encoding::decode(&bytes).text;
+            contents.lock().unwrap().insert(path, text);
+        }
+        Err(_) => {
+            // Borrado o movido entre el evento y la lectura
+            contents.lock().unwrap().remove(&path);
+        }
+    }
+}
+
+/// Coincidencias de un archivo.
+pub struct FileResults {
+    pub path: PathBuf,
+    pub lines: Vec<LineMatch>,
+}
+
+/// Línea con al menos una coincidencia.
+pub struct LineMatch {
+    /// Número de línea, empezando en 1.
+    pub line_number: usize,
+    /// Posición en bytes de la primera coincidencia dentro del archivo.
+    pub offset: usize,
+    /// Fragmento de la línea a mostrar.
+    pub snippet: String,
+    /// Coincidencias dentro de `snippet`, en bytes.
+    pub ranges: Vec<Range<usize>>,
+    /// Coincidencias en toda la línea, aunque no todas quepan en `snippet`.
+    pub matches: usize,
+}
+
+fn search_lines(text: &str, matcher: &Matcher, limit: usize) -> Vec<LineMatch> {
+    let mut lines = Vec::new();
+    let mut line_start = 0;
+
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        if lines.len() >= limit {
+            break;
+        }
+        let content = line.trim_end_matches(['\n', '\r']);
+        let ranges: Vec<Range<usize>> = matcher.find_iter(content).collect();
+        if let Some(first) = ranges.first() {
+            let matches = ranges.len();
+            let (snippet, ranges) = snippet(content, &ranges);
+            lines.push(LineMatch {
+                line_number: index + 1,
+                offset: line_start + first.start,
+                snippet,
+                ranges,
+                matches,
+            });
+        }
+        line_start += line.len();
+    }
+    lines
+}
+
+// Recorta la línea alrededor de la primera coincidencia y ajusta los rangos
+fn snippet(line: &str, ranges: &[Range<usize>]) -> (String, Vec<Range<usize>>) {
+    let first = ranges[0].start;
+    let context_start = line[..first]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT_CHARS - 1)
+        .map_or(0, |(index, _)| index);
+    let leading = (line[context_start..].len() - line[context_start..].trim_start().len()).min(first - context_start);
+    let start = context_start + leading;
+    let end = line[start..]
+        .char_indices()
+        .nth(MAX_SNIPPET_CHARS)
+        .map_or(line.len(), |(index, _)| start + index)
+        .max(ranges[0].end);
+
+    let prefix = if context_start > 0 { "…" } else { "" };
+    let suffix = if end < line.len() { "…" } else { "" };
+    let shift = prefix.len() as isize - start as isize;
+    let ranges = ranges
+        .iter()
+        .filter(|range| range.start >= start && range.end <= end)
+        .map(|range| {
+            let start = (range.start as isize + shift) as usize;
+            let end = (range.end as isize + shift) as usize;
+            start..end
+        })
+        .collect();
+    (format!("{}{}{}", prefix, &line[start..end], suffix), ranges)
+}
+
+/// Estado del panel de búsqueda en el espacio de trabajo.
+#[derive(Default)]
+pub struct WorkspaceSearch {
+    pub query: String,
+    pub options: SearchOptions,
+    pub invalid_regex: bool,
+    pub results: Vec<FileResults>,
+    pub request_focus: bool,
+    computed_for: Option<(String, SearchOptions, u64)>,
+}
+
+impl WorkspaceSearch {
+    /// Repite la búsqueda si cambió la consulta o el contenido del índice.
+    pub fn refresh(&mut self, index: &SearchIndex, files: &[PathBuf]) {
+        let key = (self.query.clone(), self.options.clone(), index.generation());
+        if self.computed_for.as_ref() == Some(&key) {
+            return;
+        }
+
+        self.results.clear();
+        self.invalid_regex = false;
+        if !self.query.is_empty() {
+            match Matcher::new(&self.query, &self.options) {
+                Ok(matcher) => self.results = index.search(&matcher, files),
+                Err(_) => self.invalid_regex = true,
+            }
+        }
+        self.computed_for = Some(key);
+    }
+
+    pub fn match_count(&self) -> usize {
+        self.results.iter().flat_map(|file| &file.lines).map(|line| line.matches).sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.results.clear();
+        self.computed_for = None;
+    }
+}
+
+/// Ruta relativa a la raíz del espacio de trabajo, para mostrar en los resultados.
+pub fn display_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root).unwrap_or(path).display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(text: &str, query: &str) -> Vec<LineMatch> {
+        let matcher = Matcher::new(query, &SearchOptions::default()).unwrap();
+        search_lines(text, &matcher, MAX_RESULTS)
+    }
+
+    fn highlighted(line: &LineMatch) -> Vec<&str> {
+        line.ranges.iter().map(|range| &line.snippet[range.clone()]).collect()
+    }
+
+    #[test]
+    fn multibyte_characters_next_to_the_match() {
+        let lines = search("uno\ncañón€dos ñandú\n", "ñ");
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].line_number, 2);
+        assert_eq!(lines[0].offset, "uno\nca".len());
+        assert_eq!(lines[0].snippet, "cañón€dos ñandú");
+        assert_eq!(highlighted(&lines[0]), vec!["ñ", "ñ"]);
+        assert_eq!(lines[0].matches, 2);
+    }
+
+    #[test]
+    fn matches_at_the_start_and_end_of_a_line() {
+        let lines = search("fin y más fin\r\nfin", "fin");
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].snippet, "fin y más fin");
+        assert_eq!(lines[0].ranges, vec![0..3, 11..14]);
+        assert_eq!(lines[1].offset, "fin y más fin\r\n".len());
+        assert_eq!(highlighted(&lines[1]), vec!["fin"]);
+    }
+
+    #[test]
+    fn long_lines_are_cut_around_the_first_match() {
+        let line = format!("{}clave{}clave", "é".repeat(100), "ü".repeat(200));
+        let lines = search(&line, "clave");
+        let snippet = &lines[0].snippet;
+        let expected = format!("…{}clave{}…", "é".repeat(SNIPPET_CONTEXT_CHARS), "ü".repeat(MAX_SNIPPET_CHARS - SNIPPET_CONTEXT_CHARS - 5));
+        assert_eq!(snippet, &expected);
+        assert_eq!(highlighted(&lines[0]), vec!["clave"]);
+        assert_eq!(lines[0].matches, 2);
+    }
+
+    #[test]
+    fn long_first_match_is_kept_whole() {
+        let query = "a".repeat(MAX_SNIPPET_CHARS);
+        let lines = search(&format!("{} {} fin", "b".repeat(50), query), &query);
+        assert!(lines[0].snippet.ends_with(&format!("{query}…")));
+        assert_eq!(highlighted(&lines[0]), vec![query.as_str()]);
+    }
+
+    #[test]
+    fn leading_indentation_is_dropped() {
+        let lines = search("        - elemento", "elemento");
+        assert_eq!(lines[0].snippet, "- elemento");
+        assert_eq!(highlighted(&lines[0]), vec!["elemento"]);
+    }
+}